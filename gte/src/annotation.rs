@@ -0,0 +1,314 @@
+/*! Format-independent reading and writing of annotations.
+
+The readers and writers of all annotation formats implement the `AnnotationReader` and
+`AnnotationWriter` traits, so that code can work with annotations without knowing their format.
+The `open_annotation` function creates a reader of an annotation file, whose format is picked
+from the file extension or, failing that, detected from the first data line of the file.
+
+Since BED files do not group transcripts into genes, the genes read from BED files each contain
+a single transcript.
+*/
+use std::convert::AsRef;
+use std::io::{self, BufRead, Read};
+use std::path::Path;
+use std::str::FromStr;
+
+use linked_hash_map::LinkedHashMap;
+
+use {BedReader, BedWriter, GBuilder, Gene, GffReader, GffType, GffWriter, GzipReader,
+     RefFlatLayout, RefFlatReader, RefFlatWriter, SafWriter, Transcript, DEF_ID};
+
+
+/// Extensions of compressed files, which are removed before the format extension is looked up.
+const COMPRESSED_EXTS: [&'static str; 2] = ["gz", "bgz"];
+
+/// Maximum number of bytes read when looking for the first data line of a file.
+const MAX_SNIFF_LEN: usize = 1 << 20;
+
+/// Iterator over transcripts returned by annotation readers.
+pub type AnnotationTranscripts<'a> = Box<Iterator<Item=::Result<Transcript>> + 'a>;
+
+/// Iterator over genes returned by annotation readers.
+pub type AnnotationGenes<'a> = Box<Iterator<Item=::Result<Gene>> + 'a>;
+
+/// Trait for readers of transcripts and genes from annotation files of any format.
+pub trait AnnotationReader {
+
+    /// Creates an iterator of transcripts.
+    fn transcripts<'a>(&'a mut self) -> ::Result<AnnotationTranscripts<'a>>;
+
+    /// Creates an iterator of genes.
+    fn genes<'a>(&'a mut self) -> ::Result<AnnotationGenes<'a>>;
+}
+
+/// Trait for writers of transcripts and genes to annotation files of any format.
+pub trait AnnotationWriter {
+
+    /// Writes a single transcript.
+    fn write_transcript(&mut self, transcript: &Transcript) -> ::Result<()>;
+
+    /// Writes a single gene.
+    fn write_gene(&mut self, gene: &Gene) -> ::Result<()>;
+}
+
+impl<R: Read> AnnotationReader for RefFlatReader<R> {
+
+    fn transcripts<'a>(&'a mut self) -> ::Result<AnnotationTranscripts<'a>> {
+        Ok(Box::new(self.transcripts_stream()))
+    }
+
+    fn genes<'a>(&'a mut self) -> ::Result<AnnotationGenes<'a>> {
+        Ok(Box::new(self.genes_stream()))
+    }
+}
+
+impl<R: Read> AnnotationReader for GffReader<R> {
+
+    fn transcripts<'a>(&'a mut self) -> ::Result<AnnotationTranscripts<'a>> {
+        let transcripts = GffReader::transcripts(self)?;
+        Ok(Box::new(transcripts))
+    }
+
+    fn genes<'a>(&'a mut self) -> ::Result<AnnotationGenes<'a>> {
+        let genes = GffReader::genes(self)?;
+        Ok(Box::new(genes))
+    }
+}
+
+impl<R: Read> AnnotationReader for BedReader<R> {
+
+    fn transcripts<'a>(&'a mut self) -> ::Result<AnnotationTranscripts<'a>> {
+        Ok(Box::new(self.transcripts_stream()))
+    }
+
+    fn genes<'a>(&'a mut self) -> ::Result<AnnotationGenes<'a>> {
+        Ok(Box::new(self.transcripts_stream().map(|result| result.and_then(single_gene))))
+    }
+}
+
+/// Helper function for creating a gene that contains only the given transcript.
+///
+/// The gene takes the gene identifier of the transcript if present, or its transcript identifier
+/// otherwise.
+fn single_gene(transcript: Transcript) -> ::Result<Gene> {
+    let tid = transcript.id().unwrap_or(DEF_ID).to_owned();
+    let gid = transcript.gene_id().unwrap_or(&tid).to_owned();
+    let builder = GBuilder::new(transcript.seq_name(), transcript.start(), transcript.end())
+        .id(gid)
+        .strand(*transcript.strand());
+    let mut transcripts = LinkedHashMap::new();
+    let _ = transcripts.insert(tid, transcript);
+    builder.transcripts(transcripts).build()
+}
+
+impl<W: io::Write> AnnotationWriter for RefFlatWriter<W> {
+
+    fn write_transcript(&mut self, transcript: &Transcript) -> ::Result<()> {
+        RefFlatWriter::write_transcript(self, transcript)
+    }
+
+    fn write_gene(&mut self, gene: &Gene) -> ::Result<()> {
+        RefFlatWriter::write_gene(self, gene)
+    }
+}
+
+impl<W: io::Write> AnnotationWriter for GffWriter<W> {
+
+    fn write_transcript(&mut self, transcript: &Transcript) -> ::Result<()> {
+        GffWriter::write_transcript(self, transcript)
+    }
+
+    fn write_gene(&mut self, gene: &Gene) -> ::Result<()> {
+        GffWriter::write_gene(self, gene)
+    }
+}
+
+impl<W: io::Write> AnnotationWriter for BedWriter<W> {
+
+    fn write_transcript(&mut self, transcript: &Transcript) -> ::Result<()> {
+        BedWriter::write_transcript(self, transcript)
+    }
+
+    fn write_gene(&mut self, gene: &Gene) -> ::Result<()> {
+        BedWriter::write_gene(self, gene)
+    }
+}
+
+impl<W: io::Write> AnnotationWriter for SafWriter<W> {
+
+    fn write_transcript(&mut self, transcript: &Transcript) -> ::Result<()> {
+        SafWriter::write_transcript(self, transcript)
+    }
+
+    fn write_gene(&mut self, gene: &Gene) -> ::Result<()> {
+        SafWriter::write_gene(self, gene)
+    }
+}
+
+/// Annotation file formats.
+#[derive(Debug, Clone, Copy)]
+pub enum AnnotationFormat {
+    /// refFlat or other genePred layout, optionally with a leading `bin` column.
+    RefFlat {
+        /// Column layout of the rows.
+        layout: RefFlatLayout,
+        /// Whether the rows start with a `bin` column.
+        has_bin: bool,
+    },
+    /// GFF3 or GTF.
+    Gff(GffType),
+    /// BED.
+    Bed,
+}
+
+impl AnnotationFormat {
+
+    /// Returns the format denoted by the extension of the given path, if any.
+    ///
+    /// The `.gz` and `.bgz` extensions of compressed files are skipped. The recognized
+    /// extensions are `.gtf`, `.gff`, `.gff3`, `.bed`, and `.refflat`, regardless of case.
+    /// Other genePred files do not have a distinct extension, so their layouts can only be
+    /// detected from their content.
+    pub fn from_extension<P: AsRef<Path>>(path: P) -> Option<AnnotationFormat> {
+        let path = path.as_ref();
+        let mut ext = path.extension().and_then(|ext| ext.to_str());
+        if ext.map_or(false, |ext| COMPRESSED_EXTS.contains(&ext.to_lowercase().as_str())) {
+            ext = path.file_stem()
+                .and_then(|stem| Path::new(stem).extension())
+                .and_then(|ext| ext.to_str());
+        }
+        match ext.map(|ext| ext.to_lowercase()) {
+            Some(ref ext) if ext == "gtf" => Some(AnnotationFormat::Gff(GffType::GTF2)),
+            Some(ref ext) if ext == "gff" || ext == "gff3" =>
+                Some(AnnotationFormat::Gff(GffType::GFF3)),
+            Some(ref ext) if ext == "bed" => Some(AnnotationFormat::Bed),
+            Some(ref ext) if ext == "refflat" =>
+                Some(AnnotationFormat::RefFlat { layout: RefFlatLayout::RefFlat, has_bin: false }),
+            _ => None,
+        }
+    }
+
+    /// Detects the format of the given annotation content, if possible.
+    ///
+    /// The format is detected from the first data line, which is the first line that is not
+    /// empty, not a comment, and not a BED track or browser line. A `##gff-version` directive
+    /// before that line takes precedence over the GFF variant guessed from the attributes.
+    pub fn sniff(content: &str) -> Option<AnnotationFormat> {
+        let mut gff_version = None;
+        for line in content.lines() {
+            if line.starts_with("##gff-version") {
+                gff_version = line.split_whitespace().nth(1)
+                    .map(|ver| if ver.starts_with('3') { GffType::GFF3 } else { GffType::GTF2 });
+            }
+            if is_data_line(line) {
+                return sniff_line(line, gff_version);
+            }
+        }
+        None
+    }
+
+    /// Creates a reader of the format from another reader.
+    pub fn reader<R>(&self, in_reader: R) -> Box<AnnotationReader>
+        where R: Read + 'static
+    {
+        match *self {
+            AnnotationFormat::RefFlat { layout, has_bin } => {
+                let mut reader = RefFlatReader::from_reader(in_reader);
+                let _ = reader.layout(layout).has_bin(has_bin);
+                Box::new(reader)
+            },
+            AnnotationFormat::Gff(gff_type) =>
+                Box::new(GffReader::from_reader(in_reader, gff_type)),
+            AnnotationFormat::Bed => Box::new(BedReader::from_reader(in_reader)),
+        }
+    }
+}
+
+/// Opens an annotation file for reading, regardless of its format.
+///
+/// The format is picked from the extension of the path, or detected from the content of the
+/// file when the extension is not recognized. Gzip and BGZF-compressed files are decompressed
+/// transparently. An error of kind `InvalidData` is returned if the format can not be detected.
+pub fn open_annotation<P: AsRef<Path>>(path: P) -> io::Result<Box<AnnotationReader>> {
+    let path = path.as_ref();
+    let mut input = io::BufReader::new(GzipReader::from_file(path)?);
+    let mut head = Vec::new();
+
+    let format = match AnnotationFormat::from_extension(path) {
+        Some(format) => Some(format),
+        None => {
+            read_head(&mut input, &mut head)?;
+            AnnotationFormat::sniff(&String::from_utf8_lossy(&head))
+        },
+    };
+    let format = format
+        .ok_or_else(|| io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("could not detect the annotation format of '{}'", path.display())))?;
+
+    Ok(format.reader(io::Cursor::new(head).chain(input)))
+}
+
+/// Helper function for reading the lines of the input up to and including its first data line.
+///
+/// Reading stops early once `MAX_SNIFF_LEN` bytes have been read.
+fn read_head<R: BufRead>(input: &mut R, head: &mut Vec<u8>) -> io::Result<()> {
+    while head.len() < MAX_SNIFF_LEN {
+        let line_start = head.len();
+        if input.read_until(b'\n', head)? == 0 {
+            break;
+        }
+        if is_data_line(&String::from_utf8_lossy(&head[line_start..])) {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Helper function for checking whether a line may contain an annotation record.
+fn is_data_line(line: &str) -> bool {
+    let line = line.trim();
+    !(line.is_empty() || line.starts_with('#')
+      || line.starts_with("track") || line.starts_with("browser"))
+}
+
+/// Helper function for detecting the format of a data line.
+fn sniff_line(line: &str, gff_version: Option<GffType>) -> Option<AnnotationFormat> {
+    let cols = line.trim_right_matches(|c| c == '\r' || c == '\n')
+        .split('\t')
+        .collect::<Vec<&str>>();
+    let is_num = |idx: usize| cols.get(idx).map_or(false, |col| u64::from_str(col).is_ok());
+    let is_strand = |idx: usize| cols.get(idx).map_or(false, |col| *col == "+" || *col == "-");
+
+    if cols.len() == 9 && is_num(3) && is_num(4) {
+        let gff_type = gff_version.unwrap_or_else(|| {
+            // GTF attributes are space-separated key-value pairs, GFF3 attributes use '='.
+            let first_attr = cols[8].split(';').next().unwrap_or("").trim();
+            if !first_attr.contains(' ') && first_attr.contains('=') {
+                GffType::GFF3
+            } else {
+                GffType::GTF2
+            }
+        });
+        return Some(AnnotationFormat::Gff(gff_type));
+    }
+    if cols.len() >= 3 && cols.len() <= 12 && is_num(1) && is_num(2) {
+        return Some(AnnotationFormat::Bed);
+    }
+
+    let layouts = [RefFlatLayout::RefFlat, RefFlatLayout::GenePred, RefFlatLayout::GenePredExt];
+    for &has_bin in [true, false].iter() {
+        if has_bin && !is_num(0) {
+            continue;
+        }
+        for &layout in layouts.iter() {
+            let offset = has_bin as usize + (layout == RefFlatLayout::RefFlat) as usize;
+            if cols.len() == layout.num_columns() + has_bin as usize
+                && is_strand(offset + 2) && is_num(offset + 3) && is_num(offset + 4)
+            {
+                return Some(AnnotationFormat::RefFlat { layout: layout, has_bin: has_bin });
+            }
+        }
+    }
+    None
+}
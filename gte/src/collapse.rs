@@ -0,0 +1,169 @@
+/*! Collapsed gene models for gene-level read counting.
+
+This module flattens the transcripts of a gene into non-overlapping exonic bins. Two flavors are
+provided:
+
+* The union of all transcript exons, where overlapping or adjacent exons are merged.
+* DEXSeq-style counting bins, where the union exons are further split at every exon boundary
+  so that each bin is covered by the same set of transcripts.
+
+Bins of different genes may still overlap each other. These can be flagged using
+`ExonBin::flag_shared`.
+
+All coordinates are zero-based, half-open.
+*/
+use std::collections::BTreeSet;
+
+use {Gene, Strand};
+
+
+/// A non-overlapping exonic interval of a gene.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExonBin {
+    seq_name: String,
+    start: u64,
+    end: u64,
+    strand: Strand,
+    gene_id: Option<String>,
+    transcript_ids: Vec<String>,
+    shared: bool,
+}
+
+impl ExonBin {
+
+    /// Returns the sequence name of the bin.
+    pub fn seq_name(&self) -> &str {
+        self.seq_name.as_str()
+    }
+
+    /// Returns the genome-wise 5'-most coordinate of the bin.
+    pub fn start(&self) -> u64 {
+        self.start
+    }
+
+    /// Returns the genome-wise 3'-most coordinate of the bin.
+    pub fn end(&self) -> u64 {
+        self.end
+    }
+
+    /// Returns the number of bases spanned by the bin.
+    #[inline]
+    pub fn span(&self) -> u64 {
+        self.end - self.start
+    }
+
+    /// Returns a reference of the strand of the bin.
+    pub fn strand(&self) -> &Strand {
+        &self.strand
+    }
+
+    /// Returns the identifier of the gene the bin belongs to.
+    pub fn gene_id(&self) -> Option<&str> {
+        self.gene_id.as_ref().map(|id| id.as_str())
+    }
+
+    /// Returns the sorted identifiers of the transcripts with exons covering the bin.
+    pub fn transcript_ids(&self) -> &[String] {
+        self.transcript_ids.as_slice()
+    }
+
+    /// Returns whether the bin overlaps a bin of another gene.
+    ///
+    /// This is only set by `ExonBin::flag_shared`.
+    pub fn is_shared(&self) -> bool {
+        self.shared
+    }
+
+    /// Flags the bins that overlap any bin of another gene, regardless of their strands.
+    ///
+    /// The bins are sorted by their sequence names and coordinates in the process.
+    pub fn flag_shared(bins: &mut [ExonBin]) {
+        bins.sort_by(|a, b| (&a.seq_name, a.start, a.end).cmp(&(&b.seq_name, b.start, b.end)));
+        for idx in 0..bins.len() {
+            let mut next = idx + 1;
+            while next < bins.len() && bins[next].seq_name == bins[idx].seq_name
+                && bins[next].start < bins[idx].end
+            {
+                if bins[next].gene_id != bins[idx].gene_id {
+                    bins[idx].shared = true;
+                    bins[next].shared = true;
+                }
+                next += 1;
+            }
+        }
+    }
+}
+
+impl Gene {
+
+    /// Returns the union of all the exons of the gene's transcripts.
+    ///
+    /// Overlapping and adjacent exons are merged into a single bin. The bins are ordered
+    /// genome-wise.
+    pub fn collapsed_exons(&self) -> Vec<ExonBin> {
+        let mut merged: Vec<ExonBin> = Vec::new();
+        for bin in self.counting_bins() {
+            if let Some(last) = merged.last_mut() {
+                if last.end == bin.start {
+                    last.end = bin.end;
+                    let tids = last.transcript_ids.drain(..)
+                        .chain(bin.transcript_ids.into_iter())
+                        .collect::<BTreeSet<String>>();
+                    last.transcript_ids = tids.into_iter().collect();
+                    continue;
+                }
+            }
+            merged.push(bin);
+        }
+        merged
+    }
+
+    /// Returns the DEXSeq-style counting bins of the gene.
+    ///
+    /// The union exons are split at every exon start and end coordinate, so that all bases in
+    /// each bin are covered by the same transcripts. The bins are ordered genome-wise.
+    pub fn counting_bins(&self) -> Vec<ExonBin> {
+        let exons = self.transcripts().values()
+            .flat_map(|trx| {
+                let tid = trx.id().unwrap_or(::DEF_ID);
+                trx.exons().iter().map(move |exon| (exon.start(), exon.end(), tid))
+            })
+            .collect::<Vec<(u64, u64, &str)>>();
+        let boundaries = exons.iter()
+            .flat_map(|&(start, end, _)| vec![start, end])
+            .collect::<BTreeSet<u64>>()
+            .into_iter()
+            .collect::<Vec<u64>>();
+
+        boundaries.iter().zip(boundaries.iter().skip(1))
+            .filter_map(|(&start, &end)| {
+                let tids = exons.iter()
+                    .filter(|&&(exn_start, exn_end, _)| exn_start <= start && end <= exn_end)
+                    .map(|&(_, _, tid)| tid.to_owned())
+                    .collect::<BTreeSet<String>>();
+                if tids.is_empty() {
+                    return None;
+                }
+                Some(ExonBin {
+                    seq_name: self.seq_name().to_owned(),
+                    start: start,
+                    end: end,
+                    strand: self.strand().clone(),
+                    gene_id: self.id().map(|id| id.to_owned()),
+                    transcript_ids: tids.into_iter().collect(),
+                    shared: false,
+                })
+            })
+            .collect()
+    }
+
+    /// Returns the number of bases covered by the union of all the exons of the gene's
+    /// transcripts.
+    ///
+    /// This is the gene length commonly used for computing TPM values.
+    pub fn effective_len(&self) -> u64 {
+        self.collapsed_exons().iter()
+            .map(|bin| bin.span())
+            .fold(0, |acc, x| acc + x)
+    }
+}
@@ -0,0 +1,262 @@
+/*! In-memory interval index of genes, transcripts, and exons.
+
+The index stores the models it is created from and groups them by their sequence names. Within
+each sequence, the model intervals are sorted by their coordinates and augmented with the running
+maximum of their end coordinates, so that region queries only visit models close to the region.
+
+All coordinates are zero-based, half-open.
+*/
+use std::cmp::max;
+use std::collections::HashMap;
+
+use {Exon, Gene, Strand, Transcript};
+
+
+/// Trait for models that can be stored in an `AnnotationIndex`.
+pub trait Indexable {
+
+    /// Returns the sequence name of the model.
+    fn seq_name(&self) -> &str;
+
+    /// Returns the genome-wise 5'-most coordinate of the model.
+    fn start(&self) -> u64;
+
+    /// Returns the genome-wise 3'-most coordinate of the model.
+    fn end(&self) -> u64;
+
+    /// Returns a reference of the strand of the model.
+    fn strand(&self) -> &Strand;
+}
+
+/// Macro for implementing `Indexable` on the gene, transcript, and exon models.
+macro_rules! impl_indexable {
+    ($struct_ty:ty) => (
+
+        impl Indexable for $struct_ty {
+
+            fn seq_name(&self) -> &str {
+                <$struct_ty>::seq_name(self)
+            }
+
+            fn start(&self) -> u64 {
+                <$struct_ty>::start(self)
+            }
+
+            fn end(&self) -> u64 {
+                <$struct_ty>::end(self)
+            }
+
+            fn strand(&self) -> &Strand {
+                <$struct_ty>::strand(self)
+            }
+        }
+    );
+}
+
+impl_indexable!(Gene);
+impl_indexable!(Transcript);
+impl_indexable!(Exon);
+
+/// Sorted intervals of the models in a single sequence.
+#[derive(Debug, Clone, Default)]
+struct SeqIntervals {
+    /// Start and end coordinates of the models, along with their positions in the index.
+    entries: Vec<(u64, u64, usize)>,
+    /// Maximum end coordinate of all the entries up to and including the entry at each position.
+    max_ends: Vec<u64>,
+}
+
+impl SeqIntervals {
+
+    /// Sorts the entries and computes their running maximum end coordinates.
+    fn finalize(&mut self) {
+        self.entries.sort();
+        let mut max_end = 0;
+        self.max_ends = self.entries.iter()
+            .map(|&(_, end, _)| {
+                max_end = max(max_end, end);
+                max_end
+            })
+            .collect();
+    }
+
+    /// Returns the positions of the models overlapping the given interval, sorted by their
+    /// coordinates.
+    fn overlapping(&self, start: u64, end: u64) -> Vec<usize> {
+        // Find the number of entries starting before the query end.
+        let (mut lo, mut hi) = (0, self.entries.len());
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            if self.entries[mid].0 < end {
+                lo = mid + 1;
+            } else {
+                hi = mid;
+            }
+        }
+        let mut idxs = vec![];
+        for pos in (0..lo).rev() {
+            if self.max_ends[pos] <= start {
+                break;
+            }
+            let (_, entry_end, idx) = self.entries[pos];
+            if entry_end > start {
+                idxs.push(idx);
+            }
+        }
+        idxs.reverse();
+        idxs
+    }
+}
+
+/// In-memory index for region queries over genes, transcripts, or exons.
+///
+/// All query methods accept an optional strand. If it is set, only models on the given strand
+/// are returned. The returned models are sorted by their coordinates.
+#[derive(Debug, Clone)]
+pub struct AnnotationIndex<T: Indexable> {
+    models: Vec<T>,
+    seqs: HashMap<String, SeqIntervals>,
+}
+
+impl<T: Indexable> AnnotationIndex<T> {
+
+    /// Creates an index of the given models.
+    pub fn new<I>(models: I) -> AnnotationIndex<T>
+        where I: IntoIterator<Item=T>
+    {
+        let models = models.into_iter().collect::<Vec<T>>();
+        let mut seqs: HashMap<String, SeqIntervals> = HashMap::new();
+        for (idx, model) in models.iter().enumerate() {
+            seqs.entry(model.seq_name().to_owned())
+                .or_insert_with(SeqIntervals::default)
+                .entries.push((model.start(), model.end(), idx));
+        }
+        for intervals in seqs.values_mut() {
+            intervals.finalize();
+        }
+        AnnotationIndex {
+            models: models,
+            seqs: seqs,
+        }
+    }
+
+    /// Creates an index of the given model results, such as those returned by the readers.
+    ///
+    /// The first error encountered is returned, if any.
+    pub fn from_results<I>(results: I) -> ::Result<AnnotationIndex<T>>
+        where I: IntoIterator<Item=::Result<T>>
+    {
+        let mut models = vec![];
+        for result in results {
+            models.push(result?);
+        }
+        Ok(AnnotationIndex::new(models))
+    }
+
+    /// Returns the number of models in the index.
+    pub fn len(&self) -> usize {
+        self.models.len()
+    }
+
+    /// Returns whether the index contains no models.
+    pub fn is_empty(&self) -> bool {
+        self.models.is_empty()
+    }
+
+    /// Returns a slice of all the models in the index, in the order they were supplied.
+    pub fn models(&self) -> &[T] {
+        self.models.as_slice()
+    }
+
+    /// Returns the models that overlap the given interval by at least one base.
+    pub fn overlapping(&self, seq_name: &str, start: u64, end: u64, strand: Option<&Strand>)
+        -> Vec<&T>
+    {
+        self.seqs.get(seq_name)
+            .map(|intervals| intervals.overlapping(start, end))
+            .unwrap_or_else(Vec::new)
+            .into_iter()
+            .map(|idx| &self.models[idx])
+            .filter(|model| strand.map(|s| model.strand() == s).unwrap_or(true))
+            .collect()
+    }
+
+    /// Returns the models that lie completely within the given interval.
+    pub fn contained(&self, seq_name: &str, start: u64, end: u64, strand: Option<&Strand>)
+        -> Vec<&T>
+    {
+        self.overlapping(seq_name, start, end, strand).into_iter()
+            .filter(|model| model.start() >= start && model.end() <= end)
+            .collect()
+    }
+
+    /// Returns the models that completely enclose the given interval.
+    pub fn containing(&self, seq_name: &str, start: u64, end: u64, strand: Option<&Strand>)
+        -> Vec<&T>
+    {
+        self.overlapping(seq_name, start, end, strand).into_iter()
+            .filter(|model| model.start() <= start && model.end() >= end)
+            .collect()
+    }
+
+    /// Returns the models that contain the given position.
+    pub fn at(&self, seq_name: &str, pos: u64, strand: Option<&Strand>) -> Vec<&T> {
+        self.overlapping(seq_name, pos, pos + 1, strand)
+    }
+}
+
+impl AnnotationIndex<Gene> {
+
+    /// Returns the transcripts of the indexed genes that overlap the given interval.
+    pub fn overlapping_transcripts(
+        &self,
+        seq_name: &str,
+        start: u64,
+        end: u64,
+        strand: Option<&Strand>,
+    ) -> Vec<&Transcript> {
+        let mut transcripts = self.overlapping(seq_name, start, end, strand).into_iter()
+            .flat_map(|gene| gene.transcripts().values())
+            .filter(|trx| trx.start() < end && trx.end() > start)
+            .collect::<Vec<&Transcript>>();
+        transcripts.sort_by_key(|trx| (trx.start(), trx.end()));
+        transcripts
+    }
+
+    /// Returns the exons of the indexed genes that overlap the given interval.
+    pub fn overlapping_exons(
+        &self,
+        seq_name: &str,
+        start: u64,
+        end: u64,
+        strand: Option<&Strand>,
+    ) -> Vec<&Exon> {
+        overlapping_exons(self.overlapping_transcripts(seq_name, start, end, strand), start, end)
+    }
+}
+
+impl AnnotationIndex<Transcript> {
+
+    /// Returns the exons of the indexed transcripts that overlap the given interval.
+    pub fn overlapping_exons(
+        &self,
+        seq_name: &str,
+        start: u64,
+        end: u64,
+        strand: Option<&Strand>,
+    ) -> Vec<&Exon> {
+        overlapping_exons(self.overlapping(seq_name, start, end, strand), start, end)
+    }
+}
+
+/// Helper function to collect the exons of the given transcripts that overlap an interval.
+fn overlapping_exons<'a>(transcripts: Vec<&'a Transcript>, start: u64, end: u64)
+    -> Vec<&'a Exon>
+{
+    let mut exons = transcripts.into_iter()
+        .flat_map(|trx| trx.exons().iter())
+        .filter(|exon| exon.start() < end && exon.end() > start)
+        .collect::<Vec<&Exon>>();
+    exons.sort_by_key(|exon| (exon.start(), exon.end()));
+    exons
+}
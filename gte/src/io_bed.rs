@@ -0,0 +1,626 @@
+/*! Reader and writer for the BED format.
+
+The BED format is an interval-oriented format in which each feature is denoted in a single line.
+It is used most prominently by genome browsers and tools such as
+[bedtools](https://bedtools.readthedocs.io/).
+
+This module supports the BED12 variant, in which each line denotes a transcript with its exons
+stored as blocks, and the BED6 variant, in which each line denotes a single interval. Rows with
+7 to 11 columns are read as BED6 rows, ignoring their extra columns.
+
+A specification of the columns can be found on
+[this page](https://genome.ucsc.edu/FAQ/FAQformat.html#format1).
+*/
+use std::convert::AsRef;
+use std::error::Error;
+use std::io;
+use std::num::ParseIntError;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use csv;
+use itertools::Itertools;
+
+use {Coord, Exon, Gene, GzipReader, Strand, TabixReader, Transcript, TranscriptFeatureKind,
+     TBuilder, DEF_ID};
+use utils::{LinePos, LineReader, OptionDeref, update_seq_name};
+
+
+/// Name for attribute key of record scores.
+const SCORE_STR: &'static str = "score";
+
+/// Name for attribute key of record colors.
+const ITEM_RGB_STR: &'static str = "itemRgb";
+
+/// Value for undefined record names.
+const UNK_STR: &'static str = ".";
+
+/// Value for undefined record scores.
+const DEF_SCORE: &'static str = "0";
+
+/// Prefixes of lines that do not contain records.
+const NON_RECORD_PREFIXES: &'static [&'static str] = &["#", "track", "browser"];
+
+/// The type of errors along with the name of the offending column, if known.
+type ColumnError = (Option<&'static str>, ::Error);
+
+quick_error! {
+    /// Errors that occur when reading or writing BED files.
+    #[derive(Debug)]
+    pub enum BedError {
+        /// Occurs when a row has fewer than 3 or more than 12 columns.
+        InvalidColumnCount(count: usize) {
+            description("unsupported number of columns")
+            display(self_) -> ("{}: {}", self_.description(), count)
+        }
+        /// Occurs when any of the coordinate columns is not a valid u64 value.
+        InvalidCoord(err: ParseIntError, name: Option<String>) {
+            description(err.description())
+            display(self_) -> ("{}, name: {}",
+                               self_.description(), name.as_deref().unwrap_or(DEF_ID))
+            cause(err)
+        }
+        /// Occurs when the value of the block count column, the number of block sizes, and/or
+        /// the number of block starts are not the same.
+        BlockCountMismatch(name: Option<String>) {
+            description("number of blocks and number of block coordinates are not equal")
+            display(self_) -> ("{}, name: {}",
+                               self_.description(), name.as_deref().unwrap_or(DEF_ID))
+        }
+        /// Errors propagated from the underlying `csv` crate.
+        Csv(err: csv::Error) {
+            description(err.description())
+            from()
+            cause(err)
+        }
+        /// Errors that occur when reading from the underlying reader.
+        Io(err: io::Error) {
+            description(err.description())
+            display("{}", err)
+            from()
+            cause(err)
+        }
+    }
+}
+
+/// BED record type.
+///
+/// This type represents the information present in a BED12 or BED6 record. The block sizes and
+/// block starts are stored as `Vec<u64>` and are guaranteed to have the same length. BED6
+/// records have no blocks and their thick coordinates are equal to their start coordinates.
+///
+/// All coordinates are zero-based, half-open. Block starts are relative to the record start.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BedRecord {
+    seq_name: String,
+    start: u64,
+    end: u64,
+    name: Option<String>,
+    score: Option<String>,
+    strand: char,
+    thick_start: u64,
+    thick_end: u64,
+    item_rgb: Option<String>,
+    block_sizes: Vec<u64>,
+    block_starts: Vec<u64>,
+}
+
+impl BedRecord {
+
+    /// Returns the sequence name.
+    pub fn seq_name(&self) -> &str {
+        self.seq_name.as_str()
+    }
+
+    /// Returns the genome-wise 5'-most coordinate of the record.
+    pub fn start(&self) -> u64 {
+        self.start
+    }
+
+    /// Returns the genome-wise 3'-most coordinate of the record.
+    pub fn end(&self) -> u64 {
+        self.end
+    }
+
+    /// Returns the name of the record.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Returns the score of the record.
+    pub fn score(&self) -> Option<&str> {
+        self.score.as_deref()
+    }
+
+    /// Returns the strand.
+    pub fn strand(&self) -> char {
+        self.strand
+    }
+
+    /// Returns the genome-wise 5'-most thick (coding) coordinate of the record.
+    pub fn thick_start(&self) -> u64 {
+        self.thick_start
+    }
+
+    /// Returns the genome-wise 3'-most thick (coding) coordinate of the record.
+    pub fn thick_end(&self) -> u64 {
+        self.thick_end
+    }
+
+    /// Returns the RGB color value of the record.
+    pub fn item_rgb(&self) -> Option<&str> {
+        self.item_rgb.as_deref()
+    }
+
+    /// Returns the number of blocks contained within the record.
+    pub fn num_blocks(&self) -> usize {
+        self.block_sizes.len() // must be the same as block_starts
+    }
+
+    /// Returns a slice of the block sizes of the record.
+    pub fn block_sizes(&self) -> &[u64] {
+        self.block_sizes.as_slice()
+    }
+
+    /// Returns a slice of the block starts of the record, relative to the record start.
+    pub fn block_starts(&self) -> &[u64] {
+        self.block_starts.as_slice()
+    }
+
+    /// Returns the genome-wise coordinates of the blocks of the record.
+    ///
+    /// Records without any blocks are treated as having a single block spanning the record.
+    pub fn block_coords(&self) -> Vec<Coord<u64>> {
+        if self.block_sizes.is_empty() {
+            return vec![(self.start, self.end)];
+        }
+        self.block_starts.iter().zip(self.block_sizes.iter())
+            .map(|(bstart, bsize)| (self.start + bstart, self.start + bstart + bsize))
+            .collect()
+    }
+
+    /// Creates a record from the columns of a row.
+    ///
+    /// This method will return an error if:
+    /// * the number of columns is not between 3 and 12,
+    /// * any of the coordinates are not valid u64 values, or
+    /// * the number of block coordinates and the block count column value are not equal.
+    pub fn try_from_row(row: Vec<String>) -> ::Result<Self> {
+        Self::parse_row(row).map_err(|(_, err)| err)
+    }
+
+    /// Creates a record from the columns of a row, returning the name of the offending column
+    /// along with any error.
+    fn parse_row(mut row: Vec<String>) -> Result<Self, ColumnError> {
+
+        let ncols = row.len();
+        if ncols < 3 || ncols > 12 {
+            return Err((None, ::Error::from(BedError::InvalidColumnCount(ncols))));
+        }
+        // Columns after the sixth are only used when all BED12 columns are present.
+        if ncols < 12 {
+            row.truncate(6);
+        }
+        row.resize(12, String::new());
+
+        let name = defined_value(&row[3]);
+        let parse_num = |raw: &str, field: &'static str| u64::from_str(raw)
+            .map_err(|e| (Some(field), ::Error::from(BedError::InvalidCoord(e, name.clone()))));
+
+        let start = parse_num(&row[1], "chromStart")?;
+        let end = parse_num(&row[2], "chromEnd")?;
+        let (thick_start, thick_end) =
+            if ncols == 12 {
+                (parse_num(&row[6], "thickStart")?, parse_num(&row[7], "thickEnd")?)
+            } else {
+                (start, start)
+            };
+
+        let (block_sizes, block_starts) =
+            if ncols == 12 {
+                let block_count = parse_num(&row[9], "blockCount")? as usize;
+                let sizes = Self::parse_coords(&row[10], name.as_deref())
+                    .map_err(|e| (Some("blockSizes"), ::Error::from(e)))?;
+                let starts = Self::parse_coords(&row[11], name.as_deref())
+                    .map_err(|e| (Some("blockStarts"), ::Error::from(e)))?;
+                if sizes.len() != block_count || starts.len() != block_count {
+                    let err = BedError::BlockCountMismatch(name.clone());
+                    return Err((Some("blockCount"), ::Error::from(err)));
+                }
+                (sizes, starts)
+            } else {
+                (Vec::new(), Vec::new())
+            };
+
+        Ok(BedRecord {
+            strand: row[5].chars().next().unwrap_or('.'),
+            score: defined_value(&row[4]),
+            item_rgb: defined_value(&row[8]),
+            seq_name: row.swap_remove(0),
+            start: start,
+            end: end,
+            name: defined_value(&row[3]),
+            thick_start: thick_start,
+            thick_end: thick_end,
+            block_sizes: block_sizes,
+            block_starts: block_starts,
+        })
+    }
+
+    /// Transforms the record into a transcript.
+    ///
+    /// Each block becomes an exon and the thick coordinates become the coding region, which
+    /// includes the stop codon. The score and color values are stored as the `score` and
+    /// `itemRgb` attributes of the transcript.
+    pub fn into_transcript(self) -> ::Result<Transcript> {
+
+        let coding_coord =
+            if self.thick_start == self.thick_end {
+                None
+            } else {
+                Some((self.thick_start, self.thick_end))
+            };
+        let exon_coords = self.block_coords();
+
+        let mut builder = TBuilder::new(self.seq_name, self.start, self.end)
+            .strand_char(self.strand)
+            .coords(exon_coords, coding_coord)
+            .coding_incl_stop(true);
+        if let Some(name) = self.name {
+            builder = builder.id(name);
+        }
+        if let Some(score) = self.score {
+            builder = builder.attribute(SCORE_STR, score);
+        }
+        if let Some(item_rgb) = self.item_rgb {
+            builder = builder.attribute(ITEM_RGB_STR, item_rgb);
+        }
+
+        builder.build()
+    }
+
+    /// Parses the given raw comma-separated string into a vector of u64s.
+    ///
+    /// The record name argument is required for when an error type is returned.
+    #[inline]
+    fn parse_coords(raw_coords: &str, name: Option<&str>) -> Result<Vec<u64>, BedError> {
+        let rcoords = raw_coords
+            .trim_matches(',')
+            .split(',')
+            .map(|item| u64::from_str(item)
+                 .map_err(|e| BedError::InvalidCoord(e, name.map(|v| v.to_owned()))));
+
+        let mut res = vec![];
+        for rcoord in rcoords {
+            res.push(rcoord?);
+        }
+        Ok(res)
+    }
+}
+
+/// BED reader.
+pub struct Reader<R: io::Read> {
+    inner: LineReader<R>,
+    seq_name_prefix: Option<String>,
+    seq_name_lstrip: Option<String>,
+}
+
+impl<R: io::Read> Reader<R> {
+
+    /// Creates a BED reader from another reader.
+    pub fn from_reader(in_reader: R) -> Reader<R> {
+        Reader {
+            inner: LineReader::new(in_reader),
+            seq_name_prefix: None,
+            seq_name_lstrip: None,
+        }
+    }
+
+    /// Sets the reader to add the given prefix to all sequence names.
+    pub fn seq_name_prefix<T>(&mut self, prefix: T) -> &mut Self
+        where T: Into<String>
+    {
+        self.seq_name_prefix = Some(prefix.into());
+        self
+    }
+
+    /// Sets the reader to trim the given string from all sequence names if present at the
+    /// beginning.
+    pub fn seq_name_lstrip<T>(&mut self, lstrip: T) -> &mut Self
+        where T: Into<String>
+    {
+        self.seq_name_lstrip = Some(lstrip.into());
+        self
+    }
+
+    /// Creates an iterator of BED records.
+    ///
+    /// Empty, comment, `track`, and `browser` lines are skipped. Errors of records that can not
+    /// be parsed are returned as `Error::Line` values, which contain the line number, the line,
+    /// and the offending column name.
+    pub fn records_stream(&mut self) -> BedRecordsStream<R> {
+        BedRecordsStream {
+            inner: &mut self.inner,
+            seq_name_prefix: self.seq_name_prefix.as_deref(),
+            seq_name_lstrip: self.seq_name_lstrip.as_deref(),
+        }
+    }
+
+    /// Creates an iterator of transcripts.
+    ///
+    /// Errors of transcripts that can not be created are also returned as `Error::Line` values.
+    pub fn transcripts_stream(&mut self) -> BedTranscriptsStream<R> {
+        BedTranscriptsStream {
+            inner: self.records_stream()
+        }
+    }
+}
+
+impl Reader<GzipReader<fs::File>> {
+
+    /// Creates a BED reader that reads from the given path.
+    ///
+    /// Gzip and BGZF-compressed files are decompressed transparently.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        GzipReader::from_file(path).map(Reader::from_reader)
+    }
+}
+
+/// BED reader of BGZF-compressed files with a tabix index.
+pub struct IndexedReader<R: io::Read + io::Seek> {
+    inner: TabixReader<R>,
+}
+
+impl<R: io::Read + io::Seek> IndexedReader<R> {
+
+    /// Creates an indexed BED reader from a tabix reader.
+    pub fn from_reader(in_reader: TabixReader<R>) -> IndexedReader<R> {
+        IndexedReader { inner: in_reader }
+    }
+
+    /// Returns the transcripts that overlap the given zero-based, half-open interval of a
+    /// sequence.
+    ///
+    /// The first error of a record or transcript is returned instead of the transcripts. Its
+    /// line number is the number of the record among the records read for the interval.
+    pub fn fetch(&mut self, seq_name: &str, start: u64, end: u64) -> ::Result<Vec<Transcript>> {
+        let raw = self.inner.fetch(seq_name, start, end)?.join("\n");
+        let mut reader = Reader::from_reader(raw.as_bytes());
+        let transcripts = reader.transcripts_stream().collect::<::Result<Vec<Transcript>>>()?;
+        Ok(transcripts)
+    }
+}
+
+impl IndexedReader<fs::File> {
+
+    /// Creates an indexed BED reader of the BGZF-compressed file at the given path.
+    ///
+    /// The index is read from the path with a `.tbi` or `.csi` extension added.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> ::Result<Self> {
+        TabixReader::from_file(path).map(IndexedReader::from_reader)
+    }
+}
+
+/// Iterator over BED records.
+pub struct BedRecordsStream<'a, R: 'a> where R: io::Read {
+    inner: &'a mut LineReader<R>,
+    seq_name_prefix: Option<&'a str>,
+    seq_name_lstrip: Option<&'a str>,
+}
+
+impl<'a, R> BedRecordsStream<'a, R> where R: io::Read {
+
+    /// Returns the next BED record along with the location of its line.
+    fn next_located(&mut self) -> Option<::Result<(LinePos, BedRecord)>> {
+        let lstrip = self.seq_name_lstrip.map(|v| (v, v.len()));
+        let prefix = self.seq_name_prefix;
+        while let Some(result) = self.inner.next_line(|e| ::Error::from(BedError::from(e))) {
+            let pos = match result {
+                Ok(pos) => pos,
+                Err(e) => return Some(Err(e)),
+            };
+            let is_record = !pos.raw.trim().is_empty()
+                && !NON_RECORD_PREFIXES.iter().any(|pre| pos.raw.starts_with(pre));
+            if !is_record {
+                continue;
+            }
+            let mut row = pos.columns();
+            update_seq_name(&mut row[0], prefix, lstrip);
+            return Some(BedRecord::parse_row(row)
+                .map(|rec| (pos.clone(), rec))
+                .map_err(|(field, err)| pos.error(field, err)));
+        }
+        None
+    }
+}
+
+impl<'a, R> Iterator for BedRecordsStream<'a, R> where R: io::Read {
+
+    type Item = ::Result<BedRecord>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_located()
+            .map(|result| result.map(|(_, rec)| rec))
+    }
+}
+
+/// Iterator over transcripts created from BED records.
+pub struct BedTranscriptsStream<'a, R: 'a> where R: io::Read {
+    inner: BedRecordsStream<'a, R>,
+}
+
+impl<'a, R> Iterator for BedTranscriptsStream<'a, R> where R: io::Read {
+
+    type Item = ::Result<Transcript>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next_located()
+            .map(|record| record.and_then(|(pos, rec)| {
+                rec.into_transcript().map_err(|e| pos.error(None, e))
+            }))
+    }
+}
+
+/// BED writer.
+pub struct Writer<W: io::Write> {
+    inner: csv::Writer<W>,
+}
+
+impl<W: io::Write> Writer<W> {
+
+    /// Creates a BED writer from another writer.
+    pub fn from_writer(in_writer: W) -> Writer<W> {
+        Writer {
+            inner: csv::Writer::from_writer(in_writer)
+                .delimiter(b'\t')
+                .quote_style(csv::QuoteStyle::Never)
+                .flexible(true)
+        }
+    }
+
+    /// Writes the given record.
+    ///
+    /// Records without any blocks are written as BED6 rows. Otherwise, they are written as BED12
+    /// rows.
+    pub fn write_record(&mut self, record: &BedRecord) -> ::Result<()> {
+        let name = record.name().unwrap_or(UNK_STR);
+        let score = record.score().unwrap_or(DEF_SCORE);
+        if record.block_sizes.is_empty() {
+            return self.inner
+                .encode((record.seq_name(), record.start, record.end, name, score,
+                         record.strand))
+                .map_err(|e| ::Error::from(BedError::from(e)));
+        }
+        let mut block_sizes = record.block_sizes.iter().join(",");
+        block_sizes.push(',');
+        let mut block_starts = record.block_starts.iter().join(",");
+        block_starts.push(',');
+        self.inner
+            .encode((record.seq_name(), record.start, record.end, name, score, record.strand,
+                     record.thick_start, record.thick_end, record.item_rgb().unwrap_or("0"),
+                     record.num_blocks(), block_sizes, block_starts))
+            .map_err(|e| ::Error::from(BedError::from(e)))
+    }
+
+    /// Writes the given transcript as a single BED12 row.
+    ///
+    /// The thick coordinates are set to the coding region, including the stop codon. For
+    /// non-coding transcripts, both thick coordinates are set to the transcript start.
+    pub fn write_transcript(&mut self, transcript: &Transcript) -> ::Result<()> {
+        let record = transcript.bed_record();
+        self.write_record(&record)
+    }
+
+    /// Writes the given gene as multiple BED12 rows.
+    pub fn write_gene(&mut self, gene: &Gene) -> ::Result<()> {
+        for transcript in gene.transcripts().values() {
+            self.write_transcript(&transcript)?;
+        }
+        Ok(())
+    }
+
+    /// Writes the introns of the given transcript as BED6 rows.
+    ///
+    /// Each intron is named after its transcript and its transcript-wise rank, e.g.
+    /// `NM_001.1_intron2`.
+    pub fn write_introns(&mut self, transcript: &Transcript) -> ::Result<()> {
+        let tid = transcript.id().unwrap_or(UNK_STR);
+        for intron in transcript.introns() {
+            let rank = match intron.kind() {
+                &TranscriptFeatureKind::Intron { rank, .. } => rank,
+                _ => continue,
+            };
+            self.inner
+                .encode((transcript.seq_name(), intron.start(), intron.end(),
+                         format!("{}_intron{}", tid, rank), DEF_SCORE,
+                         strand_to_char(transcript.strand())))
+                .map_err(|e| ::Error::from(BedError::from(e)))?;
+        }
+        Ok(())
+    }
+
+    /// Writes the given exon as a single BED6 row.
+    pub fn write_exon(&mut self, exon: &Exon) -> ::Result<()> {
+        let score = exon.attributes().get(SCORE_STR).map(|v| v.as_str()).unwrap_or(DEF_SCORE);
+        self.inner
+            .encode((exon.seq_name(), exon.start(), exon.end(), exon.id().unwrap_or(UNK_STR),
+                     score, strand_to_char(exon.strand())))
+            .map_err(|e| ::Error::from(BedError::from(e)))
+    }
+
+    /// Writes all buffered rows to the underlying writer.
+    pub fn flush(&mut self) -> ::Result<()> {
+        self.inner.flush()
+            .map_err(|e| ::Error::from(BedError::from(e)))
+    }
+}
+
+impl Transcript {
+
+    /// Creates a BED12 record of the transcript.
+    fn bed_record(&self) -> BedRecord {
+        let (thick_start, thick_end) = self.coding_coord(true)
+            .unwrap_or((self.start(), self.start()));
+        let (block_sizes, block_starts) = self.exons().iter()
+            .map(|exon| (exon.span(), exon.start() - self.start()))
+            .unzip();
+        BedRecord {
+            seq_name: self.seq_name().to_owned(),
+            start: self.start(),
+            end: self.end(),
+            name: self.id().map(|v| v.to_owned()),
+            score: self.attributes().get(SCORE_STR).cloned(),
+            strand: strand_to_char(self.strand()),
+            thick_start: thick_start,
+            thick_end: thick_end,
+            item_rgb: self.attributes().get(ITEM_RGB_STR).cloned(),
+            block_sizes: block_sizes,
+            block_starts: block_starts,
+        }
+    }
+}
+
+impl Writer<fs::File> {
+
+    /// Creates a BED writer that writes to the given path.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let f = fs::File::create(path)?;
+        Ok(Writer::from_writer(f))
+    }
+}
+
+impl Writer<Vec<u8>> {
+
+    /// Creates a BED writer that writes to an in-memory buffer.
+    ///
+    /// The initial capacity of the buffer is 64 KiB.
+    pub fn from_memory() -> Writer<Vec<u8>> {
+        Writer::from_writer(Vec::with_capacity(1024 * 64))
+    }
+
+    /// Returns the values of the in-memory buffer as a string.
+    pub fn as_string(&mut self) -> &str {
+        self.inner.as_string()
+    }
+}
+
+/// Helper function to treat empty and undefined column values as missing.
+#[inline(always)]
+fn defined_value(raw: &str) -> Option<String> {
+    if raw.is_empty() || raw == UNK_STR {
+        None
+    } else {
+        Some(raw.to_owned())
+    }
+}
+
+/// Helper function to create a char given a strand reference.
+#[inline(always)]
+fn strand_to_char(strand: &Strand) -> char {
+    match strand {
+        &Strand::Forward => '+',
+        &Strand::Reverse => '-',
+        &Strand::Unknown => '.',
+    }
+}
@@ -1,4 +1,4 @@
-/*! Reader for GFF format variants.
+/*! Reader and writer for GFF format variants.
 
 The GFF format is a feature-oriented format that is commonly used to store gene annotation data.
 
@@ -6,24 +6,35 @@ An unofficial specification of the formats can be found [here](http://mblab.wust
 or [here](http://www.ensembl.org/info/website/upload/gff.html).
 
 The reader provided by this module is based on a modified version of the GFF reader provided by
-the [rust-bio](https://github.com/rust-bio/rust-bio) library.
+the [rust-bio](https://github.com/rust-bio/rust-bio) library. The writer accepts the GFF records
+created by the gene, transcript, and exon models.
 */
 use std::cmp::{max, min};
+use std::collections::{HashMap, HashSet};
 use std::convert::AsRef;
 use std::error::Error;
 use std::io;
 use std::fs;
+use std::mem;
 use std::path::Path;
+use std::str::FromStr;
 use std::vec;
 
 use bio::io::gff::{self, GffType};
+use csv;
 use itertools::{GroupBy, Group, Itertools};
+use linked_hash_map::LinkedHashMap;
 use multimap::MultiMap;
-use regex::{Error as RegexError, Regex};
+use regex::Error as RegexError;
 
-use {Coord, Exon, ExonFeatureKind as EFK, Gene, Strand, TBuilder, Transcript,
-     RawTrxCoords, INIT_START, INIT_END, INIT_COORD, DEF_ID};
-use utils::{OptionDeref, update_seq_name};
+use bio::utils::Interval;
+
+use {Coord, EBuilder, ErrorPolicy, ErrorReport, Exon, ExonFeature, ExonFeatureKind as EFK,
+     Feature, Gene, GBuilder, GzipReader, IdLines, Strand, TabixReader, TBuilder, Transcript,
+     TranscriptFeature, TranscriptFeatureKind as TFK, ModelError, RawTrxCoords, INIT_START,
+     INIT_END, INIT_COORD, DEF_ID};
+use policy::{ErrorHandler, defer_error};
+use utils::{LinePos, LineReader, OptionDeref, update_seq_name};
 
 
 /// Name for gene features.
@@ -44,6 +55,18 @@ const UTR5_STR: &'static str = "UTR5";
 /// Name for 3'UTR features.
 const UTR3_STR: &'static str = "UTR3";
 
+/// Name for GFF3 coding transcript features.
+const GFF3_MRNA_STR: &'static str = "mRNA";
+
+/// Name for GFF3 non-coding transcript features.
+const GFF3_NCRNA_STR: &'static str = "ncRNA";
+
+/// Name for GFF3 5'UTR features.
+const GFF3_UTR5_STR: &'static str = "five_prime_UTR";
+
+/// Name for GFF3 3'UTR features.
+const GFF3_UTR3_STR: &'static str = "three_prime_UTR";
+
 /// Name for CDS features.
 const CDS_STR: &'static str = "CDS";
 
@@ -53,20 +76,60 @@ const START_CODON_STR: &'static str = "start_codon";
 /// Name for stop codon features.
 const STOP_CODON_STR: &'static str = "stop_codon";
 
+/// Name for intron features.
+const INTRON_STR: &'static str = "intron";
+
+/// Names of GFF3 features that are treated as transcripts.
+const GFF3_TRANSCRIPT_STRS: &'static [&'static str] = &[
+    TRANSCRIPT_STR, "mRNA", "ncRNA", "lnc_RNA", "lncRNA", "rRNA", "tRNA", "snRNA", "snoRNA",
+    "scRNA", "miRNA", "misc_RNA", "antisense_RNA", "primary_transcript", "pseudogenic_transcript",
+    "unconfirmed_transcript", "NMD_transcript_variant", "processed_transcript",
+];
+
+/// Names of GFF3 features that are treated as genes.
+const GFF3_GENE_STRS: &'static [&'static str] = &[GENE_STR, "ncRNA_gene", "pseudogene"];
+
 /// Name for attribute key of gene identifiers.
 const GENE_ID_STR: &'static str = "gene_id";
 
 /// Name for attribute key of transcript identifiers.
 const TRANSCRIPT_ID_STR: &'static str = "transcript_id";
 
+/// Name for attribute key of record sources.
+const SOURCE_STR: &'static str = "source";
+
+/// Name for attribute key of record scores.
+const SCORE_STR: &'static str = "score";
+
+/// Name for attribute key of GFF3 record identifiers.
+const GFF3_ID_STR: &'static str = "ID";
+
+/// Name for attribute key of GFF3 parent record identifiers.
+const GFF3_PARENT_STR: &'static str = "Parent";
+
+/// Header line for GFF3 files.
+const GFF3_HEADER: &'static str = "##gff-version 3";
+
+/// Header line for GFF2 and GTF2 files.
+const GFF2_HEADER: &'static str = "##gff-version 2";
+
 /// Value for columns that are undefined, as a string.
 const UNK_STR: &'static str = ".";
 
 /// Value for columns that are undefined, as a char.
 const UNK_CHAR: char = '.';
 
+/// Directive line after which the GFF3 records end and FASTA sequences begin.
+const FASTA_DIRECTIVE: &'static str = "##FASTA";
+
+/// Number of columns in a GFF row.
+const NUM_COLS: usize = 9;
+
+/// Name of the attribute column, as used in error locations.
+const ATTRIBUTES_FIELD: &'static str = "attributes";
+
 quick_error! {
-    /// Errors that occur when reading GFF file variants.
+    /// Errors that occur when reading or writing GFF file variants.
     #[derive(Debug)]
     pub enum GffError {
         /// Occurs when a record does not have any gene identifier attribute.
@@ -122,16 +185,44 @@ quick_error! {
             display(self_) -> ("{}, transcript ID: {}",
                                self_.description(), tid.as_deref().unwrap_or(DEF_ID))
         }
+        /// Occurs when records of a transcript are not grouped together in a streamed input.
+        UngroupedTranscript(tid: Option<String>) {
+            description("transcript records are not grouped together")
+            display(self_) -> ("{}, transcript ID: {}",
+                               self_.description(), tid.as_deref().unwrap_or(DEF_ID))
+        }
         /// Occurs when an unsupported GFF variant is used.
         UnsupportedGffType {
             description("unsupported gff type")
         }
+        /// Occurs when a row has a number of columns other than 9.
+        InvalidColumnCount(count: usize) {
+            description("unexpected number of columns")
+            display(self_) -> ("{}: {}", self_.description(), count)
+        }
+        /// Occurs when a start or end column does not contain a positive integer.
+        InvalidCoord(raw: String) {
+            description("coordinate is not a positive integer")
+            display(self_) -> ("{}: '{}'", self_.description(), raw)
+        }
+        /// Occurs when the strand column does not contain a valid strand character.
+        InvalidStrand(raw: String) {
+            description("invalid strand value")
+            display(self_) -> ("{}: '{}'", self_.description(), raw)
+        }
         /// Generic wrapper type for errors from the regex crate.
         Regex(err: RegexError) {
             description(err.description())
             from()
             cause(err)
         }
+        /// Errors propagated from the underlying `csv` crate.
+        Csv(err: csv::Error) {
+            description(err.description())
+            display("{}", err)
+            from()
+            cause(err)
+        }
         /// Generic wrapper for GFF errors from the rust-bio crate.
         Bio(err: gff::GffError) {
             description(err.description())
@@ -139,17 +230,34 @@ quick_error! {
             from()
             cause(err)
         }
+        /// Errors that occur when reading from the underlying reader.
+        Io(err: io::Error) {
+            description(err.description())
+            display("{}", err)
+            from()
+            cause(err)
+        }
     }
 }
 
 /// GFF reader.
+///
+/// Empty and comment lines are skipped. Errors of rows that can not be parsed are returned as
+/// `Error::Line` values, which contain the line number, the line, and the offending column name.
+/// These errors and those of transcripts and genes that can not be created are handled
+/// according to the error policy of the reader.
 pub struct Reader<R: io::Read> {
-    inner: gff::Reader<R>,
+    inner: LineReader<R>,
     gene_id_attr: String,
     transcript_id_attr: String,
     seq_name_prefix: Option<String>,
     seq_name_lstrip: Option<String>,
     loose_codons: bool,
+    lossless: bool,
+    error_policy: ErrorPolicy,
+    report: ErrorReport,
+    record_id_lines: bool,
+    id_lines: IdLines,
     pub(crate) gff_type: GffType,
 }
 
@@ -158,12 +266,17 @@ impl<R: io::Read> Reader<R> {
     /// Creates a GFF reader of the given variant from another reader.
     pub fn from_reader(in_reader: R, gff_type: GffType) -> Reader<R> {
         Reader {
-            inner: gff::Reader::new(in_reader, gff_type),
+            inner: LineReader::new(in_reader),
             gene_id_attr: GENE_ID_STR.to_owned(),
             transcript_id_attr: TRANSCRIPT_ID_STR.to_owned(),
             seq_name_prefix: None,
             seq_name_lstrip: None,
             loose_codons: false,
+            lossless: false,
+            error_policy: ErrorPolicy::default(),
+            report: ErrorReport::default(),
+            record_id_lines: false,
+            id_lines: IdLines::default(),
             gff_type: gff_type.clone(),
         }
     }
@@ -185,19 +298,19 @@ impl<R: io::Read> Reader<R> {
     }
 
     /// Sets the reader to add the given prefix to all sequence names.
-    pub fn seq_name_prefix<T>(&mut self, prefix: Option<T>) -> &mut Self
+    pub fn seq_name_prefix<T>(&mut self, prefix: T) -> &mut Self
         where T: Into<String>
     {
-        self.seq_name_prefix = prefix.map(|v| v.into());
+        self.seq_name_prefix = Some(prefix.into());
         self
     }
 
     /// Sets the reader to trim the given string from all sequence names if present at the
     /// beginning.
-    pub fn seq_name_lstrip<T>(&mut self, lstrip: Option<T>) -> &mut Self
+    pub fn seq_name_lstrip<T>(&mut self, lstrip: T) -> &mut Self
         where T: Into<String>
     {
-        self.seq_name_lstrip = lstrip.map(|v| v.into());
+        self.seq_name_lstrip = Some(lstrip.into());
         self
     }
 
@@ -208,77 +321,588 @@ impl<R: io::Read> Reader<R> {
         self
     }
 
+    /// Sets the reader to keep values that are otherwise discarded when creating models.
+    ///
+    /// When set, the source and score columns are stored as the `source` and `score` attributes
+    /// of genes, transcripts, exons, and features. Exon features are then created as-is from
+    /// the CDS, codon, and other feature records of each transcript instead of being inferred
+    /// from its coding coordinates, keeping their frames and attributes. Records of other
+    /// feature types become `ExonFeatureKind::Any` features when they lie within an exon and
+    /// `TranscriptFeatureKind::Any` features otherwise. Records without a transcript are still
+    /// ignored.
+    pub fn lossless(&mut self, lossless: bool) -> &mut Self {
+        self.lossless = lossless;
+        self
+    }
+
+    /// Sets how errors of rows, transcripts, and genes that can not be read are handled.
+    ///
+    /// For `transcripts` and `genes`, which read all rows first, errors of rows are returned
+    /// before any transcripts or genes. With the `Strict` policy, the first error of a row is
+    /// returned by `transcripts` or `genes` themselves.
+    pub fn error_policy(&mut self, policy: ErrorPolicy) -> &mut Self {
+        self.error_policy = policy;
+        self
+    }
+
+    /// Returns the rows, transcripts, and genes rejected by the iterators of the reader so far.
+    ///
+    /// Rejections are only stored when the `Collect` error policy is used.
+    pub fn error_report(&self) -> &ErrorReport {
+        &self.report
+    }
+
+    /// Sets the reader to record the line numbers of the transcript and gene identifiers in
+    /// the rows it reads.
+    pub fn record_id_lines(&mut self, record_id_lines: bool) -> &mut Self {
+        self.record_id_lines = record_id_lines;
+        self
+    }
+
+    /// Returns the line numbers of the identifiers read by the iterators of the reader so far.
+    ///
+    /// Lines are only recorded when set with `record_id_lines`. Rows that can not be parsed
+    /// are not recorded, since their errors already contain their line numbers.
+    pub fn id_lines(&self) -> &IdLines {
+        &self.id_lines
+    }
+
     /// Creates an iterator of transcripts.
     ///
     /// This iterator reads all GFF records into memory first, before sorting and grouping them
     /// into transcripts. This is because features of a transcript may be interspersed with
     /// features from another transcript.
+    ///
+    /// For GFF3 input, the gene and transcript identifier attributes are not used. Instead,
+    /// records are linked to their transcripts and genes through their `ID` and `Parent`
+    /// attributes.
     pub fn transcripts(&mut self) -> ::Result<GffTranscripts> {
 
-        let gid_regex = make_gff_id_regex(self.gene_id_attr.as_str(), self.gff_type)?;
-        let tid_regex = make_gff_id_regex(self.transcript_id_attr.as_str(), self.gff_type)?;
+        let (mut parts, gene_records, row_errors) = self.read_parts()?;
+        self.add_id_lines(&parts, &gene_records);
+        parts.sort_by_key(|ref elem| elem.sort_key());
+
+        let opts = self.trx_opts();
+        Ok(GffTranscripts {
+            row_errors: row_errors.into_iter(),
+            groups: parts.into_iter().group_by(TrxPart::transcript_group_key),
+            opts: opts,
+            handler: ErrorHandler::new(self.error_policy, &mut self.report),
+        })
+    }
+
+    /// Creates a streaming iterator of transcripts.
+    ///
+    /// Unlike `transcripts`, this iterator does not read all GFF records into memory. Instead, it
+    /// requires the records of each transcript to be grouped together in the input, as is the
+    /// case for GTF files from Ensembl and GENCODE. A transcript is created as soon as a record
+    /// of another transcript is encountered. Records of a transcript that has already been
+    /// created in the same sequence result in an error, even when records of other sequences
+    /// come in between.
+    ///
+    /// For GFF3 input, the `ID` and `Parent` attributes are used as in `transcripts`, which
+    /// requires each transcript record to precede the records of its exons, CDSes, and codons.
+    pub fn transcripts_stream(&mut self) -> ::Result<GffTranscriptsStream<R>> {
+        let opts = self.trx_opts();
+        Ok(GffTranscriptsStream {
+            inner: GffRawRows { inner: &mut self.inner, finished: false },
+            gene_id_attr: self.gene_id_attr.as_str(),
+            transcript_id_attr: self.transcript_id_attr.as_str(),
+            gff_type: self.gff_type,
+            seq_name_prefix: self.seq_name_prefix.as_deref(),
+            seq_name_lstrip: self.seq_name_lstrip.as_deref(),
+            opts: opts,
+            group_key: None,
+            group: Vec::new(),
+            pending: Vec::new().into_iter(),
+            seq_name: None,
+            closed_ids: HashSet::new(),
+            record_ids: HashSet::new(),
+            trx_gene_ids: HashMap::new(),
+            id_lines: if self.record_id_lines { Some(&mut self.id_lines) } else { None },
+            handler: ErrorHandler::new(self.error_policy, &mut self.report),
+        })
+    }
+
+    /// Creates an iterator of genes.
+    ///
+    /// Similar to `transcripts`, this iterator reads all GFF records into memory first. The
+    /// transcripts are then grouped into genes using their gene identifiers. The gene coordinates
+    /// and attributes are taken from the gene record when present. Otherwise, the gene spans all
+    /// of its transcripts and has no attributes.
+    ///
+    /// Errors of transcripts that can not be created are returned before their gene, which is
+    /// still created from its remaining transcripts, if any.
+    pub fn genes(&mut self) -> ::Result<GffGenes> {
+
+        let (mut parts, gene_records, row_errors) = self.read_parts()?;
+        self.add_id_lines(&parts, &gene_records);
+        parts.sort_by_key(|ref elem| elem.gene_sort_key());
+
+        let opts = self.trx_opts();
+        Ok(GffGenes {
+            row_errors: row_errors.into_iter(),
+            groups: parts.into_iter().group_by(TrxPart::gene_group_key),
+            gene_records: gene_records,
+            pending: Vec::new().into_iter(),
+            opts: opts,
+            handler: ErrorHandler::new(self.error_policy, &mut self.report),
+        })
+    }
+
+    /// Returns the options for creating transcripts from the records.
+    ///
+    /// Coding coordinates are inferred from CDS features when no codon features are present
+    /// only for GFF3, where CDS features include the stop codon and start and stop codon
+    /// features are usually absent.
+    fn trx_opts(&self) -> TrxOpts {
+        TrxOpts {
+            loose_codons: self.loose_codons,
+            infer_codons: match self.gff_type {
+                GffType::GFF3 => true,
+                _ => false,
+            },
+            lossless: self.lossless,
+        }
+    }
+
+    /// Records the line numbers of the identifiers of the given parts and gene records, if set.
+    fn add_id_lines(
+        &mut self,
+        parts: &[TrxPart],
+        gene_records: &HashMap<GeneRecordKey, GeneRecord>,
+    ) {
+        if !self.record_id_lines {
+            return;
+        }
+        for part in parts.iter() {
+            part.add_id_lines(&mut self.id_lines);
+        }
+        for (&(ref gid, _), record) in gene_records.iter() {
+            self.id_lines.add(gid, record.line);
+        }
+    }
+
+    /// Reads all GFF records into transcript parts, gene records, and errors of rows that were
+    /// skipped.
+    ///
+    /// The first error is returned instead if the rows can not be skipped.
+    fn read_parts(&mut self) -> ::Result<ReadParts> {
+        if let GffType::GFF3 = self.gff_type {
+            return self.read_gff3_parts();
+        }
+
+        let gid_attr = self.gene_id_attr.clone();
+        let tid_attr = self.transcript_id_attr.clone();
         let prefix = self.seq_name_prefix.clone();
         let lstrip = self.seq_name_lstrip.clone();
+        let gff_type = self.gff_type;
+        let lossless = self.lossless;
+        let policy = self.error_policy;
 
         let mut parts = Vec::new();
+        let mut gene_records = HashMap::new();
+        let mut row_errors = Vec::new();
         for result in self.raw_rows_stream() {
-            let mut row = result.map_err(::Error::from)?;
-            update_seq_name(&mut row.0, prefix.as_deref(),
-                            lstrip.as_deref().map(|v| (v, v.len())));
-            match row.2.as_str() {
-                TRANSCRIPT_STR | EXON_STR | CDS_STR | START_CODON_STR | STOP_CODON_STR => {
-                    let rf = TrxPart::try_from_row(row, &gid_regex, &tid_regex)
-                        .map_err(::Error::from)?;
-                    parts.push(rf);
-                },
-                _ => {},
+            let row_result = result.and_then(|(mut row, pos): (RawRow, LinePos)| -> ::Result<()> {
+                update_seq_name(&mut row.0, prefix.as_deref(),
+                                lstrip.as_deref().map(|v| (v, v.len())));
+                match row.2.as_str() {
+                    TRANSCRIPT_STR | EXON_STR | CDS_STR | START_CODON_STR | STOP_CODON_STR => {
+                        let rf = TrxPart::try_from_row(row, &pos, &gid_attr, &tid_attr, gff_type)
+                            .map_err(|e| pos.error(Some(ATTRIBUTES_FIELD), e))?;
+                        parts.push(rf);
+                    },
+                    GENE_STR => {
+                        let (key, rec) = GeneRecord::try_from_row(row, &pos, &gid_attr, gff_type)
+                            .map_err(|e| pos.error(Some(ATTRIBUTES_FIELD), e))?;
+                        let _ = gene_records.insert(key, rec);
+                    },
+                    _ if lossless => {
+                        match TrxPart::try_from_row(row, &pos, &gid_attr, &tid_attr, gff_type) {
+                            Ok(rf) => parts.push(rf),
+                            Err(GffError::MissingGeneId)
+                                | Err(GffError::MissingTranscriptId) => {},
+                            Err(e) => return Err(pos.error(Some(ATTRIBUTES_FIELD), e)),
+                        }
+                    },
+                    _ => {},
+                }
+                Ok(())
+            });
+            if let Err(e) = row_result {
+                defer_error(policy, &mut row_errors, e)?;
             }
         }
-        parts.sort_by_key(|ref elem| elem.sort_key());
 
-        Ok(GffTranscripts {
-            groups: parts.into_iter().group_by(TrxPart::transcript_group_key),
-            loose_codons: self.loose_codons,
-        })
+        Ok((parts, gene_records, row_errors))
+    }
+
+    /// Reads all GFF3 records into transcript parts and gene records.
+    ///
+    /// Instead of the gene and transcript identifier attributes, the `ID` and `Parent` attributes
+    /// are used to link exons, CDSes, and codons to their transcripts and transcripts to their
+    /// genes. Features with multiple parents are added to each of their transcripts, and features
+    /// whose parents are not transcripts (e.g. exons of pseudogenes) are ignored.
+    fn read_gff3_parts(&mut self) -> ::Result<ReadParts> {
+        let prefix = self.seq_name_prefix.clone();
+        let lstrip = self.seq_name_lstrip.clone();
+        let lossless = self.lossless;
+        let policy = self.error_policy;
+
+        let mut parts = Vec::new();
+        let mut gene_records = HashMap::new();
+        let mut record_ids = HashSet::new();
+        let mut trx_gene_ids = HashMap::new();
+        let mut children = Vec::new();
+        let mut row_errors = Vec::new();
+        for result in self.raw_rows_stream() {
+            let row_result = result.and_then(|(mut row, pos): (RawRow, LinePos)| -> ::Result<()> {
+                update_seq_name(&mut row.0, prefix.as_deref(),
+                                lstrip.as_deref().map(|v| (v, v.len())));
+                let attributes_err = |err: GffError| pos.error(Some(ATTRIBUTES_FIELD), err);
+                let mut attributes = parse_attributes(&row.8, GffType::GFF3)
+                    .map_err(&attributes_err)?;
+                let rec_id = attributes.get(GFF3_ID_STR).cloned();
+                if let Some(ref id) = rec_id {
+                    let _ = record_ids.insert(id.clone());
+                }
+                let feature = row.2.clone();
+                match feature.as_str() {
+                    ft if GFF3_TRANSCRIPT_STRS.contains(&ft) => {
+                        let tid = rec_id
+                            .ok_or_else(|| attributes_err(GffError::MissingTranscriptId))?;
+                        let gid = attributes.get(GFF3_PARENT_STR).cloned()
+                            .ok_or_else(|| attributes_err(GffError::MissingGeneId))?;
+                        let _ = attributes.remove(GFF3_ID_STR);
+                        let _ = attributes.remove(GFF3_PARENT_STR);
+                        let _ = trx_gene_ids.insert(tid.clone(), gid.clone());
+                        parts.push(TrxPart::from_gff3_row(&row, &pos, TRANSCRIPT_STR, tid, gid,
+                                                          attributes));
+                    },
+                    ft if GFF3_GENE_STRS.contains(&ft) => {
+                        let gid = rec_id
+                            .ok_or_else(|| attributes_err(GffError::MissingGeneId))?;
+                        let _ = attributes.remove(GFF3_ID_STR);
+                        let record = GeneRecord {
+                            coord: (row.3, row.4),
+                            source: row.1.clone(),
+                            score: row.5.clone(),
+                            attributes: attributes,
+                            line: pos.line,
+                        };
+                        let _ = gene_records.insert((gid, row.0), record);
+                    },
+                    EXON_STR | CDS_STR | START_CODON_STR | STOP_CODON_STR => {
+                        let parents = attributes.remove(GFF3_PARENT_STR)
+                            .ok_or_else(|| attributes_err(GffError::MissingTranscriptId))?;
+                        children.push((row, pos.clone(), parents, attributes));
+                    },
+                    _ if lossless => {
+                        if let Some(parents) = attributes.remove(GFF3_PARENT_STR) {
+                            children.push((row, pos.clone(), parents, attributes));
+                        }
+                    },
+                    _ => {},
+                }
+                Ok(())
+            });
+            if let Err(e) = row_result {
+                defer_error(policy, &mut row_errors, e)?;
+            }
+        }
+
+        for (row, pos, parents, attributes) in children {
+            for tid in parents {
+                match trx_gene_ids.get(&tid) {
+                    Some(gid) => {
+                        let (gid, attribs) = (gid.clone(), attributes.clone());
+                        parts.push(TrxPart::from_gff3_row(&row, &pos, row.2.as_str(), tid,
+                                                          gid, attribs));
+                    },
+                    None if record_ids.contains(&tid) => {},
+                    None => {
+                        let err = GffError::MissingTranscript(Some(tid));
+                        let err = pos.error(Some(ATTRIBUTES_FIELD), err);
+                        defer_error(policy, &mut row_errors, err)?;
+                    },
+                }
+            }
+        }
+
+        Ok((parts, gene_records, row_errors))
     }
 
-    /// Creates an iterator of GFF rows.
+    /// Creates an iterator of GFF rows, along with the locations of their lines.
     pub(crate) fn raw_rows_stream(&mut self) -> GffRawRows<R> {
         GffRawRows {
-            inner: self.inner.raw_rows()
+            inner: &mut self.inner,
+            finished: false,
         }
     }
 }
 
-impl Reader<fs::File> {
+impl Reader<GzipReader<fs::File>> {
 
-    /// Creates a refFlat reader that reads from the given path.
+    /// Creates a GFF reader that reads from the given path.
+    ///
+    /// Gzip and BGZF-compressed files are decompressed transparently.
     pub fn from_file<P: AsRef<Path>>(path: P, gff_type: GffType) -> io::Result<Self> {
-        fs::File::open(path).map(|file| Reader::from_reader(file, gff_type))
+        GzipReader::from_file(path).map(|file| Reader::from_reader(file, gff_type))
+    }
+}
+
+/// GFF reader of BGZF-compressed files with a tabix index.
+///
+/// Unlike `Reader`, this reader only reads the records needed for the transcripts in a given
+/// region, which makes it suitable for looking up single loci.
+pub struct IndexedReader<R: io::Read + io::Seek> {
+    inner: TabixReader<R>,
+    gene_id_attr: String,
+    transcript_id_attr: String,
+    loose_codons: bool,
+    lossless: bool,
+    gff_type: GffType,
+}
+
+impl<R: io::Read + io::Seek> IndexedReader<R> {
+
+    /// Creates an indexed GFF reader of the given variant from a tabix reader.
+    pub fn from_reader(in_reader: TabixReader<R>, gff_type: GffType) -> IndexedReader<R> {
+        IndexedReader {
+            inner: in_reader,
+            gene_id_attr: GENE_ID_STR.to_owned(),
+            transcript_id_attr: TRANSCRIPT_ID_STR.to_owned(),
+            loose_codons: false,
+            lossless: false,
+            gff_type: gff_type,
+        }
+    }
+
+    /// Sets the reader to use the given attribute key for getting gene identifiers.
+    pub fn gene_id_attr<T>(&mut self, gene_id_attr: T) -> &mut Self
+        where T: Into<String>
+    {
+        self.gene_id_attr = gene_id_attr.into();
+        self
+    }
+
+    /// Sets the reader to use the given attribute key for getting transcript identifiers.
+    pub fn transcript_id_attr<T>(&mut self, transcript_id_attr: T) -> &mut Self
+        where T: Into<String>
+    {
+        self.transcript_id_attr = transcript_id_attr.into();
+        self
+    }
+
+    /// Sets the reader to use CDS coordinates when start and/or stop codons for transcripts
+    /// can not be found.
+    pub fn loose_codons(&mut self, loose_codons: bool) -> &mut Self {
+        self.loose_codons = loose_codons;
+        self
+    }
+
+    /// Sets the reader to keep values that are otherwise discarded when creating models.
+    ///
+    /// See `Reader::lossless` for the values that are kept.
+    pub fn lossless(&mut self, lossless: bool) -> &mut Self {
+        self.lossless = lossless;
+        self
+    }
+
+    /// Returns the transcripts that overlap the given zero-based, half-open interval of a
+    /// sequence.
+    ///
+    /// A transcript is returned when any of its records overlaps the interval. Its records
+    /// outside of the interval are read as well: the region that is read is extended until it
+    /// contains all records of the transcripts, so that transcripts straddling the edges of the
+    /// interval are complete. The parent records of the transcripts, such as their gene records,
+    /// are read along with them. Records are linked to their transcripts as they are by
+    /// `Reader::transcripts`. A transcript whose intron spans the whole interval is therefore
+    /// only returned when the input has transcript records.
+    ///
+    /// The first error of a record or transcript is returned instead of the transcripts. Its
+    /// line number is the number of the record among the records read for the interval.
+    pub fn fetch(&mut self, seq_name: &str, start: u64, end: u64) -> ::Result<Vec<Transcript>> {
+        let mut lines = self.inner.fetch(seq_name, start, end)?;
+        let mut ids = HashSet::new();
+        let mut invalid_lines = Vec::new();
+        for line in lines.iter() {
+            match self.record_ids(line) {
+                Some(linked) => ids.extend(linked.ids),
+                None => invalid_lines.push(line.clone()),
+            }
+        }
+
+        let mut region = (start, end);
+        let mut records;
+        loop {
+            records = lines.into_iter()
+                .filter_map(|line| self.record_ids(&line).map(|linked| (line, linked)))
+                .filter(|&(_, ref linked)| linked.ids.iter().any(|id| ids.contains(id)))
+                .collect::<Vec<(String, LinkedIds)>>();
+
+            // Parents are linked by their own identifiers, so they are only found once the
+            // identifiers of their children are known.
+            let num_ids = ids.len();
+            for &(_, ref linked) in records.iter() {
+                ids.extend(linked.parent_ids.iter().cloned());
+            }
+            let extent = records.iter()
+                .fold(region, |acc, &(_, ref linked)| {
+                    (min(acc.0, linked.coord.0), max(acc.1, linked.coord.1))
+                });
+            if extent == region && ids.len() == num_ids {
+                break;
+            }
+            region = extent;
+            lines = self.inner.fetch(seq_name, region.0, region.1)?;
+        }
+
+        let raw = invalid_lines.into_iter()
+            .chain(records.into_iter().map(|(line, _)| line))
+            .collect::<Vec<String>>()
+            .join("\n");
+        let mut reader = Reader::from_reader(raw.as_bytes(), self.gff_type);
+        let _ = reader.gene_id_attr(self.gene_id_attr.as_str())
+            .transcript_id_attr(self.transcript_id_attr.as_str())
+            .loose_codons(self.loose_codons)
+            .lossless(self.lossless)
+            .error_policy(ErrorPolicy::Strict);
+        let transcripts = reader.transcripts()?.collect::<::Result<Vec<Transcript>>>()?;
+        Ok(transcripts)
+    }
+
+    /// Returns the coordinates of a record and the identifiers that link it to transcripts and
+    /// their parents.
+    ///
+    /// A record is linked by the identifiers of transcripts for transcript records and by the
+    /// identifiers of their parents for other records. For GFF3 input, gene records are linked
+    /// by their own identifiers and transcript records name their genes as parents. For GTF
+    /// input, gene records are linked by their gene identifiers, which all other records name
+    /// as parents. Returns `None` if the record can not be parsed.
+    fn record_ids(&self, line: &str) -> Option<LinkedIds> {
+        let pos = LinePos { line: 0, raw: line.to_owned() };
+        let row = match parse_row(&pos) {
+            Ok(row) => row,
+            Err(_) => return None,
+        };
+        let attributes = match parse_attributes(&row.8, self.gff_type) {
+            Ok(attributes) => attributes,
+            Err(_) => return None,
+        };
+        let feature = row.2.as_str();
+        let (id_key, parent_key) = match self.gff_type {
+            GffType::GFF3 if GFF3_TRANSCRIPT_STRS.contains(&feature) =>
+                (GFF3_ID_STR, Some(GFF3_PARENT_STR)),
+            GffType::GFF3 if GFF3_GENE_STRS.contains(&feature) => (GFF3_ID_STR, None),
+            GffType::GFF3 => (GFF3_PARENT_STR, None),
+            _ if feature == GENE_STR => (self.gene_id_attr.as_str(), None),
+            _ => (self.transcript_id_attr.as_str(), Some(self.gene_id_attr.as_str())),
+        };
+        let get_ids = |key: &str| attributes.get_vec(key).cloned().unwrap_or_else(Vec::new);
+        Some(LinkedIds {
+            coord: (row.3, row.4),
+            ids: get_ids(id_key),
+            parent_ids: parent_key.map(&get_ids).unwrap_or_else(Vec::new),
+        })
+    }
+}
+
+/// Coordinates of a record and the identifiers linking it to other records.
+struct LinkedIds {
+    coord: Coord<u64>,
+    ids: Vec<String>,
+    parent_ids: Vec<String>,
+}
+
+impl IndexedReader<fs::File> {
+
+    /// Creates an indexed GFF reader of the BGZF-compressed file at the given path.
+    ///
+    /// The index is read from the path with a `.tbi` or `.csi` extension added.
+    pub fn from_file<P: AsRef<Path>>(path: P, gff_type: GffType) -> ::Result<Self> {
+        TabixReader::from_file(path).map(|reader| IndexedReader::from_reader(reader, gff_type))
     }
 }
 
-/// Iterator over GFF rows.
+/// Raw GFF row type.
+///
+/// Each tuple element represents a GFF column: sequence name, source, feature, start, end, score,
+/// strand, frame, and attributes. Unlike in the GFF file, the start coordinate is zero-based.
+pub(crate) type RawRow = (String, String, String, u64, u64, String, Strand, String, String);
+
+/// Iterator over GFF rows, along with the locations of their lines.
+///
+/// Empty and comment lines are skipped. Lines after a `##FASTA` directive are not read.
 pub(crate) struct GffRawRows<'a, R: 'a> where R: io::Read {
-    inner: gff::RawRows<'a, R>,
+    inner: &'a mut LineReader<R>,
+    finished: bool,
 }
 
 impl<'a, R> Iterator for GffRawRows<'a, R> where R: io::Read {
 
-    type Item = ::Result<gff::RawRow>;
+    type Item = ::Result<(RawRow, LinePos)>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.inner.next()
-            .map(|row| row.map_err(|e| ::Error::from(GffError::from(e))))
+        if self.finished {
+            return None;
+        }
+        while let Some(result) = self.inner.next_line(|e| ::Error::from(GffError::from(e))) {
+            let pos = match result {
+                Ok(pos) => pos,
+                Err(e) => return Some(Err(e)),
+            };
+            if pos.raw.starts_with(FASTA_DIRECTIVE) {
+                break;
+            }
+            if pos.raw.trim().is_empty() || pos.raw.starts_with('#') {
+                continue;
+            }
+            return Some(parse_row(&pos).map(|row| (row, pos)));
+        }
+        self.finished = true;
+        None
+    }
+}
+
+/// Helper function to parse the columns of a GFF line.
+///
+/// Errors are located at the line and the offending column.
+fn parse_row(pos: &LinePos) -> ::Result<RawRow> {
+    let mut cols = pos.columns();
+    if cols.len() != NUM_COLS {
+        return Err(pos.error(None, GffError::InvalidColumnCount(cols.len())));
     }
+    let start = match u64::from_str(&cols[3]) {
+        Ok(start) if start > 0 => start - 1,
+        _ => return Err(pos.error(Some("start"), GffError::InvalidCoord(cols[3].clone()))),
+    };
+    let end = match u64::from_str(&cols[4]) {
+        Ok(end) => end,
+        _ => return Err(pos.error(Some("end"), GffError::InvalidCoord(cols[4].clone()))),
+    };
+    let strand = {
+        let mut strand_chars = cols[6].chars();
+        match (strand_chars.next(), strand_chars.next()) {
+            (Some(c), None) => Strand::from_char(&c).ok(),
+            _ => None,
+        }
+    };
+    let strand = match strand {
+        Some(strand) => strand,
+        None => return Err(pos.error(Some("strand"), GffError::InvalidStrand(cols[6].clone()))),
+    };
+
+    let mut take = |idx: usize| mem::replace(&mut cols[idx], String::new());
+    Ok((take(0), take(1), take(2), start, end, take(5), strand, take(7), take(8)))
 }
 
 /// Helper struct for creating transcripts.
 ///
-/// This struct is meant to be used when complete parsing of the GFF attribute column is not
-/// required. Instead, only the minimum required values (gene and transcript identifiers) are
-/// parsed.
-#[derive(Debug, PartialEq)]
+/// The gene and transcript identifiers are stored separately from the rest of the attributes,
+/// which are used as the attributes of the transcript and its exons.
+#[derive(Debug)]
 struct TrxPart {
     feature: String,
     chrom: String,
@@ -286,6 +910,12 @@ struct TrxPart {
     strand: Strand,
     transcript_id: String,
     gene_id: String,
+    source: String,
+    score: String,
+    frame: Option<u8>,
+    attributes: MultiMap<String, String>,
+    /// Location of the row, for reporting errors of the transcript.
+    pos: LinePos,
 }
 
 /// The type used for sorting GFF records.
@@ -294,46 +924,110 @@ struct TrxPart {
 /// coordinate, end coordinate, end strand.
 type TrxSortKey = (String, String, String, u64, u64, u8);
 
+/// The type used for sorting GFF records into genes.
+///
+/// The tuple elements represent gene identifier, sequence, strand, transcript identifier, start
+/// coordinate, and end coordinate.
+type GeneSortKey = (String, String, u8, String, u64, u64);
+
 impl TrxPart {
 
-    /// Creates a `TrxPart` from the given GFF row and the gene and transcript identifier regexes.
+    /// Creates a `TrxPart` from the given GFF row and the gene and transcript identifier
+    /// attribute keys.
+    ///
+    /// The identifier attributes are not stored in the part attributes, since they are already
+    /// stored as the gene and transcript identifiers.
     fn try_from_row(
-        row: gff::RawRow,
-        gx_regex: &Regex,
-        trx_regex: &Regex,
+        row: RawRow,
+        pos: &LinePos,
+        gid_attr: &str,
+        tid_attr: &str,
+        gff_type: GffType,
     ) -> Result<Self, GffError> {
 
-        let gx_id = gx_regex.captures(&row.8)
-            .and_then(|cap| cap.name("value"))
-            .map(|v| v.as_str().to_owned())
+        let mut attributes = parse_attributes(&row.8, gff_type)?;
+
+        let gx_id = attributes.remove(gid_attr)
+            .and_then(|vs| vs.into_iter().next())
             .ok_or(GffError::MissingGeneId)?;
 
-        let trx_id = trx_regex.captures(&row.8)
-            .and_then(|cap| cap.name("value"))
-            .map(|v| v.as_str().to_owned())
-            .ok_or(GffError::MissingTranscriptId)?;
+        let trx_id = match attributes.remove(tid_attr) {
+            Some(ref vs) if vs.len() > 1 => return Err(GffError::MultipleTranscriptIds),
+            Some(mut vs) => vs.pop().ok_or(GffError::MissingTranscriptId)?,
+            None => return Err(GffError::MissingTranscriptId),
+        };
 
         Ok(TrxPart {
+            frame: row.7.parse().ok(),
             feature: row.2,
             chrom: row.0,
-            coord: (row.3 - 1, row.4),
-            strand: Strand::from_char(&row.6).unwrap(),
+            coord: (row.3, row.4),
+            strand: row.6,
             transcript_id: trx_id,
             gene_id: gx_id,
+            source: row.1,
+            score: row.5,
+            attributes: attributes,
+            pos: pos.clone(),
         })
     }
 
+    /// Creates a `TrxPart` from the given GFF3 row, feature name, the already-resolved
+    /// transcript and gene identifiers, and the parsed attributes.
+    fn from_gff3_row(
+        row: &RawRow,
+        pos: &LinePos,
+        feature: &str,
+        trx_id: String,
+        gx_id: String,
+        attributes: MultiMap<String, String>,
+    ) -> Self {
+        TrxPart {
+            feature: feature.to_owned(),
+            chrom: row.0.clone(),
+            coord: (row.3, row.4),
+            strand: row.6,
+            transcript_id: trx_id,
+            gene_id: gx_id,
+            source: row.1.clone(),
+            score: row.5.clone(),
+            frame: row.7.parse().ok(),
+            attributes: attributes,
+            pos: pos.clone(),
+        }
+    }
+
+    /// Adds the line number of the part to the lines of its transcript and gene identifiers.
+    fn add_id_lines(&self, id_lines: &mut IdLines) {
+        id_lines.add(&self.transcript_id, self.pos.line);
+        id_lines.add(&self.gene_id, self.pos.line);
+        if self.feature == TRANSCRIPT_STR {
+            id_lines.add_transcript_row(&self.transcript_id);
+        }
+    }
+
     /// Returns a tuple of sorting key.
     fn sort_key(&self) -> TrxSortKey {
         (self.gene_id.clone(), self.transcript_id.clone(),
          self.chrom.clone(), self.coord.0, self.coord.1, self.strand_ord())
     }
 
+    /// Returns a tuple of sorting key for grouping into genes.
+    fn gene_sort_key(&self) -> GeneSortKey {
+        (self.gene_id.clone(), self.chrom.clone(), self.strand_ord(),
+         self.transcript_id.clone(), self.coord.0, self.coord.1)
+    }
+
     /// Returns a tuple of grouping key.
     fn transcript_group_key(&self) -> TrxGroupKey {
         (self.gene_id.clone(), self.transcript_id.clone(), self.chrom.clone(), self.strand)
     }
 
+    /// Returns a tuple of grouping key for genes.
+    fn gene_group_key(&self) -> GeneGroupKey {
+        (self.gene_id.clone(), self.chrom.clone(), self.strand)
+    }
+
     /// Returns the u8 value for distinguishing strands.
     fn strand_ord(&self) -> u8 {
         match &self.strand {
@@ -396,10 +1090,14 @@ impl TrxCoords {
     }
 
     /// Returns coordinates required to create a transcript.
+    ///
+    /// If `infer_codons` is true and no codon coordinates are present, the 5' and 3'-most CDS
+    /// coordinates are used as the coding coordinates.
     fn resolve<'a>(
         self,
         strand: Strand,
         loose_codons: bool,
+        infer_codons: bool,
         tid: Option<&'a str>
     ) -> Result<RawTrxCoords, GffError> {
 
@@ -409,6 +1107,10 @@ impl TrxCoords {
         let coding_coord = match (self.codon_5, self.codon_3) {
             // common case: stop and start codon defined
             (Some(c5), Some(c3)) => Some((c5, c3)),
+            // GFF3 case: no codons defined, but CDS includes the stop codon
+            (None, None) if infer_codons => {
+                return Ok((trx_coord, self.exon_coords, self.cds_coord));
+            },
             // expected case: no stop and start codon defined
             (None, None) => None,
             // error case: only stop or start codon defined
@@ -449,10 +1151,20 @@ impl TrxCoords {
     }
 }
 
+/// Options for creating transcripts from GFF records.
+#[derive(Debug, Clone, Copy)]
+struct TrxOpts {
+    loose_codons: bool,
+    infer_codons: bool,
+    lossless: bool,
+}
+
 /// Iterator over transcripts created from GFF records.
-pub struct GffTranscripts {
+pub struct GffTranscripts<'a> {
+    row_errors: vec::IntoIter<::Error>,
     groups: GroupBy<TrxGroupKey, vec::IntoIter<TrxPart>, TrxGroupFunc>,
-    loose_codons: bool,
+    opts: TrxOpts,
+    handler: ErrorHandler<'a>,
 }
 
 /// The type used for grouping records into transcripts.
@@ -466,69 +1178,739 @@ type TrxGroupFunc = fn(&TrxPart) -> TrxGroupKey;
 /// The type of the grouped records for creating transcripts.
 type TrxGroup<'a> = Group<'a, TrxGroupKey, vec::IntoIter<TrxPart>, TrxGroupFunc>;
 
-impl Iterator for GffTranscripts {
+impl<'a> GffTranscripts<'a> {
+
+    /// Returns the identifier and the result of the next transcript.
+    fn next_transcript(&mut self) -> Option<(Option<String>, ::Result<Transcript>)> {
+        let opts = self.opts;
+        self.groups.into_iter()
+            .map(|(key, tps): (TrxGroupKey, TrxGroup)| {
+                (Some(key.1.clone()), parts_to_transcript(key, tps, opts))
+            })
+            .next()
+    }
+}
+
+impl<'a> Iterator for GffTranscripts<'a> {
 
     type Item = ::Result<Transcript>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let group_to_transcript = |(key, tps): (TrxGroupKey, TrxGroup)| {
-            let (gid, tid, chrom, strand) = key;
-            let mut tc = TrxCoords::default();
-
-            for tp in tps {
-                match (tp.feature.as_str(), strand) {
-                    (TRANSCRIPT_STR, _) => {
-                        tc.set_trx_coord(tp.coord)
-                            .map_err(::Error::from)?;
-                    },
-                    (EXON_STR, _) => {
-                        tc.add_exon_coord(tp.coord);
-                    },
-                    (CDS_STR, _) => {
-                        tc.include_cds_coord(tp.coord);
-                    },
-                    (START_CODON_STR, Strand::Forward) | (STOP_CODON_STR, Strand::Reverse) => {
-                        tc.include_codon_5(tp.coord.0);
-                    },
-                    (STOP_CODON_STR, Strand::Forward) | (START_CODON_STR, Strand::Reverse) => {
-                        tc.include_codon_3(tp.coord.1);
-                    },
-                    _ => {},
+        while !self.handler.is_stopped() {
+            let (tid, result) = match self.row_errors.next() {
+                Some(err) => (None, Err(err)),
+                None => match self.next_transcript() {
+                    Some(next) => next,
+                    None => return None,
+                },
+            };
+            if let Some(result) = self.handler.handle(tid, result) {
+                return Some(result);
+            }
+        }
+        None
+    }
+}
+
+/// Streaming iterator over transcripts created from grouped GFF records.
+pub struct GffTranscriptsStream<'a, R: 'a> where R: io::Read {
+    inner: GffRawRows<'a, R>,
+    gene_id_attr: &'a str,
+    transcript_id_attr: &'a str,
+    gff_type: GffType,
+    seq_name_prefix: Option<&'a str>,
+    seq_name_lstrip: Option<&'a str>,
+    opts: TrxOpts,
+    group_key: Option<TrxGroupKey>,
+    group: Vec<TrxPart>,
+    pending: vec::IntoIter<TrxPart>,
+    /// Sequence name of the last row.
+    seq_name: Option<String>,
+    /// Sequence names and identifiers of the transcripts that have been created.
+    closed_ids: HashSet<(String, String)>,
+    /// Identifiers of all GFF3 records in the current sequence.
+    record_ids: HashSet<String>,
+    /// Gene identifiers of the GFF3 transcripts in the current sequence.
+    trx_gene_ids: HashMap<String, String>,
+    /// Line numbers of the identifiers, if they are recorded.
+    id_lines: Option<&'a mut IdLines>,
+    handler: ErrorHandler<'a>,
+}
+
+impl<'a, R> GffTranscriptsStream<'a, R> where R: io::Read {
+
+    /// Returns the next transcript part from the underlying GFF rows.
+    ///
+    /// Rows of features not used for creating transcripts are skipped. The GFF3 record
+    /// identifiers seen so far are forgotten whenever the sequence name changes, since parents
+    /// and their children are always in the same sequence.
+    fn next_part(&mut self) -> Option<::Result<TrxPart>> {
+        let lstrip = self.seq_name_lstrip.map(|v| (v, v.len()));
+        let prefix = self.seq_name_prefix;
+        loop {
+            if let Some(part) = self.pending.next() {
+                return Some(Ok(part));
+            }
+            let (mut row, pos) = match self.inner.next() {
+                Some(Ok(located_row)) => located_row,
+                Some(Err(e)) => return Some(Err(e)),
+                None => return None,
+            };
+            update_seq_name(&mut row.0, prefix, lstrip);
+            if self.seq_name.as_ref() != Some(&row.0) {
+                self.seq_name = Some(row.0.clone());
+                self.record_ids.clear();
+                self.trx_gene_ids.clear();
+            }
+            let parts = match self.gff_type {
+                GffType::GFF3 => self.gff3_row_parts(row, &pos),
+                _ => self.row_parts(row, &pos),
+            };
+            match parts {
+                Ok(parts) => {
+                    if let Some(ref mut id_lines) = self.id_lines {
+                        for part in parts.iter() {
+                            part.add_id_lines(id_lines);
+                        }
+                    }
+                    self.pending = parts.into_iter();
+                },
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+
+    /// Creates the transcript parts of the given GTF or GFF2 row.
+    ///
+    /// The gene and transcript identifier attributes link the parts to their transcripts.
+    fn row_parts(&self, row: RawRow, pos: &LinePos) -> ::Result<Vec<TrxPart>> {
+        let (gid_attr, tid_attr) = (self.gene_id_attr, self.transcript_id_attr);
+        match row.2.as_str() {
+            TRANSCRIPT_STR | EXON_STR | CDS_STR | START_CODON_STR | STOP_CODON_STR => {
+                TrxPart::try_from_row(row, pos, gid_attr, tid_attr, self.gff_type)
+                    .map(|part| vec![part])
+                    .map_err(|e| pos.error(Some(ATTRIBUTES_FIELD), e))
+            },
+            GENE_STR => Ok(vec![]),
+            _ if self.opts.lossless => {
+                match TrxPart::try_from_row(row, pos, gid_attr, tid_attr, self.gff_type) {
+                    Ok(part) => Ok(vec![part]),
+                    Err(GffError::MissingGeneId) | Err(GffError::MissingTranscriptId) =>
+                        Ok(vec![]),
+                    Err(e) => Err(pos.error(Some(ATTRIBUTES_FIELD), e)),
                 }
+            },
+            _ => Ok(vec![]),
+        }
+    }
+
+    /// Creates the transcript parts of the given GFF3 row.
+    ///
+    /// As in `Reader::transcripts`, the `ID` and `Parent` attributes link the parts to their
+    /// transcripts. Since the rows are not read in advance, transcripts must precede their
+    /// exons, CDSes, and codons.
+    fn gff3_row_parts(&mut self, row: RawRow, pos: &LinePos) -> ::Result<Vec<TrxPart>> {
+        let attributes_err = |err: GffError| pos.error(Some(ATTRIBUTES_FIELD), err);
+        let mut attributes = parse_attributes(&row.8, GffType::GFF3)
+            .map_err(&attributes_err)?;
+        let rec_id = attributes.get(GFF3_ID_STR).cloned();
+        if let Some(ref id) = rec_id {
+            let _ = self.record_ids.insert(id.clone());
+        }
+
+        let feature = row.2.clone();
+        let parents = match feature.as_str() {
+            ft if GFF3_TRANSCRIPT_STRS.contains(&ft) => {
+                let tid = rec_id.ok_or_else(|| attributes_err(GffError::MissingTranscriptId))?;
+                let gid = attributes.get(GFF3_PARENT_STR).cloned()
+                    .ok_or_else(|| attributes_err(GffError::MissingGeneId))?;
+                let _ = attributes.remove(GFF3_ID_STR);
+                let _ = attributes.remove(GFF3_PARENT_STR);
+                let _ = self.trx_gene_ids.insert(tid.clone(), gid.clone());
+                return Ok(vec![TrxPart::from_gff3_row(&row, pos, TRANSCRIPT_STR, tid, gid,
+                                                      attributes)]);
+            },
+            EXON_STR | CDS_STR | START_CODON_STR | STOP_CODON_STR =>
+                attributes.remove(GFF3_PARENT_STR)
+                    .ok_or_else(|| attributes_err(GffError::MissingTranscriptId))?,
+            ft if self.opts.lossless && !GFF3_GENE_STRS.contains(&ft) =>
+                match attributes.remove(GFF3_PARENT_STR) {
+                    Some(parents) => parents,
+                    None => return Ok(vec![]),
+                },
+            _ => return Ok(vec![]),
+        };
+
+        let mut parts = Vec::new();
+        for tid in parents {
+            match self.trx_gene_ids.get(&tid) {
+                Some(gid) => parts.push(TrxPart::from_gff3_row(&row, pos, feature.as_str(),
+                                                               tid, gid.clone(),
+                                                               attributes.clone())),
+                None if self.record_ids.contains(&tid) => {},
+                None => return Err(attributes_err(GffError::MissingTranscript(Some(tid)))),
+            }
+        }
+        Ok(parts)
+    }
+
+    /// Creates a transcript from the currently grouped parts, if any.
+    fn finish_group(&mut self) -> Option<(Option<String>, ::Result<Transcript>)> {
+        let opts = self.opts;
+        let parts = mem::replace(&mut self.group, Vec::new());
+        let closed_ids = &mut self.closed_ids;
+        self.group_key.take()
+            .map(|key| {
+                let _ = closed_ids.insert((key.2.clone(), key.1.clone()));
+                (Some(key.1.clone()), parts_to_transcript(key, parts, opts))
+            })
+    }
+
+    /// Returns the identifier and the result of the next transcript.
+    fn next_transcript(&mut self) -> Option<(Option<String>, ::Result<Transcript>)> {
+        loop {
+            let part = match self.next_part() {
+                Some(Ok(part)) => part,
+                Some(Err(e)) => return Some((None, Err(e))),
+                None => return self.finish_group(),
+            };
+            let closed_key = (part.chrom.clone(), part.transcript_id.clone());
+            if self.closed_ids.contains(&closed_key) {
+                let err = GffError::UngroupedTranscript(Some(part.transcript_id.clone()));
+                return Some((Some(part.transcript_id), Err(part.pos.error(None, err))));
+            }
+            let part_key = part.transcript_group_key();
+            let in_group = self.group_key.as_ref()
+                .map(|key| key == &part_key)
+                .unwrap_or(true);
+            if in_group {
+                self.group_key = Some(part_key);
+                self.group.push(part);
+            } else {
+                let finished = self.finish_group();
+                self.group_key = Some(part_key);
+                self.group.push(part);
+                return finished;
+            }
+        }
+    }
+}
+
+impl<'a, R> Iterator for GffTranscriptsStream<'a, R> where R: io::Read {
+
+    type Item = ::Result<Transcript>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.handler.is_stopped() {
+            let (tid, result) = match self.next_transcript() {
+                Some(next) => next,
+                None => return None,
+            };
+            if let Some(result) = self.handler.handle(tid, result) {
+                return Some(result);
             }
+        }
+        None
+    }
+}
+
+/// Creates a transcript from the given group key and its grouped GFF records.
+///
+/// Errors are returned along with the location of the first record of the group.
+fn parts_to_transcript<I>(key: TrxGroupKey, parts: I, opts: TrxOpts) -> ::Result<Transcript>
+    where I: IntoIterator<Item=TrxPart>
+{
+    let mut parts = parts.into_iter().peekable();
+    let pos = parts.peek().map(|tp| tp.pos.clone());
+    build_transcript(key, parts, opts)
+        .map_err(|e| match pos {
+            Some(ref pos) => pos.error(None, e),
+            None => e,
+        })
+}
 
-            let ((trx_start, trx_end), exn_coords, coding_coord) =
-                tc.resolve(strand, self.loose_codons, Some(tid.as_str()))
+/// Helper function to create a transcript from the given group key and its grouped GFF
+/// records, without the location of any error.
+fn build_transcript<I>(key: TrxGroupKey, parts: I, opts: TrxOpts) -> ::Result<Transcript>
+    where I: Iterator<Item=TrxPart>
+{
+    let (gid, tid, chrom, strand) = key;
+    let mut tc = TrxCoords::default();
+    let mut trx_attribs = MultiMap::new();
+    let mut exn_attribs = HashMap::new();
+    let mut fx_parts = Vec::new();
+
+    for mut tp in parts {
+        if opts.lossless {
+            insert_source_score(&mut tp.attributes, &tp.source, &tp.score);
+        }
+        match tp.feature.as_str() {
+            TRANSCRIPT_STR | EXON_STR => {},
+            _ if opts.lossless =>
+                fx_parts.push((tp.feature.clone(), tp.coord, tp.frame, tp.attributes.clone())),
+            _ => {},
+        }
+        match (tp.feature.as_str(), strand) {
+            (TRANSCRIPT_STR, _) => {
+                tc.set_trx_coord(tp.coord)
                     .map_err(::Error::from)?;
+                trx_attribs = tp.attributes;
+            },
+            (EXON_STR, _) => {
+                tc.add_exon_coord(tp.coord);
+                let _ = exn_attribs.insert(tp.coord, tp.attributes);
+            },
+            (CDS_STR, _) => {
+                tc.include_cds_coord(tp.coord);
+            },
+            (START_CODON_STR, Strand::Forward) | (STOP_CODON_STR, Strand::Reverse) => {
+                tc.include_codon_5(tp.coord.0);
+            },
+            (STOP_CODON_STR, Strand::Forward) | (START_CODON_STR, Strand::Reverse) => {
+                tc.include_codon_3(tp.coord.1);
+            },
+            _ => {},
+        }
+    }
+
+    let ((trx_start, trx_end), exn_coords, coding_coord) =
+        tc.resolve(strand, opts.loose_codons, opts.infer_codons, Some(tid.as_str()))
+            .map_err(::Error::from)?;
+
+    if opts.lossless {
+        let (exons, features) = record_exons(&chrom, strand, &tid, &gid, exn_coords,
+                                             exn_attribs, fx_parts)?;
+        return TBuilder::new(chrom, trx_start, trx_end)
+            .id(tid)
+            .gene_id(gid)
+            .strand(strand)
+            .attributes(trx_attribs)
+            .exons(exons)
+            .features(features)
+            .build();
+    }
+
+    let mut transcript = TBuilder::new(chrom, trx_start, trx_end)
+        .id(tid)
+        .gene_id(gid)
+        .strand(strand)
+        .attributes(trx_attribs)
+        .coords(exn_coords, coding_coord)
+        .coding_incl_stop(true)
+        .build()?;
+
+    for exon in transcript.exons_mut().iter_mut() {
+        if let Some(attribs) = exn_attribs.remove(&(exon.start(), exon.end())) {
+            let _ = exon.set_attributes(attribs);
+        }
+    }
+
+    Ok(transcript)
+}
+
+/// Helper function to create exons whose features are created as-is from GFF records.
+///
+/// Each feature record becomes a feature of the exon it lies within, keeping the attributes of
+/// the record. Feature records that do not lie within any exon are returned as transcript
+/// features.
+fn record_exons(
+    chrom: &str,
+    strand: Strand,
+    tid: &str,
+    gid: &str,
+    mut exn_coords: Vec<Coord<u64>>,
+    mut exn_attribs: HashMap<Coord<u64>, MultiMap<String, String>>,
+    fx_parts: Vec<(String, Coord<u64>, Option<u8>, MultiMap<String, String>)>,
+) -> ::Result<(Vec<Exon>, Vec<TranscriptFeature>)> {
+
+    exn_coords.sort();
+    let mut exn_features: Vec<Vec<ExonFeature>> = exn_coords.iter().map(|_| Vec::new()).collect();
+    let mut trx_features = Vec::new();
+
+    for (feature, (start, end), frame, attribs) in fx_parts {
+        let interval = Interval::new(start..end)
+            .map_err(|e| ::Error::from(ModelError::from(e)))?;
+        let exn_idx = exn_coords.iter()
+            .position(|&(exn_start, exn_end)| exn_start <= start && end <= exn_end);
+        match exn_idx {
+            Some(idx) => {
+                let mut fx = Feature::new(interval, EFK::from_feature_frame(feature, frame));
+                let _ = fx.set_attributes(attribs);
+                exn_features[idx].push(fx);
+            },
+            None => {
+                let mut fx = Feature::new(interval, TFK::Any(feature));
+                let _ = fx.set_attributes(attribs);
+                trx_features.push(fx);
+            },
+        }
+    }
+
+    let mut exons = Vec::with_capacity(exn_coords.len());
+    for ((exn_start, exn_end), features) in exn_coords.into_iter().zip(exn_features) {
+        let attribs = exn_attribs.remove(&(exn_start, exn_end)).unwrap_or_else(MultiMap::new);
+        let exon = EBuilder::new(chrom, exn_start, exn_end)
+            .strand(strand)
+            .transcript_id(tid)
+            .gene_id(gid)
+            .attributes(attribs)
+            .features(features)
+            .build()?;
+        exons.push(exon);
+    }
+
+    Ok((exons, trx_features))
+}
+
+/// Helper struct for storing values of GFF gene records.
+#[derive(Debug, Clone)]
+struct GeneRecord {
+    coord: Coord<u64>,
+    source: String,
+    score: String,
+    attributes: MultiMap<String, String>,
+    /// Line number of the gene row.
+    line: usize,
+}
+
+/// The type used for looking up gene records.
+///
+/// The tuple elements represent gene identifier and sequence name.
+type GeneRecordKey = (String, String);
+
+/// The type of all GFF records read into memory.
+///
+/// The tuple elements represent transcript parts, gene records, and errors of skipped rows.
+type ReadParts = (Vec<TrxPart>, HashMap<GeneRecordKey, GeneRecord>, Vec<::Error>);
+
+impl GeneRecord {
+
+    /// Creates a gene record key and a `GeneRecord` from the given GFF row.
+    ///
+    /// The gene identifier attribute is not stored in the record attributes, since it is
+    /// already stored as the gene identifier.
+    fn try_from_row(
+        row: RawRow,
+        pos: &LinePos,
+        gid_attr: &str,
+        gff_type: GffType,
+    ) -> Result<(GeneRecordKey, Self), GffError> {
+
+        let mut attributes = parse_attributes(&row.8, gff_type)?;
+
+        let gx_id = attributes.remove(gid_attr)
+            .and_then(|vs| vs.into_iter().next())
+            .ok_or(GffError::MissingGeneId)?;
+
+        let record = GeneRecord {
+            coord: (row.3, row.4),
+            source: row.1,
+            score: row.5,
+            attributes: attributes,
+            line: pos.line,
+        };
+        Ok(((gx_id, row.0), record))
+    }
+}
+
+/// Iterator over genes created from GFF records.
+pub struct GffGenes<'a> {
+    row_errors: vec::IntoIter<::Error>,
+    groups: GroupBy<GeneGroupKey, vec::IntoIter<TrxPart>, GeneGroupFunc>,
+    gene_records: HashMap<GeneRecordKey, GeneRecord>,
+    pending: vec::IntoIter<GeneResult>,
+    opts: TrxOpts,
+    handler: ErrorHandler<'a>,
+}
+
+/// The type of the results created from grouped records, along with their identifiers.
+type GeneResult = (Option<String>, ::Result<Gene>);
+
+/// The type used for grouping records into genes.
+///
+/// The tuple elements represent gene identifier, sequence name, and strand.
+type GeneGroupKey = (String, String, Strand);
+
+/// The type of the function used for creating record-grouping keys for genes.
+type GeneGroupFunc = fn(&TrxPart) -> GeneGroupKey;
 
-            TBuilder::new(chrom, trx_start, trx_end)
-                .id(tid)
-                .gene_id(gid)
+/// The type of the grouped records for creating genes.
+type GeneGroup<'a> = Group<'a, GeneGroupKey, vec::IntoIter<TrxPart>, GeneGroupFunc>;
+
+impl<'a> GffGenes<'a> {
+
+    /// Returns the results of the next gene, along with their identifiers.
+    ///
+    /// Errors of the transcripts of the gene come first, each with the identifier of its
+    /// transcript. The gene is created from the remaining transcripts, if any.
+    fn next_results(&mut self) -> Option<Vec<GeneResult>> {
+        let opts = self.opts;
+        let gene_records = &self.gene_records;
+        let group_to_results = |(key, tps): (GeneGroupKey, GeneGroup)| {
+            let (gid, chrom, strand) = key;
+            // Genes on both strands of a sequence share the same gene record.
+            let gene_record = gene_records.get(&(gid.clone(), chrom.clone())).cloned();
+
+            let mut results = Vec::new();
+            let mut transcripts = LinkedHashMap::new();
+            let (mut gene_start, mut gene_end) = INIT_COORD;
+            let trx_groups = tps.group_by(TrxPart::transcript_group_key as TrxGroupFunc);
+            for (trx_key, trx_parts) in trx_groups.into_iter() {
+                let tid = trx_key.1.clone();
+                match parts_to_transcript(trx_key, trx_parts, opts) {
+                    Ok(transcript) => {
+                        gene_start = min(gene_start, transcript.start());
+                        gene_end = max(gene_end, transcript.end());
+                        let _ = transcripts.insert(tid, transcript);
+                    },
+                    Err(e) => results.push((Some(tid), Err(e))),
+                }
+            }
+            if transcripts.is_empty() {
+                return results;
+            }
+
+            let (gene_coord, attributes) = match gene_record {
+                Some(mut rec) => {
+                    if opts.lossless {
+                        insert_source_score(&mut rec.attributes, &rec.source, &rec.score);
+                    }
+                    (rec.coord, rec.attributes)
+                },
+                None => ((gene_start, gene_end), MultiMap::new()),
+            };
+
+            let gene = GBuilder::new(chrom, gene_coord.0, gene_coord.1)
+                .id(gid.clone())
                 .strand(strand)
-                .coords(exn_coords, coding_coord)
-                .coding_incl_stop(true)
-                .build()
+                .attributes(attributes)
+                .transcripts(transcripts)
+                .build();
+            results.push((Some(gid), gene));
+            results
         };
 
-        self.groups.into_iter().map(group_to_transcript).next()
+        self.groups.into_iter()
+            .map(group_to_results)
+            .next()
     }
 }
 
-/// Helper function to create regex for parsing GFF identifiers.
-fn make_gff_id_regex(attr_name: &str, gff_type: GffType) -> ::Result<Regex> {
-    let fmts = match gff_type {
-        GffType::GFF2 | GffType::GTF2 => Ok((" ", ";", r#"""#)),
-        GffType::GFF3 => Ok(("=", ",", "")),
-        _ => Err(::Error::from(GffError::UnsupportedGffType)),
-    };
-    fmts.and_then(|(delim, term, nest)| {
-        let pat = format!(
-            r#"{attr_name}{delim}{nest}(?P<value>[^{delim}{term}\t]+){nest}{term}?"#,
-            attr_name=attr_name, delim=delim, term=term, nest=nest);
-        Regex::new(&pat)
+impl<'a> Iterator for GffGenes<'a> {
+
+    type Item = ::Result<Gene>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.handler.is_stopped() {
+            let (id, result) = match self.row_errors.next() {
+                Some(err) => (None, Err(err)),
+                None => match self.pending.next() {
+                    Some(next) => next,
+                    None => match self.next_results() {
+                        Some(results) => {
+                            self.pending = results.into_iter();
+                            continue;
+                        },
+                        None => return None,
+                    },
+                },
+            };
+            if let Some(result) = self.handler.handle(id, result) {
+                return Some(result);
+            }
+        }
+        None
+    }
+}
+
+/// Helper function to parse the attribute column of a GFF record.
+///
+/// The values of each attribute key are stored in the order they appear in the column. In GTF2
+/// and GFF2, surrounding quotes of the values are removed. In GFF3, comma-separated values are
+/// stored as multiple values and percent-encoded characters are decoded.
+fn parse_attributes(raw: &str, gff_type: GffType) -> Result<MultiMap<String, String>, GffError> {
+    let mut attribs = MultiMap::new();
+    match gff_type {
+        GffType::GFF3 => {
+            for item in raw.split(';').map(|v| v.trim()).filter(|v| !v.is_empty()) {
+                let mut kv = item.splitn(2, '=');
+                let key = kv.next().unwrap_or("").trim();
+                let values = kv.next().unwrap_or("");
+                for value in values.split(',') {
+                    attribs.insert(key.to_owned(), unescape_gff3_value(value));
+                }
+            }
+        },
+        GffType::GFF2 | GffType::GTF2 => {
+            for item in split_gtf_attributes(raw) {
+                let mut kv = item.splitn(2, char::is_whitespace);
+                let key = kv.next().unwrap_or("");
+                let value = kv.next().unwrap_or("").trim().trim_matches('"');
+                attribs.insert(key.to_owned(), value.to_owned());
+            }
+        },
+        _ => return Err(GffError::UnsupportedGffType),
+    }
+    Ok(attribs)
+}
+
+/// Helper function to split the GTF2 or GFF2 attribute column into `key value` items.
+///
+/// Semicolons enclosed in double quotes are not treated as item separators.
+fn split_gtf_attributes(raw: &str) -> Vec<&str> {
+    let mut items = Vec::new();
+    let (mut item_start, mut in_quotes) = (0, false);
+    for (idx, c) in raw.char_indices() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            ';' if !in_quotes => {
+                items.push(raw[item_start..idx].trim());
+                item_start = idx + 1;
+            },
+            _ => {},
+        }
+    }
+    items.push(raw[item_start..].trim());
+    items.retain(|item| !item.is_empty());
+    items
+}
+
+/// Helper function to decode percent-encoded characters in GFF3 attribute values.
+///
+/// Invalid percent-encodings are kept as they are.
+fn unescape_gff3_value(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut idx = 0;
+    while idx < bytes.len() {
+        if bytes[idx] == b'%' && idx + 2 < bytes.len() {
+            let hex = ::std::str::from_utf8(&bytes[idx + 1..idx + 3]).ok()
+                .and_then(|h| u8::from_str_radix(h, 16).ok());
+            if let Some(b) = hex {
+                decoded.push(b);
+                idx += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[idx]);
+        idx += 1;
+    }
+    String::from_utf8(decoded).unwrap_or_else(|_| value.to_owned())
+}
+
+/// GFF writer.
+pub struct Writer<W: io::Write> {
+    inner: csv::Writer<W>,
+    gff_type: GffType,
+    header_written: bool,
+}
+
+impl<W: io::Write> Writer<W> {
+
+    /// Creates a GFF writer of the given variant from another writer.
+    pub fn from_writer(in_writer: W, gff_type: GffType) -> Writer<W> {
+        Writer {
+            inner: csv::Writer::from_writer(in_writer)
+                .delimiter(b'\t')
+                .quote_style(csv::QuoteStyle::Never),
+            gff_type: gff_type,
+            header_written: false,
+        }
+    }
+
+    /// Writes the `##gff-version` header line if it has not been written.
+    fn write_header(&mut self) -> ::Result<()> {
+        if self.header_written {
+            return Ok(());
+        }
+        let header = match self.gff_type {
+            GffType::GFF3 => GFF3_HEADER,
+            GffType::GFF2 | GffType::GTF2 => GFF2_HEADER,
+            _ => return Err(::Error::from(GffError::UnsupportedGffType)),
+        };
+        self.inner.write(vec![header].into_iter())
+            .map_err(|e| ::Error::from(GffError::from(e)))?;
+        self.header_written = true;
+        Ok(())
+    }
+
+    /// Writes the given GFF record.
+    ///
+    /// Since `gff::Record::score` only parses integer scores, other scores are written as
+    /// undefined.
+    pub fn write_record(&mut self, record: &gff::Record) -> ::Result<()> {
+        let score = record.score()
+            .map(|v| v.to_string())
+            .unwrap_or_else(|| UNK_STR.to_owned());
+        self.write_scored_record(record, &score)
+    }
+
+    /// Writes the given GFF record with the given raw score column.
+    fn write_scored_record(&mut self, record: &gff::Record, score: &str) -> ::Result<()> {
+        self.write_header()?;
+        let attribs = format_attributes(record.attributes(), self.gff_type)?;
+        let strand = record.strand()
+            .map(|ref s| strand_to_char(s))
+            .unwrap_or(UNK_CHAR);
+        self.inner
+            .encode((record.seqname(), record.source(), record.feature_type(),
+                     record.start(), record.end(), score, strand, record.frame(), attribs))
+            .map_err(|e| ::Error::from(GffError::from(e)))
+    }
+
+    /// Writes the given exon and its features as multiple records.
+    pub fn write_exon(&mut self, exon: &Exon) -> ::Result<()> {
+        for &(ref record, ref score) in exon.gff_records(self.gff_type)?.iter() {
+            self.write_scored_record(record, score)?;
+        }
+        Ok(())
+    }
+
+    /// Writes the given transcript, its exons, and their features as multiple records.
+    pub fn write_transcript(&mut self, transcript: &Transcript) -> ::Result<()> {
+        for &(ref record, ref score) in transcript.gff_records(self.gff_type)?.iter() {
+            self.write_scored_record(record, score)?;
+        }
+        Ok(())
+    }
+
+    /// Writes the given gene and all of its transcripts as multiple records.
+    pub fn write_gene(&mut self, gene: &Gene) -> ::Result<()> {
+        for &(ref record, ref score) in gene.gff_records(self.gff_type)?.iter() {
+            self.write_scored_record(record, score)?;
+        }
+        Ok(())
+    }
+
+    /// Writes all buffered rows to the underlying writer.
+    pub fn flush(&mut self) -> ::Result<()> {
+        self.inner.flush()
             .map_err(|e| ::Error::from(GffError::from(e)))
-    })
+    }
+}
+
+impl Writer<fs::File> {
+
+    /// Creates a GFF writer of the given variant that writes to the given path.
+    pub fn from_file<P: AsRef<Path>>(path: P, gff_type: GffType) -> io::Result<Self> {
+        let f = fs::File::create(path)?;
+        Ok(Writer::from_writer(f, gff_type))
+    }
+}
+
+impl Writer<Vec<u8>> {
+
+    /// Creates a GFF writer of the given variant that writes to an in-memory buffer.
+    ///
+    /// The initial capacity of the buffer is 64 KiB.
+    pub fn from_memory(gff_type: GffType) -> Writer<Vec<u8>> {
+        Writer::from_writer(Vec::with_capacity(1024 * 64), gff_type)
+    }
 
+    /// Returns the values of the in-memory buffer as a string.
+    pub fn as_string(&mut self) -> &str {
+        self.inner.as_string()
+    }
 }
 
 impl Gene {
@@ -542,32 +1924,36 @@ impl Gene {
     }
 
     // TODO: also handle gene-level features
-    /// Transforms the gene into GFF records.
-    pub fn into_gff_records(mut self) -> ::Result<Vec<gff::Record>> {
+    /// Transforms the gene into GTF records.
+    pub fn into_gff_records(self) -> ::Result<Vec<gff::Record>> {
+        self.gff_records(GffType::GTF2)
+            .map(|recs| recs.into_iter().map(|(rec, _)| rec).collect())
+    }
 
-        let mut attribs = self.set_attributes(MultiMap::new());
+    /// Creates GFF records of the given variant of the gene and all of its transcripts.
+    pub(crate) fn gff_records(&self, gff_type: GffType) -> ::Result<Vec<ScoredRecord>> {
 
-        self.id()
-            .ok_or(GffError::MissingGeneId)
-            .map(|gid| attribs.insert(GENE_ID_STR.to_owned(), gid.to_owned()))?;
+        let mut attribs = self.attributes().clone();
+        let gid = self.id().ok_or(GffError::MissingGeneId)?;
+        insert_ids(&mut attribs, gff_type, gid, None, true);
 
         let (source, score) = extract_source_score(&mut attribs);
 
         let mut recs = Vec::with_capacity(self.num_records());
 
-        let gx_record = gff::RecordBuilder::new(self.seq_name(), self.start(), self.end())
+        let gx_record = gff::RecordBuilder::new(self.seq_name(), self.start() + 1, self.end())
             .source(source)
             .feature_type(GENE_STR)
-            .score(score)
+            .score(score.as_str())
             .strand(strand_to_char(&self.strand()))
             .frame(UNK_CHAR)
             .attributes(attribs)
             .build()
             .map_err(|e| ::Error::from(GffError::from(e)))?;
-        recs.push(gx_record);
+        recs.push((gx_record, score));
 
-        for (_, transcript) in self.take_transcripts() {
-            recs.append(&mut transcript.into_gff_records()?);
+        for transcript in self.transcripts().values() {
+            recs.append(&mut transcript.gff_records(gff_type)?);
         }
 
         Ok(recs)
@@ -579,42 +1965,70 @@ impl Transcript {
     /// Returns the number of GFF records the transcript has.
     #[inline(always)]
     fn num_records(&self) -> usize {
-        1 + self.exons().iter()
+        1 + self.features().len() + self.exons().iter()
             .map(|ref exn| 1 + exn.features().len())
             .fold(0, |acc, x| acc + x)
     }
 
-    // TODO: also handle transcript-level features
-    /// Transforms the transcript into GFF records.
-    pub fn into_gff_records(mut self) -> ::Result<Vec<gff::Record>> {
-
-        let mut attribs = self.set_attributes(MultiMap::new());
+    /// Transforms the transcript into GTF records.
+    pub fn into_gff_records(self) -> ::Result<Vec<gff::Record>> {
+        self.gff_records(GffType::GTF2)
+            .map(|recs| recs.into_iter().map(|(rec, _)| rec).collect())
+    }
 
-        self.gene_id()
-            .ok_or(GffError::MissingGeneId)
-            .map(|gid| attribs.insert(GENE_ID_STR.to_owned(), gid.to_owned()))?;
+    /// Creates GFF records of the given variant of the transcript, its exons, and their
+    /// features.
+    ///
+    /// The transcript feature records are written after all the exon records. In GFF3, coding
+    /// transcripts are written as `mRNA` records and non-coding transcripts as `ncRNA` records.
+    pub(crate) fn gff_records(&self, gff_type: GffType) -> ::Result<Vec<ScoredRecord>> {
 
-        self.id()
-            .ok_or(GffError::MissingTranscriptId)
-            .map(|tid| attribs.insert(TRANSCRIPT_ID_STR.to_owned(), tid.to_owned()))?;
+        let gid = self.gene_id().ok_or(GffError::MissingGeneId)?;
+        let tid = self.id().ok_or(GffError::MissingTranscriptId)?;
 
+        let mut attribs = self.attributes().clone();
+        insert_ids(&mut attribs, gff_type, gid, Some(tid), true);
         let (source, score) = extract_source_score(&mut attribs);
+        let trx_feature = match gff_type {
+            GffType::GFF3 if self.is_coding() => GFF3_MRNA_STR,
+            GffType::GFF3 => GFF3_NCRNA_STR,
+            _ => TRANSCRIPT_STR,
+        };
 
         let mut recs = Vec::with_capacity(self.num_records());
 
-        let trx_record = gff::RecordBuilder::new(self.seq_name(), self.start(), self.end())
+        let trx_record = gff::RecordBuilder::new(self.seq_name(), self.start() + 1, self.end())
             .source(source)
-            .feature_type(TRANSCRIPT_STR)
-            .score(score)
+            .feature_type(trx_feature)
+            .score(score.as_str())
             .strand(strand_to_char(&self.strand()))
             .frame(UNK_CHAR)
             .attributes(attribs)
             .build()
             .map_err(|e| ::Error::from(GffError::from(e)))?;
-        recs.push(trx_record);
+        recs.push((trx_record, score));
+
+        for exon in self.exons().iter() {
+            recs.append(&mut exon.gff_records(gff_type)?);
+        }
 
-        for exon in self.take_exons() {
-            recs.append(&mut exon.into_gff_records()?);
+        for fx in self.features().iter() {
+            let feature = match fx.kind() {
+                &TFK::Intron { .. } => INTRON_STR,
+                &TFK::Any(ref s) => s.as_str(),
+            };
+            let (fx_attribs, fx_source, fx_score) =
+                feature_attributes(fx.attributes(), self.attributes(), gff_type, gid, tid);
+            let fx_record = gff::RecordBuilder::new(self.seq_name(), fx.start() + 1, fx.end())
+                .source(fx_source)
+                .feature_type(feature)
+                .score(fx_score.as_str())
+                .strand(strand_to_char(&self.strand()))
+                .frame(UNK_CHAR)
+                .attributes(fx_attribs)
+                .build()
+                .map_err(|e| ::Error::from(GffError::from(e)))?;
+            recs.push((fx_record, fx_score));
         }
 
         Ok(recs)
@@ -623,12 +2037,20 @@ impl Transcript {
 
 impl EFK {
 
-    /// Returns the feature name and the frame of the exon feature kind.
+    /// Returns the feature name in the given GFF variant and the frame of the exon feature kind.
+    ///
+    /// GFF3 feature names are Sequence Ontology terms.
     #[inline(always)]
-    fn get_feature_frame(&self) -> (String, char) {
+    fn get_feature_frame(&self, gff_type: GffType) -> (String, char) {
+        let is_gff3 = match gff_type {
+            GffType::GFF3 => true,
+            _ => false,
+        };
         let (feature, frame) = match self {
             &EFK::UTR => (UTR_STR, UNK_CHAR),
+            &EFK::UTR5 if is_gff3 => (GFF3_UTR5_STR, UNK_CHAR),
             &EFK::UTR5 => (UTR5_STR, UNK_CHAR),
+            &EFK::UTR3 if is_gff3 => (GFF3_UTR3_STR, UNK_CHAR),
             &EFK::UTR3 => (UTR3_STR, UNK_CHAR),
             &EFK::CDS { frame: ref f } => (CDS_STR, frame_to_char(f)),
             &EFK::StopCodon { frame: ref f } => (STOP_CODON_STR, frame_to_char(f)),
@@ -637,44 +2059,50 @@ impl EFK {
         };
         (feature.to_owned(), frame)
     }
+
+    /// Creates an exon feature kind from the given feature name and frame.
+    ///
+    /// This is the inverse of `get_feature_frame` for all GFF variants. Unknown feature names
+    /// become `Any` features.
+    fn from_feature_frame(feature: String, frame: Option<u8>) -> Self {
+        match feature.as_str() {
+            UTR_STR => EFK::UTR,
+            UTR5_STR | GFF3_UTR5_STR => EFK::UTR5,
+            UTR3_STR | GFF3_UTR3_STR => EFK::UTR3,
+            CDS_STR => EFK::CDS { frame: frame },
+            STOP_CODON_STR => EFK::StopCodon { frame: frame },
+            START_CODON_STR => EFK::StartCodon { frame: frame },
+            _ => EFK::Any(feature),
+        }
+    }
 }
 
 impl Exon {
 
-    /// Transforms the exon into GFF records.
-    pub fn into_gff_records(mut self) -> ::Result<Vec<gff::Record>> {
+    /// Transforms the exon into GTF records.
+    pub fn into_gff_records(self) -> ::Result<Vec<gff::Record>> {
+        self.gff_records(GffType::GTF2)
+            .map(|recs| recs.into_iter().map(|(rec, _)| rec).collect())
+    }
 
-        let mut attribs = self.set_attributes(MultiMap::new());
+    /// Creates GFF records of the given variant of the exon and its features.
+    ///
+    /// The exon record is always the first record, followed by the feature records in the
+    /// order they appear in the exon.
+    pub(crate) fn gff_records(&self, gff_type: GffType) -> ::Result<Vec<ScoredRecord>> {
 
-        self.gene_id()
-            .ok_or(GffError::MissingGeneId)
-            .map(|gid| attribs.insert(GENE_ID_STR.to_owned(), gid.to_owned()))?;
+        let gid = self.gene_id().ok_or(GffError::MissingGeneId)?;
+        let tid = self.transcript_id().ok_or(GffError::MissingTranscriptId)?;
 
-        self.transcript_id()
-            .ok_or(GffError::MissingTranscriptId)
-            .map(|tid| attribs.insert(TRANSCRIPT_ID_STR.to_owned(), tid.to_owned()))?;
+        let mut attribs = self.attributes().clone();
+        insert_ids(&mut attribs, gff_type, gid, Some(tid), false);
 
         let (source, score) = extract_source_score(&mut attribs);
 
         let mut recs = Vec::with_capacity(1 + self.features().len());
 
-        for (idx, fx) in self.features().iter().enumerate() {
-            let (feature, frame) = fx.kind().get_feature_frame();
-            let fx_record = gff::RecordBuilder::new(self.seq_name(), fx.start(), fx.end())
-                .source(source.as_str())
-                .feature_type(feature.as_str())
-                .score(score.as_str())
-                .strand(strand_to_char(&self.strand()))
-                .frame(frame)
-                .attributes(attribs.clone())
-                .build()
-                .map_err(|e| ::Error::from(GffError::from(e)))?;
-
-            recs[1 + idx] = fx_record;
-        }
-
-        let exn_record = gff::RecordBuilder::new(self.seq_name(), self.start(), self.end())
-            .source(source.as_str())
+        let exn_record = gff::RecordBuilder::new(self.seq_name(), self.start() + 1, self.end())
+            .source(source)
             .feature_type(EXON_STR)
             .score(score.as_str())
             .strand(strand_to_char(&self.strand()))
@@ -682,24 +2110,182 @@ impl Exon {
             .attributes(attribs)
             .build()
             .map_err(|e| ::Error::from(GffError::from(e)))?;
-        recs[0] = exn_record;
+        recs.push((exn_record, score));
+
+        for fx in self.features().iter() {
+            let (feature, frame) = fx.kind().get_feature_frame(gff_type);
+            let (fx_attribs, fx_source, fx_score) =
+                feature_attributes(fx.attributes(), self.attributes(), gff_type, gid, tid);
+            let fx_record = gff::RecordBuilder::new(self.seq_name(), fx.start() + 1, fx.end())
+                .source(fx_source)
+                .feature_type(feature.as_str())
+                .score(fx_score.as_str())
+                .strand(strand_to_char(&self.strand()))
+                .frame(frame)
+                .attributes(fx_attribs)
+                .build()
+                .map_err(|e| ::Error::from(GffError::from(e)))?;
+            recs.push((fx_record, fx_score));
+        }
 
         Ok(recs)
     }
 }
 
+/// A GFF record along with its raw score column.
+///
+/// `gff::Record::score` only parses integer scores, so the raw value is kept for writing other
+/// scores as they are.
+type ScoredRecord = (gff::Record, String);
+
+/// Helper function to create the attribute column of a GFF record.
+///
+/// Identifier attributes are always written first, followed by the other attributes sorted by
+/// their keys. Attributes with multiple values are written as repeated `key "value";` pairs in
+/// GTF2 or GFF2 and as a single comma-separated `key=value` pair in GFF3.
+fn format_attributes(
+    attributes: &MultiMap<String, String>,
+    gff_type: GffType,
+) -> Result<String, GffError> {
+
+    let lead_keys: &[&str] = match gff_type {
+        GffType::GFF3 => &[GFF3_ID_STR, GFF3_PARENT_STR, GENE_ID_STR, TRANSCRIPT_ID_STR],
+        GffType::GFF2 | GffType::GTF2 => &[GENE_ID_STR, TRANSCRIPT_ID_STR],
+        _ => return Err(GffError::UnsupportedGffType),
+    };
+
+    let mut keys: Vec<&String> = attributes.keys()
+        .filter(|k| !lead_keys.contains(&k.as_str()))
+        .collect();
+    keys.sort();
+
+    let ordered_keys = lead_keys.iter()
+        .filter(|k| attributes.contains_key(**k))
+        .map(|k| *k)
+        .chain(keys.into_iter().map(|k| k.as_str()));
+
+    let mut items = Vec::with_capacity(attributes.len());
+    for key in ordered_keys {
+        let values = match attributes.get_vec(key) {
+            Some(vs) => vs,
+            None => continue,
+        };
+        match gff_type {
+            GffType::GFF3 => {
+                let value = values.iter().map(|v| escape_gff3_value(v)).join(",");
+                items.push(format!("{}={}", key, value));
+            },
+            _ => {
+                for value in values.iter() {
+                    items.push(format!("{} \"{}\";", key, value));
+                }
+            },
+        }
+    }
+
+    match gff_type {
+        GffType::GFF3 => Ok(items.join(";")),
+        _ => Ok(items.join(" ")),
+    }
+}
+
+/// Helper function to percent-encode characters with special meanings in GFF3 attribute values.
+fn escape_gff3_value(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            ';' | '=' | '&' | ',' | '%' | '\t' | '\n' | '\r' =>
+                escaped.push_str(&format!("%{:02X}", c as u32)),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Helper function to store the identifiers of a record as attributes.
+///
+/// In GFF3, records are linked through their `ID` and `Parent` attributes. Genes and
+/// transcripts, as denoted by `has_id`, are given their own identifiers as `ID`. Transcripts
+/// name their genes as `Parent`, while exons and other features name their transcripts. In GTF2
+/// and GFF2, the gene and transcript identifiers are stored instead.
+fn insert_ids(
+    attributes: &mut MultiMap<String, String>,
+    gff_type: GffType,
+    gene_id: &str,
+    transcript_id: Option<&str>,
+    has_id: bool,
+) {
+    match gff_type {
+        GffType::GFF3 => {
+            let _ = attributes.remove(GFF3_ID_STR);
+            let _ = attributes.remove(GFF3_PARENT_STR);
+            let (id, parent) = match transcript_id {
+                Some(tid) if has_id => (Some(tid), Some(gene_id)),
+                Some(tid) => (None, Some(tid)),
+                None => (Some(gene_id), None),
+            };
+            if let Some(id) = id {
+                attributes.insert(GFF3_ID_STR.to_owned(), id.to_owned());
+            }
+            if let Some(parent) = parent {
+                attributes.insert(GFF3_PARENT_STR.to_owned(), parent.to_owned());
+            }
+        },
+        _ => {
+            attributes.insert(GENE_ID_STR.to_owned(), gene_id.to_owned());
+            if let Some(tid) = transcript_id {
+                attributes.insert(TRANSCRIPT_ID_STR.to_owned(), tid.to_owned());
+            }
+        },
+    }
+}
+
+/// Helper function to create the attributes, source, and score of a feature record.
+///
+/// Features read in lossless mode keep the attributes of their own records. Other features,
+/// such as those inferred from coding coordinates, have no attributes and take the attributes
+/// of their exon or transcript instead.
+fn feature_attributes(
+    fx_attributes: &MultiMap<String, String>,
+    parent_attributes: &MultiMap<String, String>,
+    gff_type: GffType,
+    gene_id: &str,
+    transcript_id: &str,
+) -> (MultiMap<String, String>, String, String) {
+    let mut attribs = if fx_attributes.is_empty() {
+        parent_attributes.clone()
+    } else {
+        fx_attributes.clone()
+    };
+    insert_ids(&mut attribs, gff_type, gene_id, Some(transcript_id), false);
+    let (source, score) = extract_source_score(&mut attribs);
+    (attribs, source, score)
+}
+
 /// Helper function to extract source and score attributes.
 #[inline(always)]
 fn extract_source_score(attributes: &mut MultiMap<String, String>) -> (String, String) {
-    let source = attributes.remove("source")
+    let source = attributes.remove(SOURCE_STR)
         .and_then(|mut vec| vec.pop())
         .unwrap_or(UNK_STR.to_owned());
-    let score = attributes.remove("score")
+    let score = attributes.remove(SCORE_STR)
         .and_then(|mut vec| vec.pop())
         .unwrap_or(UNK_STR.to_owned());
     (source, score)
 }
 
+/// Helper function to store the source and score columns of a GFF record as attributes.
+///
+/// Undefined column values are not stored.
+fn insert_source_score(attributes: &mut MultiMap<String, String>, source: &str, score: &str) {
+    if source != UNK_STR {
+        attributes.insert(SOURCE_STR.to_owned(), source.to_owned());
+    }
+    if score != UNK_STR {
+        attributes.insert(SCORE_STR.to_owned(), score.to_owned());
+    }
+}
+
 /// Helper function to create a char given a strand reference.
 #[inline(always)]
 fn strand_to_char(strand: &Strand) -> char {
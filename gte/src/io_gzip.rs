@@ -0,0 +1,395 @@
+/*! Reader and writer for gzip-compressed input and BGZF output.
+
+Annotation files are commonly distributed gzip-compressed. The reader in this module detects
+gzip input, including BGZF input, from its magic bytes and decompresses it transparently. Other
+input is read as-is, so that it can be used for all input files.
+
+The BGZF format is a variant of gzip made up of a series of independently compressed blocks.
+It is produced by `bgzip` and is required for creating tabix indices. Since each BGZF block is a
+complete gzip member, BGZF files can also be read by regular gzip readers. A description of the
+format can be found in the [SAM specification](https://samtools.github.io/hts-specs/SAMv1.pdf).
+
+Positions in BGZF data are denoted by virtual offsets. The upper 48 bits of a virtual offset are
+the offset of a block in the compressed data, and the lower 16 bits are the offset of a byte in
+the uncompressed data of the block.
+*/
+use std::cmp::min;
+use std::convert::AsRef;
+use std::fs;
+use std::io::{self, Read, Seek, Write};
+use std::path::Path;
+
+use flate2::{Compression, Crc};
+use flate2::read::{DeflateDecoder, MultiGzDecoder};
+use flate2::write::DeflateEncoder;
+
+
+/// Magic bytes at the beginning of gzip and BGZF data.
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Maximum number of uncompressed bytes in a BGZF block, as used by `bgzip`.
+const BLOCK_SIZE: usize = 0xff00;
+
+/// Header of BGZF blocks, without the trailing total block size field.
+const BLOCK_HEADER: [u8; 16] = [0x1f, 0x8b, 0x08, 0x04, 0x00, 0x00, 0x00, 0x00,
+                                0x00, 0xff, 0x06, 0x00, b'B', b'C', 0x02, 0x00];
+
+/// Number of bytes in BGZF block headers, including the total block size field.
+const HEADER_LEN: usize = 18;
+
+/// Number of bytes in gzip member headers, up to and including the extra field length.
+const GZIP_HEADER_LEN: usize = 12;
+
+/// Number of bytes in BGZF block footers, which contain the CRC32 and the uncompressed size.
+const FOOTER_LEN: usize = 8;
+
+/// Empty BGZF block that marks the end of BGZF data.
+const EOF_BLOCK: [u8; 28] = [0x1f, 0x8b, 0x08, 0x04, 0x00, 0x00, 0x00, 0x00,
+                             0x00, 0xff, 0x06, 0x00, b'B', b'C', 0x02, 0x00,
+                             0x1b, 0x00, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00,
+                             0x00, 0x00, 0x00, 0x00];
+
+/// The type of the underlying reader, after its magic bytes have been read.
+type Peeked<R> = io::Chain<io::Cursor<Vec<u8>>, R>;
+
+/// Reader that decompresses gzip and BGZF input and reads other input as-is.
+pub struct Reader<R: Read> {
+    inner: Inner<R>,
+}
+
+/// Readers of plain and compressed input.
+enum Inner<R: Read> {
+    Plain(Peeked<R>),
+    Gzip(MultiGzDecoder<Peeked<R>>),
+}
+
+impl<R: Read> Reader<R> {
+
+    /// Creates a reader from another reader.
+    ///
+    /// The magic bytes of the input are read here, so an error is returned if they can not be
+    /// read or if the header of gzip input is invalid.
+    pub fn from_reader(mut in_reader: R) -> io::Result<Reader<R>> {
+        let mut magic = vec![0; GZIP_MAGIC.len()];
+        let num_read = read_full(&mut in_reader, &mut magic)?;
+        magic.truncate(num_read);
+
+        let is_gzip = magic.as_slice() == &GZIP_MAGIC[..];
+        let peeked = io::Cursor::new(magic).chain(in_reader);
+        let inner =
+            if is_gzip {
+                Inner::Gzip(MultiGzDecoder::new(peeked)?)
+            } else {
+                Inner::Plain(peeked)
+            };
+        Ok(Reader { inner: inner })
+    }
+
+    /// Returns whether the input is gzip or BGZF-compressed.
+    pub fn is_compressed(&self) -> bool {
+        match self.inner {
+            Inner::Gzip(_) => true,
+            Inner::Plain(_) => false,
+        }
+    }
+}
+
+impl<R: Read> Read for Reader<R> {
+
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self.inner {
+            Inner::Plain(ref mut r) => r.read(buf),
+            Inner::Gzip(ref mut r) => r.read(buf),
+        }
+    }
+}
+
+impl Reader<fs::File> {
+
+    /// Creates a reader that reads from the given path.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        fs::File::open(path).and_then(Reader::from_reader)
+    }
+}
+
+/// BGZF writer.
+///
+/// Written bytes are buffered and compressed into blocks of at most 65280 bytes. Flushing the
+/// writer compresses the buffered bytes into a block right away. The end-of-file marker block is
+/// written by `finish`, or when the writer is dropped.
+pub struct Writer<W: Write> {
+    inner: W,
+    buf: Vec<u8>,
+    coffset: u64,
+    finished: bool,
+}
+
+impl<W: Write> Writer<W> {
+
+    /// Creates a BGZF writer from another writer.
+    pub fn from_writer(in_writer: W) -> Writer<W> {
+        Writer {
+            inner: in_writer,
+            buf: Vec::with_capacity(BLOCK_SIZE),
+            coffset: 0,
+            finished: false,
+        }
+    }
+
+    /// Returns a reference to the underlying writer.
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Returns the virtual offset of the next byte to be written.
+    ///
+    /// The offset is relative to the position of the underlying writer when the BGZF writer was
+    /// created.
+    pub fn virtual_offset(&self) -> u64 {
+        (self.coffset << 16) | self.buf.len() as u64
+    }
+
+    /// Writes all buffered bytes and the end-of-file marker block.
+    ///
+    /// This must be called once all bytes have been written. Dropping an unfinished writer
+    /// also does this as a last resort, but any errors are then lost.
+    pub fn finish(&mut self) -> io::Result<()> {
+        if self.finished {
+            return Ok(());
+        }
+        self.write_block()?;
+        self.inner.write_all(&EOF_BLOCK)?;
+        self.coffset += EOF_BLOCK.len() as u64;
+        self.inner.flush()?;
+        self.finished = true;
+        Ok(())
+    }
+
+    /// Compresses the buffered bytes into a BGZF block, if there are any.
+    fn write_block(&mut self) -> io::Result<()> {
+        if self.buf.is_empty() {
+            return Ok(());
+        }
+        let mut encoder = DeflateEncoder::new(Vec::with_capacity(self.buf.len()),
+                                              Compression::Default);
+        encoder.write_all(&self.buf)?;
+        let cdata = encoder.finish()?;
+        let mut crc = Crc::new();
+        crc.update(&self.buf);
+
+        let block_size = HEADER_LEN + cdata.len() + FOOTER_LEN;
+        let mut block = Vec::with_capacity(block_size);
+        block.extend_from_slice(&BLOCK_HEADER);
+        push_le(&mut block, (block_size - 1) as u64, 2);
+        block.extend_from_slice(&cdata);
+        push_le(&mut block, crc.sum() as u64, 4);
+        push_le(&mut block, self.buf.len() as u64, 4);
+
+        self.inner.write_all(&block)?;
+        self.coffset += block.len() as u64;
+        self.buf.clear();
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for Writer<W> {
+
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let num_written = min(buf.len(), BLOCK_SIZE - self.buf.len());
+        self.buf.extend_from_slice(&buf[..num_written]);
+        self.finished = false;
+        if self.buf.len() >= BLOCK_SIZE {
+            self.write_block()?;
+        }
+        Ok(num_written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.write_block()?;
+        self.inner.flush()
+    }
+}
+
+impl<W: Write> Drop for Writer<W> {
+
+    fn drop(&mut self) {
+        let _ = self.finish();
+    }
+}
+
+impl Writer<fs::File> {
+
+    /// Creates a BGZF writer that writes to the given path.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let f = fs::File::create(path)?;
+        Ok(Writer::from_writer(f))
+    }
+}
+
+/// Reader of BGZF data that supports seeking to virtual offsets.
+///
+/// Blocks are read and decompressed one at a time, as needed.
+pub(crate) struct BlockReader<R: Read + Seek> {
+    inner: R,
+    block: Vec<u8>,
+    pos: usize,
+    coffset: u64,
+    next_coffset: u64,
+}
+
+impl<R: Read + Seek> BlockReader<R> {
+
+    /// Creates a block reader from another reader, positioned at its first block.
+    pub(crate) fn new(in_reader: R) -> BlockReader<R> {
+        BlockReader {
+            inner: in_reader,
+            block: Vec::with_capacity(BLOCK_SIZE),
+            pos: 0,
+            coffset: 0,
+            next_coffset: 0,
+        }
+    }
+
+    /// Moves the reader to the given virtual offset.
+    pub(crate) fn seek(&mut self, voffset: u64) -> io::Result<()> {
+        let uoffset = (voffset & 0xffff) as usize;
+        let _ = self.read_block(voffset >> 16)?;
+        if uoffset > self.block.len() {
+            return Err(io::Error::new(io::ErrorKind::InvalidData,
+                                      "virtual offset is outside of its BGZF block"));
+        }
+        self.pos = uoffset;
+        Ok(())
+    }
+
+    /// Returns the virtual offset of the next byte to be read.
+    ///
+    /// When the current block has been read completely, the offset points to the start of the
+    /// next block, as it does in the offsets of the writer.
+    pub(crate) fn virtual_offset(&mut self) -> io::Result<u64> {
+        while self.pos == self.block.len() {
+            let next_coffset = self.next_coffset;
+            if !self.read_block(next_coffset)? {
+                break;
+            }
+        }
+        Ok((self.coffset << 16) | self.pos as u64)
+    }
+
+    /// Appends the next line, including its newline character, to the given buffer.
+    ///
+    /// The number of bytes read is returned, which is zero at the end of the data.
+    pub(crate) fn read_line(&mut self, buf: &mut Vec<u8>) -> io::Result<usize> {
+        let mut num_read = 0;
+        loop {
+            if self.pos == self.block.len() {
+                let next_coffset = self.next_coffset;
+                if !self.read_block(next_coffset)? {
+                    return Ok(num_read);
+                }
+                continue;
+            }
+            let rest = &self.block[self.pos..];
+            let (len, found) = match rest.iter().position(|&b| b == b'\n') {
+                Some(idx) => (idx + 1, true),
+                None => (rest.len(), false),
+            };
+            buf.extend_from_slice(&rest[..len]);
+            self.pos += len;
+            num_read += len;
+            if found {
+                return Ok(num_read);
+            }
+        }
+    }
+
+    /// Reads and decompresses the block at the given compressed offset.
+    ///
+    /// Returns `false` if there are no more blocks at the offset.
+    fn read_block(&mut self, coffset: u64) -> io::Result<bool> {
+        let _ = self.inner.seek(io::SeekFrom::Start(coffset))?;
+        self.block.clear();
+        self.pos = 0;
+        self.coffset = coffset;
+        self.next_coffset = coffset;
+
+        let mut header = [0u8; GZIP_HEADER_LEN];
+        match read_full(&mut self.inner, &mut header)? {
+            0 => return Ok(false),
+            n if n < header.len() => return Err(invalid_block("truncated block header")),
+            _ => {},
+        }
+        if header[..4] != BLOCK_HEADER[..4] {
+            return Err(invalid_block("invalid block header"));
+        }
+        let xlen = read_le(&header[10..12]) as usize;
+        let mut extra = vec![0; xlen];
+        self.inner.read_exact(&mut extra)?;
+        let block_size = match find_block_size(&extra) {
+            Some(size) => size,
+            None => return Err(invalid_block("block size field not found")),
+        };
+        if block_size < GZIP_HEADER_LEN + xlen + FOOTER_LEN {
+            return Err(invalid_block("invalid block size"));
+        }
+
+        let mut rest = vec![0; block_size - GZIP_HEADER_LEN - xlen];
+        self.inner.read_exact(&mut rest)?;
+        let (cdata, footer) = rest.split_at(rest.len() - FOOTER_LEN);
+        let _ = DeflateDecoder::new(cdata).read_to_end(&mut self.block)?;
+        if self.block.len() != read_le(&footer[4..]) as usize {
+            return Err(invalid_block("block size does not match its data"));
+        }
+        self.next_coffset = coffset + block_size as u64;
+        Ok(true)
+    }
+}
+
+/// Helper function to find the total block size in the extra subfields of a BGZF block header.
+fn find_block_size(extra: &[u8]) -> Option<usize> {
+    let mut rest = extra;
+    while rest.len() >= 4 {
+        let slen = read_le(&rest[2..4]) as usize;
+        if rest.len() < 4 + slen {
+            break;
+        }
+        if rest[0] == b'B' && rest[1] == b'C' && slen == 2 {
+            return Some(read_le(&rest[4..6]) as usize + 1);
+        }
+        rest = &rest[4 + slen..];
+    }
+    None
+}
+
+/// Helper function to read as many bytes as possible into the given buffer.
+///
+/// Unlike `read_exact`, reaching the end of the input is not an error.
+fn read_full<R: Read>(reader: &mut R, buf: &mut [u8]) -> io::Result<usize> {
+    let mut num_read = 0;
+    while num_read < buf.len() {
+        match reader.read(&mut buf[num_read..]) {
+            Ok(0) => break,
+            Ok(n) => num_read += n,
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {},
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(num_read)
+}
+
+/// Helper function to create errors of invalid BGZF blocks.
+fn invalid_block(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("invalid BGZF data: {}", msg))
+}
+
+/// Helper function to read a little-endian value from the given bytes.
+fn read_le(bytes: &[u8]) -> u32 {
+    bytes.iter().rev().fold(0, |acc, &b| (acc << 8) | b as u32)
+}
+
+/// Helper function to append the given number of little-endian bytes of a value.
+pub(crate) fn push_le(bytes: &mut Vec<u8>, value: u64, num_bytes: usize) {
+    for idx in 0..num_bytes {
+        bytes.push((value >> (8 * idx)) as u8);
+    }
+}
@@ -6,22 +6,56 @@ line. It is used most prominently by the
 
 A minimum specification of the columns can be found on
 [this page](https://genome.ucsc.edu/goldenPath/gbdDescriptionsOld.html#RefFlat).
+
+The refFlat format is a variant of the genePred format, with an extra leading gene name column.
+The reader and writer in this module also support the plain genePred format and the extended
+genePredExt format, optionally with a leading `bin` column as found in UCSC table dumps. The
+column layouts are described on
+[this page](https://genome.ucsc.edu/FAQ/FAQformat.html#format9).
 */
 use std::cmp::{max, min};
 use std::convert::AsRef;
 use std::error::Error;
+use std::fmt;
 use std::io;
 use std::num::ParseIntError;
 use std::fs;
 use std::path::Path;
 use std::str::FromStr;
+use std::vec;
 
 use csv;
 use itertools::{GroupBy, Group, Itertools};
 use linked_hash_map::LinkedHashMap;
 
-use {Coord, Gene, GBuilder, Strand, Transcript, TBuilder, DEF_ID, INIT_COORD};
-use utils::{OptionDeref, update_seq_name};
+use bio::utils::Interval;
+
+use {Coord, ErrorPolicy, ErrorReport, ExonFeature, ExonFeatureKind, Gene, GBuilder, GzipReader,
+     IdLines, ModelError, Strand, Transcript, TBuilder, DEF_ID, INIT_COORD};
+use policy::ErrorHandler;
+use utils::{LinePos, LineReader, OptionDeref, update_seq_name};
+
+
+/// Name for attribute key of record scores.
+const SCORE_STR: &'static str = "score";
+
+/// Name for attribute key of the coding region start completeness.
+const CDS_START_STAT_STR: &'static str = "cdsStartStat";
+
+/// Name for attribute key of the coding region end completeness.
+const CDS_END_STAT_STR: &'static str = "cdsEndStat";
+
+/// Number of columns in a genePred row.
+const GENEPRED_NUM_COLS: usize = 10;
+
+/// Number of columns in a genePredExt row.
+const GENEPRED_EXT_NUM_COLS: usize = 15;
+
+/// Exon frame value of exons without any coding region.
+const NO_FRAME: i8 = -1;
+
+/// The type of errors along with the name of the offending column, if known.
+type ColumnError = (Option<&'static str>, ::Error);
 
 
 quick_error! {
@@ -56,12 +90,111 @@ quick_error! {
                                self_.description(), tid.as_deref().unwrap_or(DEF_ID))
             cause(err)
         }
+        /// Occurs when any of the other numeric columns does not contain a valid integer value.
+        InvalidNumber(err: ParseIntError, tid: Option<String>) {
+            description(err.description())
+            display(self_) -> ("{}, transcript ID: {}",
+                               self_.description(), tid.as_deref().unwrap_or(DEF_ID))
+            cause(err)
+        }
+        /// Occurs when the strand column does not contain a single character.
+        InvalidStrand(tid: Option<String>) {
+            description("strand column is not a single character")
+            display(self_) -> ("{}, transcript ID: {}",
+                               self_.description(), tid.as_deref().unwrap_or(DEF_ID))
+        }
+        /// Occurs when the `cdsStartStat` or `cdsEndStat` column has an unknown value.
+        InvalidCdsStat(stat: String, tid: Option<String>) {
+            description("invalid coding region completeness value")
+            display(self_) -> ("{}: '{}', transcript ID: {}",
+                               self_.description(), stat, tid.as_deref().unwrap_or(DEF_ID))
+        }
+        /// Occurs when a row has a number of columns other than what its layout requires.
+        InvalidColumnCount(count: usize) {
+            description("unexpected number of columns")
+            display(self_) -> ("{}: {}", self_.description(), count)
+        }
         /// Errors propagated from the underlying `csv` crate.
         Csv(err: csv::Error) {
             description(err.description())
             from()
             cause(err)
         }
+        /// Errors that occur when reading from the underlying reader.
+        Io(err: io::Error) {
+            description(err.description())
+            display("{}", err)
+            from()
+            cause(err)
+        }
+    }
+}
+
+/// Column layouts of the genePred family of formats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Layout {
+    /// refFlat layout: genePred columns preceded by a gene name column.
+    RefFlat,
+    /// Plain genePred layout, without any gene name column.
+    GenePred,
+    /// genePredExt layout: genePred columns followed by the `score`, `name2`, `cdsStartStat`,
+    /// `cdsEndStat`, and `exonFrames` columns.
+    GenePredExt,
+}
+
+impl Layout {
+
+    /// Returns the number of columns in a row of the layout, excluding any `bin` column.
+    pub fn num_columns(&self) -> usize {
+        match *self {
+            Layout::RefFlat => GENEPRED_NUM_COLS + 1,
+            Layout::GenePred => GENEPRED_NUM_COLS,
+            Layout::GenePredExt => GENEPRED_EXT_NUM_COLS,
+        }
+    }
+}
+
+impl Default for Layout {
+    fn default() -> Layout {
+        Layout::RefFlat
+    }
+}
+
+/// Completeness of a coding region end, as denoted in the `cdsStartStat` and `cdsEndStat`
+/// columns of genePredExt rows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CdsStat {
+    /// No coding region (`none`).
+    None,
+    /// Unknown completeness (`unk`).
+    Unknown,
+    /// Incomplete coding region end (`incmpl`).
+    Incomplete,
+    /// Complete coding region end (`cmpl`).
+    Complete,
+}
+
+impl CdsStat {
+
+    /// Returns the string representation of the value, as used in genePredExt rows.
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            CdsStat::None => "none",
+            CdsStat::Unknown => "unk",
+            CdsStat::Incomplete => "incmpl",
+            CdsStat::Complete => "cmpl",
+        }
+    }
+
+    /// Creates the value from its string representation.
+    pub fn parse(raw: &str) -> Option<CdsStat> {
+        match raw {
+            "none" => Some(CdsStat::None),
+            "unk" => Some(CdsStat::Unknown),
+            "incmpl" => Some(CdsStat::Incomplete),
+            "cmpl" => Some(CdsStat::Complete),
+            _ => None,
+        }
     }
 }
 
@@ -96,6 +229,11 @@ pub type RefFlatRow = (String, String, String, char, u64, u64, u64, u64, usize,
 /// * The exon coordinates are represented here as `Vec<u64>`, as opposed to just `String` in the
 ///   row type.
 /// * The number of exon start and end coordinates are guaranteed to be equal in this type.
+///
+/// Records read from genePred rows use the transcript identifier as their gene identifier, while
+/// records read from genePredExt rows use the `name2` column, if it is not empty. The `score`,
+/// `cdsStartStat`, `cdsEndStat`, and `exonFrames` values are only defined for records read from
+/// genePredExt rows.
 #[derive(Debug, Clone, PartialEq)]
 pub struct RefFlatRecord {
     gene_id: String,
@@ -108,6 +246,10 @@ pub struct RefFlatRecord {
     coding_end: u64,
     exon_starts: Vec<u64>,
     exon_ends: Vec<u64>,
+    score: Option<i64>,
+    cds_start_stat: Option<CdsStat>,
+    cds_end_stat: Option<CdsStat>,
+    exon_frames: Vec<i8>,
 }
 
 impl RefFlatRecord {
@@ -235,6 +377,45 @@ impl RefFlatRecord {
         Ok(())
     }
 
+    /// Returns the score of the record.
+    pub fn score(&self) -> Option<i64> {
+        self.score
+    }
+
+    /// Sets the score of the record.
+    pub fn set_score(&mut self, score: Option<i64>) {
+        self.score = score;
+    }
+
+    /// Returns the completeness of the genome-wise 5'-most coding region end.
+    pub fn cds_start_stat(&self) -> Option<CdsStat> {
+        self.cds_start_stat
+    }
+
+    /// Sets the completeness of the genome-wise 5'-most coding region end.
+    pub fn set_cds_start_stat(&mut self, stat: Option<CdsStat>) {
+        self.cds_start_stat = stat;
+    }
+
+    /// Returns the completeness of the genome-wise 3'-most coding region end.
+    pub fn cds_end_stat(&self) -> Option<CdsStat> {
+        self.cds_end_stat
+    }
+
+    /// Sets the completeness of the genome-wise 3'-most coding region end.
+    pub fn set_cds_end_stat(&mut self, stat: Option<CdsStat>) {
+        self.cds_end_stat = stat;
+    }
+
+    /// Returns a slice of the exon frames of the record.
+    ///
+    /// Following the genePredExt convention, each value is the position of the transcript-wise
+    /// 5'-most coding base of the exon within its codon, or -1 if the exon is not coding. The
+    /// slice is empty if the record has no exon frames.
+    pub fn exon_frames(&self) -> &[i8] {
+        self.exon_frames.as_slice()
+    }
+
     /// Creates a record from a row.
     ///
     /// This method will return an error if:
@@ -266,10 +447,141 @@ impl RefFlatRecord {
             coding_end: row.7,
             exon_starts: exon_starts,
             exon_ends: exon_ends,
+            score: None,
+            cds_start_stat: None,
+            cds_end_stat: None,
+            exon_frames: Vec::new(),
+        })
+    }
+
+    /// Creates a record from the columns of a row in the given layout.
+    ///
+    /// If `has_bin` is true, the first column is expected to be the `bin` column and is ignored.
+    ///
+    /// This method will return an error if:
+    /// * the number of columns does not match the layout,
+    /// * any of the numeric columns do not contain valid integer values,
+    /// * the strand column is not a single character,
+    /// * any of the `cdsStartStat` or `cdsEndStat` columns have an unknown value, or
+    /// * the number of exon coordinates or exon frames and the number of exons column value are
+    ///   not equal
+    pub fn try_from_columns(
+        cols: Vec<String>,
+        layout: Layout,
+        has_bin: bool,
+    ) -> ::Result<Self> {
+        Self::parse_columns(cols, layout, has_bin).map_err(|(_, err)| err)
+    }
+
+    /// Creates a record from the columns of a row in the given layout, returning the name of the
+    /// offending column along with any error.
+    fn parse_columns(
+        mut cols: Vec<String>,
+        layout: Layout,
+        has_bin: bool,
+    ) -> Result<Self, ColumnError> {
+
+        let num_bin_cols = if has_bin { 1 } else { 0 };
+        if cols.len() != layout.num_columns() + num_bin_cols {
+            return Err((None, ::Error::from(RefFlatError::InvalidColumnCount(cols.len()))));
+        }
+        let _ = cols.drain(..num_bin_cols);
+        let gene_name =
+            if layout == Layout::RefFlat {
+                Some(cols.remove(0))
+            } else {
+                None
+            };
+        let ext_cols = cols.split_off(GENEPRED_NUM_COLS);
+
+        let tid = cols[0].clone();
+        let column_err =
+            |field: &'static str, err: RefFlatError| (Some(field), ::Error::from(err));
+        let parse_num = |raw: &str, field: &'static str| u64::from_str(raw)
+            .map_err(|e| column_err(field, RefFlatError::InvalidNumber(e, Some(tid.clone()))));
+
+        let transcript_start = parse_num(&cols[3], "txStart")?;
+        let transcript_end = parse_num(&cols[4], "txEnd")?;
+        let coding_start = parse_num(&cols[5], "cdsStart")?;
+        let coding_end = parse_num(&cols[6], "cdsEnd")?;
+        let num_exons = parse_num(&cols[7], "exonCount")? as usize;
+        let exon_starts = Self::parse_coords(&cols[8], &tid)
+            .map_err(|e| column_err("exonStarts", e))?;
+        let exon_ends = Self::parse_coords(&cols[9], &tid)
+            .map_err(|e| column_err("exonEnds", e))?;
+        if exon_starts.len() != num_exons || exon_starts.len() != exon_ends.len() {
+            let err = RefFlatError::ExonCountMismatch(Some(tid.clone()));
+            return Err(column_err("exonCount", err));
+        }
+
+        let strand = {
+            let mut strand_chars = cols[2].chars();
+            match (strand_chars.next(), strand_chars.next()) {
+                (Some(c), None) => c,
+                _ => {
+                    let err = RefFlatError::InvalidStrand(Some(tid.clone()));
+                    return Err(column_err("strand", err));
+                },
+            }
+        };
+
+        let (score, gene_name, cds_start_stat, cds_end_stat, exon_frames) =
+            if ext_cols.is_empty() {
+                (None, gene_name, None, None, Vec::new())
+            } else {
+                let score = i64::from_str(&ext_cols[0])
+                    .map_err(|e| column_err("score",
+                                            RefFlatError::InvalidNumber(e, Some(tid.clone()))))?;
+                let name2 =
+                    if ext_cols[1].is_empty() {
+                        gene_name
+                    } else {
+                        Some(ext_cols[1].clone())
+                    };
+                let cds_start_stat = Self::parse_cds_stat(&ext_cols[2], &tid)
+                    .map_err(|e| column_err("cdsStartStat", e))?;
+                let cds_end_stat = Self::parse_cds_stat(&ext_cols[3], &tid)
+                    .map_err(|e| column_err("cdsEndStat", e))?;
+                let exon_frames = Self::parse_frames(&ext_cols[4], &tid)
+                    .map_err(|e| column_err("exonFrames", e))?;
+                if exon_frames.len() != num_exons {
+                    let err = RefFlatError::ExonCountMismatch(Some(tid.clone()));
+                    return Err(column_err("exonFrames", err));
+                }
+                (Some(score), name2, Some(cds_start_stat), Some(cds_end_stat), exon_frames)
+            };
+
+        let mut cols = cols.into_iter();
+        let transcript_id = cols.next().unwrap();
+        let seq_name = cols.next().unwrap();
+
+        Ok(RefFlatRecord {
+            gene_id: gene_name.unwrap_or_else(|| transcript_id.clone()),
+            transcript_id: transcript_id,
+            seq_name: seq_name,
+            strand: strand,
+            transcript_start: transcript_start,
+            transcript_end: transcript_end,
+            coding_start: coding_start,
+            coding_end: coding_end,
+            exon_starts: exon_starts,
+            exon_ends: exon_ends,
+            score: score,
+            cds_start_stat: cds_start_stat,
+            cds_end_stat: cds_end_stat,
+            exon_frames: exon_frames,
         })
     }
 
     /// Transforms the record into a transcript.
+    ///
+    /// Records whose `cdsStartStat` or `cdsEndStat` value is `none` are treated as non-coding,
+    /// regardless of their coding coordinates. Each coding region end whose status is `incmpl`
+    /// or `unk` has no codon feature: a start codon there is left out and the bases of a stop
+    /// codon there are part of the CDS instead. The exon frames, when defined, set the frames
+    /// of the CDS features of their exons. The score and the coding region completeness
+    /// values, when defined, are stored as the `score`, `cdsStartStat`, and `cdsEndStat`
+    /// attributes of the transcript.
     pub fn into_transcript(self) -> ::Result<Transcript> {
 
         if self.transcript_id.is_empty() {
@@ -278,8 +590,10 @@ impl RefFlatRecord {
         if self.gene_id.is_empty() {
             return Err(::Error::from(::RefFlatError::MissingGeneId));
         }
+        let no_cds = self.cds_start_stat == Some(CdsStat::None)
+            || self.cds_end_stat == Some(CdsStat::None);
         let coding_interval =
-            if self.coding_start == self.coding_end {
+            if self.coding_start == self.coding_end || no_cds {
                 None
             } else {
                 Some((self.coding_start, self.coding_end))
@@ -288,14 +602,28 @@ impl RefFlatRecord {
         let exon_coords = self.exon_starts.into_iter().zip(self.exon_ends.into_iter())
             .collect::<Vec<Coord<u64>>>();
 
-        TBuilder::new(self.seq_name, self.transcript_start, self.transcript_end)
+        let mut builder = TBuilder::new(self.seq_name, self.transcript_start, self.transcript_end)
             .id(self.transcript_id)
             .gene_id(self.gene_id)
             .strand_char(self.strand)
             .coords(exon_coords, coding_interval)
-            .coding_incl_stop(true)
-            .build()
-            .map_err(::Error::from)
+            .coding_incl_stop(true);
+        if let Some(score) = self.score {
+            builder = builder.attribute(SCORE_STR, score.to_string());
+        }
+        if let Some(stat) = self.cds_start_stat {
+            builder = builder.attribute(CDS_START_STAT_STR, stat.as_str());
+        }
+        if let Some(stat) = self.cds_end_stat {
+            builder = builder.attribute(CDS_END_STAT_STR, stat.as_str());
+        }
+
+        let mut transcript = builder.build()?;
+        if coding_interval.is_some() {
+            adjust_coding_features(&mut transcript, self.cds_start_stat, self.cds_end_stat,
+                                   &self.exon_frames)?;
+        }
+        Ok(transcript)
     }
 
     /// Parses the given raw coordinate string into a vector of u64s.
@@ -315,13 +643,52 @@ impl RefFlatRecord {
         }
         Ok(res)
     }
+
+    /// Parses the given raw exon frames string into a vector of i8s.
+    ///
+    /// The transcript identifier argument is required for when an error type is returned.
+    #[inline]
+    fn parse_frames(raw_frames: &str, tid: &str) -> Result<Vec<i8>, RefFlatError> {
+        let rframes = raw_frames
+            .trim_matches(',')
+            .split(',')
+            .map(|item| i8::from_str(item)
+                 .map_err(|e| RefFlatError::InvalidNumber(e, Some(tid.to_owned()))));
+
+        let mut res = vec![];
+        for rframe in rframes {
+            res.push(rframe?);
+        }
+        Ok(res)
+    }
+
+    /// Parses the given raw `cdsStartStat` or `cdsEndStat` value.
+    ///
+    /// The transcript identifier argument is required for when an error type is returned.
+    #[inline]
+    fn parse_cds_stat(raw_stat: &str, tid: &str) -> Result<CdsStat, RefFlatError> {
+        CdsStat::parse(raw_stat)
+            .ok_or_else(|| RefFlatError::InvalidCdsStat(raw_stat.to_owned(),
+                                                         Some(tid.to_owned())))
+    }
 }
 
 /// RefFlat reader.
+///
+/// By default, the reader expects refFlat rows. Other genePred layouts can be read by setting
+/// the `layout` and `has_bin` values of the reader. Empty and comment lines, such as the `#bin`
+/// header line of UCSC table dumps, are skipped. Errors of records that can not be read are
+/// handled according to the error policy of the reader.
 pub struct Reader<R: io::Read> {
-    inner: csv::Reader<R>,
+    inner: LineReader<R>,
+    layout: Layout,
+    has_bin: bool,
     seq_name_prefix: Option<String>,
     seq_name_lstrip: Option<String>,
+    error_policy: ErrorPolicy,
+    report: ErrorReport,
+    record_id_lines: bool,
+    id_lines: IdLines,
 }
 
 impl<R: io::Read> Reader<R> {
@@ -329,14 +696,30 @@ impl<R: io::Read> Reader<R> {
     /// Creates a refFlat reader from another reader.
     pub fn from_reader(in_reader: R) -> Reader<R> {
         Reader {
-            inner: csv::Reader::from_reader(in_reader)
-                .delimiter(b'\t')
-                .has_headers(false),
+            inner: LineReader::new(in_reader),
+            layout: Layout::default(),
+            has_bin: false,
             seq_name_prefix: None,
             seq_name_lstrip: None,
+            error_policy: ErrorPolicy::default(),
+            report: ErrorReport::default(),
+            record_id_lines: false,
+            id_lines: IdLines::default(),
         }
     }
 
+    /// Sets the column layout of the rows to read.
+    pub fn layout(&mut self, layout: Layout) -> &mut Self {
+        self.layout = layout;
+        self
+    }
+
+    /// Sets whether the rows to read start with a `bin` column.
+    pub fn has_bin(&mut self, has_bin: bool) -> &mut Self {
+        self.has_bin = has_bin;
+        self
+    }
+
     /// Sets the reader to add the given prefix to all sequence names.
     pub fn seq_name_prefix<T>(&mut self, prefix: T) -> &mut Self
         where T: Into<String>
@@ -354,71 +737,161 @@ impl<R: io::Read> Reader<R> {
         self
     }
 
+    /// Sets how errors of records that can not be read are handled.
+    pub fn error_policy(&mut self, policy: ErrorPolicy) -> &mut Self {
+        self.error_policy = policy;
+        self
+    }
+
+    /// Returns the records rejected by the iterators of the reader so far.
+    ///
+    /// Records are only stored when the `Collect` error policy is used.
+    pub fn error_report(&self) -> &ErrorReport {
+        &self.report
+    }
+
+    /// Sets the reader to record the line numbers of the transcript and gene identifiers in
+    /// the records it reads.
+    pub fn record_id_lines(&mut self, record_id_lines: bool) -> &mut Self {
+        self.record_id_lines = record_id_lines;
+        self
+    }
+
+    /// Returns the line numbers of the identifiers read by the iterators of the reader so far.
+    ///
+    /// Lines are only recorded when set with `record_id_lines`. Records that can not be parsed
+    /// are not recorded, since their errors already contain their line numbers.
+    pub fn id_lines(&self) -> &IdLines {
+        &self.id_lines
+    }
+
     /// Creates an iterator of refFlat records.
+    ///
+    /// Empty lines are skipped. Errors of records that can not be parsed are returned as
+    /// `Error::Line` values, which contain the line number, the line, and the offending column
+    /// name.
     pub fn records_stream(&mut self) -> RefFlatRecordsStream<R> {
         RefFlatRecordsStream {
-            inner: self.inner.decode(),
-            seq_name_prefix: self.seq_name_prefix.as_deref(),
-            seq_name_lstrip: self.seq_name_lstrip.as_deref(),
+            inner: LocatedRecords {
+                inner: &mut self.inner,
+                layout: self.layout,
+                has_bin: self.has_bin,
+                seq_name_prefix: self.seq_name_prefix.as_deref(),
+                seq_name_lstrip: self.seq_name_lstrip.as_deref(),
+                id_lines: if self.record_id_lines { Some(&mut self.id_lines) } else { None },
+            },
+            handler: ErrorHandler::new(self.error_policy, &mut self.report),
         }
     }
 
     /// Creates an iterator of transcripts.
+    ///
+    /// Errors of transcripts that can not be created are also returned as `Error::Line` values.
     pub fn transcripts_stream(&mut self) -> RefFlatTranscriptsStream<R> {
+        let RefFlatRecordsStream { inner, handler } = self.records_stream();
         RefFlatTranscriptsStream {
-            inner: self.records_stream()
+            inner: inner,
+            handler: handler,
         }
     }
 
     /// Creates an iterator of genes.
     ///
     /// This iterator groups consecutive records based on their gene identifiers into genes.
+    /// Errors of records and transcripts are returned as `Error::Line` values, one for each
+    /// offending line. A gene is still created from its remaining transcripts, if any.
     pub fn genes_stream(&mut self) -> RefFlatGenesStream<R> {
+        let RefFlatRecordsStream { inner, handler } = self.records_stream();
         RefFlatGenesStream {
-            inner: self.records_stream()
-                .group_by(RefFlatGenesStream::<R>::group_func),
+            inner: inner.group_by(RefFlatGenesStream::<R>::group_func),
+            pending: Vec::new().into_iter(),
+            handler: handler,
         }
     }
 }
 
-impl Reader<fs::File> {
+impl Reader<GzipReader<fs::File>> {
 
     /// Creates a refFlat reader that reads from the given path.
+    ///
+    /// Gzip and BGZF-compressed files are decompressed transparently.
     pub fn from_file<P: AsRef<Path>>(path: P) -> io::Result<Self> {
-        fs::File::open(path).map(Reader::from_reader)
+        GzipReader::from_file(path).map(Reader::from_reader)
     }
 }
 
 /// Iterator over refFlat records.
 pub struct RefFlatRecordsStream<'a, R: 'a> where R: io::Read {
-    inner: csv::DecodedRecords<'a, R, RefFlatRow>,
-    seq_name_prefix: Option<&'a str>,
-    seq_name_lstrip: Option<&'a str>,
+    inner: LocatedRecords<'a, R>,
+    handler: ErrorHandler<'a>,
 }
 
 impl<'a, R> Iterator for RefFlatRecordsStream<'a, R> where R: io::Read {
 
     type Item = ::Result<RefFlatRecord>;
 
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.handler.is_stopped() {
+            let result = match self.inner.next() {
+                Some(result) => result.map(|(_, rec)| rec),
+                None => return None,
+            };
+            if let Some(result) = self.handler.handle(None, result) {
+                return Some(result);
+            }
+        }
+        None
+    }
+}
+
+/// Iterator over refFlat records along with the locations of their lines.
+///
+/// Unlike the public iterators, this iterator returns all errors regardless of the error policy.
+struct LocatedRecords<'a, R: 'a> where R: io::Read {
+    inner: &'a mut LineReader<R>,
+    layout: Layout,
+    has_bin: bool,
+    seq_name_prefix: Option<&'a str>,
+    seq_name_lstrip: Option<&'a str>,
+    /// Line numbers of the identifiers, if they are recorded.
+    id_lines: Option<&'a mut IdLines>,
+}
+
+impl<'a, R> Iterator for LocatedRecords<'a, R> where R: io::Read {
+
+    type Item = ::Result<(LinePos, RefFlatRecord)>;
+
     fn next(&mut self) -> Option<Self::Item> {
         let lstrip = self.seq_name_lstrip.map(|v| (v, v.len()));
         let prefix = self.seq_name_prefix;
-        self.inner.next()
-            .map(|row| {
-                row
-                    .or_else(|err| Err(::Error::from(RefFlatError::from(err))))
-                    .map(|mut row| {
-                        update_seq_name(&mut row.2, prefix, lstrip);
-                        row
-                    })
-                    .and_then(RefFlatRecord::try_from_row)
-            })
+        let (layout, has_bin) = (self.layout, self.has_bin);
+        while let Some(result) = self.inner.next_line(|e| ::Error::from(RefFlatError::from(e))) {
+            let pos = match result {
+                Ok(pos) => pos,
+                Err(e) => return Some(Err(e)),
+            };
+            if pos.raw.trim().is_empty() || pos.raw.starts_with('#') {
+                continue;
+            }
+            let mut rec = match RefFlatRecord::parse_columns(pos.columns(), layout, has_bin) {
+                Ok(rec) => rec,
+                Err((field, err)) => return Some(Err(pos.error(field, err))),
+            };
+            update_seq_name(&mut rec.seq_name, prefix, lstrip);
+            if let Some(ref mut id_lines) = self.id_lines {
+                id_lines.add(&rec.transcript_id, pos.line);
+                id_lines.add(&rec.gene_id, pos.line);
+            }
+            return Some(Ok((pos, rec)));
+        }
+        None
     }
 }
 
 /// Iterator over transcripts created from refFlat records.
 pub struct RefFlatTranscriptsStream<'a, R: 'a> where R: io::Read {
-    inner: RefFlatRecordsStream<'a, R>,
+    inner: LocatedRecords<'a, R>,
+    handler: ErrorHandler<'a>,
 }
 
 impl<'a, R> Iterator for RefFlatTranscriptsStream<'a, R> where R: io::Read {
@@ -426,8 +899,20 @@ impl<'a, R> Iterator for RefFlatTranscriptsStream<'a, R> where R: io::Read {
     type Item = ::Result<Transcript>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.inner.next()
-            .map(|record| record.and_then(|rec| rec.into_transcript()))
+        while !self.handler.is_stopped() {
+            let (tid, result) = match self.inner.next() {
+                Some(Ok((pos, rec))) => {
+                    let tid = Some(rec.transcript_id.clone());
+                    (tid, rec.into_transcript().map_err(|e| pos.error(None, e)))
+                },
+                Some(Err(e)) => (None, Err(e)),
+                None => return None,
+            };
+            if let Some(result) = self.handler.handle(tid, result) {
+                return Some(result);
+            }
+        }
+        None
     }
 }
 
@@ -437,54 +922,79 @@ impl<'a, R> Iterator for RefFlatTranscriptsStream<'a, R> where R: io::Read {
 type GroupKey = Option<(String, String, char)>;
 
 /// The type of the function used for creating record-grouping keys for genes.
-type GroupFunc = fn(&::Result<RefFlatRecord>) -> GroupKey;
+type GroupFunc = fn(&::Result<(LinePos, RefFlatRecord)>) -> GroupKey;
 
 /// The type of the grouped records for creating genes.
-type GroupedRecords<'a, 'b, R> = Group<'b, GroupKey, RefFlatRecordsStream<'a, R>, GroupFunc>;
+type GroupedRecords<'a, 'b, R> = Group<'b, GroupKey, LocatedRecords<'a, R>, GroupFunc>;
+
+/// The type of the results created from grouped records, along with their identifiers.
+type GroupResult = (Option<String>, ::Result<Gene>);
 
 /// Iterator over genes created from refFlat records.
 pub struct RefFlatGenesStream<'a, R: 'a> where R: io::Read, {
-    inner: GroupBy<GroupKey, RefFlatRecordsStream<'a, R>, GroupFunc>,
+    inner: GroupBy<GroupKey, LocatedRecords<'a, R>, GroupFunc>,
+    pending: vec::IntoIter<GroupResult>,
+    handler: ErrorHandler<'a>,
 }
 
 impl<'a, R> RefFlatGenesStream<'a, R> where R: io::Read {
 
     /// Creates the group key from the given refFlat record result.
-    fn group_func(result: &::Result<RefFlatRecord>) -> GroupKey {
+    fn group_func(result: &::Result<(LinePos, RefFlatRecord)>) -> GroupKey {
         result.as_ref().ok()
-            .map(|ref res| (res.gene_id.clone(), res.seq_name.clone(), res.strand.clone()))
+            .map(|&(_, ref res)| (res.gene_id.clone(), res.seq_name.clone(), res.strand.clone()))
     }
 
-    /// Creates genes from the given grouped records.
-    fn group_to_gene<'b>(group: (GroupKey, GroupedRecords<'a, 'b, R>)) -> ::Result<Gene> {
+    /// Creates the results of the gene from the given grouped records.
+    ///
+    /// Errors of the records and transcripts of the gene come first, each with the identifier
+    /// of its transcript if known. The gene is created from the remaining transcripts, if any.
+    fn group_to_results<'b>(group: (GroupKey, GroupedRecords<'a, 'b, R>)) -> Vec<GroupResult> {
         let (group_key, records) = group;
-        match group_key {
-
-            None => Err(records.filter_map(|x| x.err()).next().unwrap()),
-
-            Some((gid, seq_name, strand_char)) => {
-                let mut transcripts = LinkedHashMap::new();
-                let (mut gene_start, mut gene_end) = INIT_COORD;
-                for record in records {
-                    let transcript = record.and_then(|rec| rec.into_transcript())?;
-                    gene_start = min(gene_start, transcript.start());
-                    gene_end = max(gene_end, transcript.end());
-                    let tid = transcript.id().map(|id| id.to_owned())
-                        .ok_or(::Error::from(RefFlatError::MissingTranscriptId))?;
-                    let existing_trx = transcripts.insert(tid, transcript);
-                    if existing_trx.is_some() {
-                        let err = RefFlatError::DuplicateTranscriptId(Some(gid));
-                        return Err(::Error::from(err));
-                    }
-                }
-                GBuilder::new(seq_name, gene_start, gene_end)
-                    .id(gid)
-                    .strand_char(strand_char)
-                    .transcripts(transcripts)
-                    .transcript_coding_incl_stop(true)
-                    .build()
-            },
+        let (gid, seq_name, strand_char) = match group_key {
+            Some(key) => key,
+            None => return records.filter_map(|x| x.err()).map(|e| (None, Err(e))).collect(),
+        };
+
+        let mut results = Vec::new();
+        let mut transcripts = LinkedHashMap::new();
+        let (mut gene_start, mut gene_end) = INIT_COORD;
+        for record in records {
+            let (pos, rec) = match record {
+                Ok(located) => located,
+                Err(e) => {
+                    results.push((None, Err(e)));
+                    continue;
+                },
+            };
+            let tid = rec.transcript_id.clone();
+            let transcript = match rec.into_transcript() {
+                Ok(transcript) => transcript,
+                Err(e) => {
+                    results.push((Some(tid), Err(pos.error(None, e))));
+                    continue;
+                },
+            };
+            if transcripts.contains_key(&tid) {
+                let err = RefFlatError::DuplicateTranscriptId(Some(gid.clone()));
+                results.push((Some(tid), Err(pos.error(Some("name"), err))));
+                continue;
+            }
+            gene_start = min(gene_start, transcript.start());
+            gene_end = max(gene_end, transcript.end());
+            let _ = transcripts.insert(tid, transcript);
         }
+
+        if !transcripts.is_empty() {
+            let gene = GBuilder::new(seq_name, gene_start, gene_end)
+                .id(gid.clone())
+                .strand_char(strand_char)
+                .transcripts(transcripts)
+                .transcript_coding_incl_stop(true)
+                .build();
+            results.push((Some(gid), gene));
+        }
+        results
     }
 }
 
@@ -493,13 +1003,33 @@ impl<'a, R> Iterator for RefFlatGenesStream<'a, R> where R: io::Read {
     type Item = ::Result<Gene>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.inner.into_iter().map(Self::group_to_gene).next()
+        while !self.handler.is_stopped() {
+            let (id, result) = match self.pending.next() {
+                Some(next) => next,
+                None => match self.inner.into_iter().next() {
+                    Some(group) => {
+                        self.pending = Self::group_to_results(group).into_iter();
+                        continue;
+                    },
+                    None => return None,
+                },
+            };
+            if let Some(result) = self.handler.handle(id, result) {
+                return Some(result);
+            }
+        }
+        None
     }
 }
 
 /// RefFlat writer.
+///
+/// By default, the writer writes refFlat rows. Other genePred layouts can be written by setting
+/// the `layout` and `has_bin` values of the writer.
 pub struct Writer<W: io::Write> {
     inner: csv::Writer<W>,
+    layout: Layout,
+    has_bin: bool,
 }
 
 impl<W: io::Write> Writer<W> {
@@ -510,10 +1040,30 @@ impl<W: io::Write> Writer<W> {
             inner: csv::Writer::from_writer(in_writer)
                 .delimiter(b'\t')
                 .quote_style(csv::QuoteStyle::Never)
+                .flexible(true),
+            layout: Layout::default(),
+            has_bin: false,
         }
     }
 
+    /// Sets the column layout of the rows to write.
+    pub fn layout(&mut self, layout: Layout) -> &mut Self {
+        self.layout = layout;
+        self
+    }
+
+    /// Sets whether the rows to write start with a `bin` column.
+    ///
+    /// The `bin` values are computed from the transcript coordinates using the UCSC binning
+    /// scheme.
+    pub fn has_bin(&mut self, has_bin: bool) -> &mut Self {
+        self.has_bin = has_bin;
+        self
+    }
+
     /// Writes the given row.
+    ///
+    /// The row is always written in the refFlat layout, regardless of the layout of the writer.
     pub fn write(&mut self, row: &RefFlatRow) -> ::Result<()> {
         self.inner
             .encode((&row.0, &row.1, &row.2, row.3, row.4, row.5, row.6, row.7, row.8,
@@ -522,39 +1072,67 @@ impl<W: io::Write> Writer<W> {
     }
 
     /// Writes the given record.
+    ///
+    /// When writing genePredExt rows, undefined values are filled in as follows:
+    ///
+    /// * The score is set to 0.
+    /// * The `cdsStartStat` and `cdsEndStat` values are set to `none` for non-coding records and
+    ///   to `unk` otherwise.
+    /// * The exon frames are computed from the exon and coding coordinates.
     pub fn write_record(&mut self, record: &RefFlatRecord) -> ::Result<()> {
-        let mut exon_starts = record.exon_starts.iter().join(",");
-        exon_starts.push(',');
-        let mut exon_ends = record.exon_ends.iter().join(",");
-        exon_ends.push(',');
+        let mut cols = Vec::with_capacity(self.layout.num_columns() + 1);
+        if self.has_bin {
+            cols.push(bin_from_range(record.transcript_start, record.transcript_end).to_string());
+        }
+        if self.layout == Layout::RefFlat {
+            cols.push(record.gene_id.clone());
+        }
+        cols.push(record.transcript_id.clone());
+        cols.push(record.seq_name.clone());
+        cols.push(record.strand.to_string());
+        cols.push(record.transcript_start.to_string());
+        cols.push(record.transcript_end.to_string());
+        cols.push(record.coding_start.to_string());
+        cols.push(record.coding_end.to_string());
+        cols.push(record.num_exons().to_string());
+        cols.push(join_values(&record.exon_starts));
+        cols.push(join_values(&record.exon_ends));
+
+        if self.layout == Layout::GenePredExt {
+            let def_stat =
+                if record.coding_start == record.coding_end {
+                    CdsStat::None
+                } else {
+                    CdsStat::Unknown
+                };
+            let exon_frames =
+                if record.exon_frames.is_empty() {
+                    record.calc_exon_frames()
+                } else {
+                    record.exon_frames.clone()
+                };
+            cols.push(record.score.unwrap_or(0).to_string());
+            cols.push(record.gene_id.clone());
+            cols.push(record.cds_start_stat.unwrap_or(def_stat).as_str().to_owned());
+            cols.push(record.cds_end_stat.unwrap_or(def_stat).as_str().to_owned());
+            cols.push(join_values(&exon_frames));
+        }
+
         self.inner
-            .encode((&record.gene_id, &record.transcript_id, &record.seq_name,
-                     record.strand, record.transcript_start, record.transcript_end,
-                     record.coding_start, record.coding_end, record.num_exons(),
-                     exon_starts, exon_ends))
+            .encode(cols)
             .map_err(|e| ::Error::from(RefFlatError::from(e)))
     }
 
     /// Writes the given transcript as a single row.
+    ///
+    /// When writing genePredExt rows, the exon frames are taken from the frames of the coding
+    /// features of the transcript exons. The score and the coding region completeness values
+    /// are taken from the `score`, `cdsStartStat`, and `cdsEndStat` attributes of the transcript,
+    /// if present. Otherwise, the coding region completeness values are inferred from the
+    /// presence of start and stop codon features.
     pub fn write_transcript(&mut self, transcript: &Transcript) -> ::Result<()> {
-        let transcript_name = transcript.id()
-            .ok_or(::Error::RefFlat(RefFlatError::MissingTranscriptId))?;
-        let strand_char = match transcript.strand() {
-            &Strand::Forward => '+',
-            &Strand::Reverse => '-',
-            &Strand::Unknown => '.',
-        };
-
-        let (coding_start, coding_end) = transcript.coding_coord(true)
-            .unwrap_or((transcript.end(), transcript.end()));
-        let (exon_starts, exon_ends) = transcript.coords_field();
-
-        self.inner
-            .encode((transcript.gene_id(), transcript_name, transcript.seq_name(), strand_char,
-                     transcript.start(), transcript.end(),
-                     coding_start, coding_end, transcript.exons().len(),
-                     exon_starts, exon_ends))
-            .map_err(|e| ::Error::from(RefFlatError::from(e)))
+        let record = transcript.refflat_record()?;
+        self.write_record(&record)
     }
 
     /// Writes the given gene as multiple rows.
@@ -564,18 +1142,198 @@ impl<W: io::Write> Writer<W> {
         }
         Ok(())
     }
+
+    /// Writes all buffered rows to the underlying writer.
+    pub fn flush(&mut self) -> ::Result<()> {
+        self.inner.flush()
+            .map_err(|e| ::Error::from(RefFlatError::from(e)))
+    }
+}
+
+/// Helper function to adjust the coding features of a transcript to the completeness of its
+/// coding region ends and to its genePredExt exon frames.
+///
+/// `cdsStartStat` and `cdsEndStat` refer to the genome-wise start and end of the coding region,
+/// so which codon each of them applies to depends on the strand.
+fn adjust_coding_features(
+    transcript: &mut Transcript,
+    cds_start_stat: Option<CdsStat>,
+    cds_end_stat: Option<CdsStat>,
+    exon_frames: &[i8],
+) -> ::Result<()> {
+    let is_incomplete = |stat: Option<CdsStat>| match stat {
+        Some(CdsStat::Incomplete) | Some(CdsStat::Unknown) => true,
+        _ => false,
+    };
+    let (drop_start_codon, drop_stop_codon) = match *transcript.strand() {
+        Strand::Forward => (is_incomplete(cds_start_stat), is_incomplete(cds_end_stat)),
+        Strand::Reverse => (is_incomplete(cds_end_stat), is_incomplete(cds_start_stat)),
+        Strand::Unknown => (false, false),
+    };
+    let is_cds = |fx: &ExonFeature| match *fx.kind() {
+        ExonFeatureKind::CDS { .. } => true,
+        _ => false,
+    };
+
+    for (idx, exon) in transcript.exons_mut().iter_mut().enumerate() {
+        // Frames in genePredExt are the position of the first base in the codon, while frames
+        // in the model are the number of bases to skip.
+        let exon_frame = exon_frames.get(idx)
+            .and_then(|&frame| if frame >= 0 { Some(((3 - frame) % 3) as u8) } else { None });
+
+        let mut features: Vec<ExonFeature> = Vec::with_capacity(exon.features().len());
+        let mut stop_idxs = vec![];
+        for fx in exon.features().iter() {
+            let kind = match *fx.kind() {
+                ExonFeatureKind::StartCodon { .. } if drop_start_codon => continue,
+                ExonFeatureKind::StopCodon { frame } if drop_stop_codon => {
+                    stop_idxs.push(features.len());
+                    ExonFeatureKind::CDS { frame: frame }
+                },
+                ref kind => kind.clone(),
+            };
+            features.push(ExonFeature::new(fx.interval().clone(), kind));
+        }
+        // Stop codon bases are merged into the CDS feature next to them, if there is any.
+        for &idx in stop_idxs.iter().rev() {
+            let cds_idx = [idx.wrapping_sub(1), idx + 1].iter().cloned()
+                .filter(|&i| i < features.len() && !stop_idxs.contains(&i))
+                .filter(|&i| is_cds(&features[i]))
+                .find(|&i| features[i].end() == features[idx].start()
+                      || features[idx].end() == features[i].start());
+            if let Some(cds_idx) = cds_idx {
+                let stop = features.remove(idx);
+                let cds_idx = if cds_idx > idx { cds_idx - 1 } else { cds_idx };
+                let interval = Interval::new(min(stop.start(), features[cds_idx].start())
+                                             ..max(stop.end(), features[cds_idx].end()))
+                    .map_err(|e| ::Error::from(ModelError::from(e)))?;
+                let kind = features[cds_idx].kind().clone();
+                features[cds_idx] = ExonFeature::new(interval, kind);
+            }
+        }
+
+        if let Some(frame) = exon_frame {
+            for fx in features.iter_mut().filter(|fx| is_cds(fx)) {
+                *fx = ExonFeature::new(fx.interval().clone(),
+                                       ExonFeatureKind::CDS { frame: Some(frame) });
+            }
+        }
+        let _ = exon.set_features(features).map_err(::Error::from)?;
+    }
+    Ok(())
+}
+
+impl RefFlatRecord {
+
+    /// Computes the genePredExt exon frames of the record from its coordinates.
+    fn calc_exon_frames(&self) -> Vec<i8> {
+        let mut frames = vec![NO_FRAME; self.num_exons()];
+        if self.coding_start == self.coding_end {
+            return frames;
+        }
+        let mut idxs = (0..self.num_exons()).collect::<Vec<usize>>();
+        if self.strand == '-' {
+            idxs.reverse();
+        }
+        let mut num_coding_bases = 0;
+        for idx in idxs {
+            let start = max(self.exon_starts[idx], self.coding_start);
+            let end = min(self.exon_ends[idx], self.coding_end);
+            if start < end {
+                frames[idx] = (num_coding_bases % 3) as i8;
+                num_coding_bases += end - start;
+            }
+        }
+        frames
+    }
 }
 
 impl Transcript {
 
-    /// Returns the string values of the exon coordinate columns.
-    #[inline(always)]
-    fn coords_field(&self) -> (String, String) {
-        let mut coord_starts = self.exons().iter().map(|exon| exon.start()).join(",");
-        coord_starts.push(',');
-        let mut coord_ends = self.exons().iter().map(|exon| exon.end()).join(",");
-        coord_ends.push(',');
-        (coord_starts, coord_ends)
+    /// Creates a refFlat record of the transcript.
+    fn refflat_record(&self) -> ::Result<RefFlatRecord> {
+        let transcript_id = self.id()
+            .ok_or(::Error::RefFlat(RefFlatError::MissingTranscriptId))?;
+        let strand_char = match self.strand() {
+            &Strand::Forward => '+',
+            &Strand::Reverse => '-',
+            &Strand::Unknown => '.',
+        };
+
+        let coding_coord = self.coding_coord(true);
+        let (coding_start, coding_end) = coding_coord.unwrap_or((self.end(), self.end()));
+        let (exon_starts, exon_ends) = self.exons().iter()
+            .map(|exon| (exon.start(), exon.end()))
+            .unzip();
+
+        let attr_stat = |key: &str| self.attributes().get(key).and_then(|v| CdsStat::parse(v));
+        let infer_stat = |has_codon: bool| match (coding_coord, has_codon) {
+            (None, _) => CdsStat::None,
+            (Some(_), true) => CdsStat::Complete,
+            (Some(_), false) => CdsStat::Incomplete,
+        };
+        let (mut has_start, mut has_stop) = (false, false);
+        for fx in self.exons().iter().flat_map(|exon| exon.features().iter()) {
+            match *fx.kind() {
+                ExonFeatureKind::StartCodon { .. } => has_start = true,
+                ExonFeatureKind::StopCodon { .. } => has_stop = true,
+                _ => {},
+            }
+        }
+        let (has_5p_codon, has_3p_codon) = match self.strand() {
+            &Strand::Forward => (has_start, has_stop),
+            &Strand::Reverse => (has_stop, has_start),
+            &Strand::Unknown => (false, false),
+        };
+
+        Ok(RefFlatRecord {
+            gene_id: self.gene_id().unwrap_or("").to_owned(),
+            transcript_id: transcript_id.to_owned(),
+            seq_name: self.seq_name().to_owned(),
+            strand: strand_char,
+            transcript_start: self.start(),
+            transcript_end: self.end(),
+            coding_start: coding_start,
+            coding_end: coding_end,
+            exon_starts: exon_starts,
+            exon_ends: exon_ends,
+            score: self.attributes().get(SCORE_STR).and_then(|v| i64::from_str(v).ok()),
+            cds_start_stat: attr_stat(CDS_START_STAT_STR)
+                .or_else(|| Some(infer_stat(has_5p_codon))),
+            cds_end_stat: attr_stat(CDS_END_STAT_STR)
+                .or_else(|| Some(infer_stat(has_3p_codon))),
+            exon_frames: self.genepred_exon_frames().unwrap_or_else(Vec::new),
+        })
+    }
+
+    /// Returns the genePredExt exon frames of the transcript.
+    ///
+    /// The frames are taken from the CDS features of each exon, or from the stop codon features
+    /// for exons without any CDS features. `None` is returned if any of these features has no
+    /// frame.
+    fn genepred_exon_frames(&self) -> Option<Vec<i8>> {
+        let mut frames = Vec::with_capacity(self.exons().len());
+        for exon in self.exons() {
+            let mut cds_frame = None;
+            let mut stop_codon_frame = None;
+            for fx in exon.features() {
+                match *fx.kind() {
+                    ExonFeatureKind::CDS { frame } if cds_frame.is_none() =>
+                        cds_frame = Some(frame),
+                    ExonFeatureKind::StopCodon { frame } if stop_codon_frame.is_none() =>
+                        stop_codon_frame = Some(frame),
+                    _ => {},
+                }
+            }
+            match cds_frame.or(stop_codon_frame) {
+                // Frames in the model are the number of bases to skip, while frames in
+                // genePredExt are the position of the first base in the codon.
+                Some(Some(frame)) => frames.push(((3 - frame) % 3) as i8),
+                Some(None) => return None,
+                None => frames.push(NO_FRAME),
+            }
+        }
+        Some(frames)
     }
 }
 
@@ -602,3 +1360,51 @@ impl Writer<Vec<u8>> {
         self.inner.as_string()
     }
 }
+
+/// Helper function to join values into a comma-separated string with a trailing comma.
+#[inline(always)]
+fn join_values<T: fmt::Display>(values: &[T]) -> String {
+    let mut joined = values.iter().join(",");
+    joined.push(',');
+    joined
+}
+
+/// Offsets of the bins of each level in the standard UCSC binning scheme.
+const BIN_OFFSETS: &'static [u64] = &[512 + 64 + 8 + 1, 64 + 8 + 1, 8 + 1, 1, 0];
+
+/// Offsets of the bins of each level in the extended UCSC binning scheme.
+const BIN_OFFSETS_EXTENDED: &'static [u64] =
+    &[4096 + 512 + 64 + 8 + 1, 512 + 64 + 8 + 1, 64 + 8 + 1, 8 + 1, 1, 0];
+
+/// Offset of the first bin of the extended UCSC binning scheme.
+const BIN_OFFSET_OLD_TO_EXTENDED: u64 = 4681;
+
+/// Maximum end coordinate supported by the standard UCSC binning scheme.
+const BIN_MAX_END_STANDARD: u64 = 1 << 29;
+
+/// Number of bits to shift to get the bin of the lowest level.
+const BIN_FIRST_SHIFT: u64 = 17;
+
+/// Number of bits to shift to get the bin of the next level.
+const BIN_NEXT_SHIFT: u64 = 3;
+
+// Adapted from: https://genome.ucsc.edu/FAQ/FAQdownloads.html#download3
+/// Helper function to compute the UCSC bin of the given interval.
+fn bin_from_range(start: u64, end: u64) -> u64 {
+    let (offsets, base) =
+        if end <= BIN_MAX_END_STANDARD {
+            (BIN_OFFSETS, 0)
+        } else {
+            (BIN_OFFSETS_EXTENDED, BIN_OFFSET_OLD_TO_EXTENDED)
+        };
+    let mut start_bin = start >> BIN_FIRST_SHIFT;
+    let mut end_bin = (max(end, 1) - 1) >> BIN_FIRST_SHIFT;
+    for offset in offsets {
+        if start_bin == end_bin {
+            return base + offset + start_bin;
+        }
+        start_bin >>= BIN_NEXT_SHIFT;
+        end_bin >>= BIN_NEXT_SHIFT;
+    }
+    base
+}
@@ -0,0 +1,211 @@
+/*! Writer for the SAF format.
+
+The Simplified Annotation Format (SAF) is a tab-separated format used by
+[featureCounts](http://subread.sourceforge.net/) in place of GTF. Each row denotes an interval
+of a meta-feature, which is usually a gene, with these columns: `GeneID`, `Chr`, `Start`, `End`,
+and `Strand`. Rows with the same `GeneID` are counted together.
+
+Unlike the models in this crate, SAF coordinates are one-based and closed. The writer converts
+the coordinates accordingly.
+*/
+use std::collections::BTreeSet;
+use std::convert::AsRef;
+use std::error::Error;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use csv;
+
+use {Coord, Exon, ExonFeatureKind, Gene, Strand, Transcript};
+
+
+/// Column names of the header row.
+const HEADER: (&'static str, &'static str, &'static str, &'static str, &'static str) =
+    ("GeneID", "Chr", "Start", "End", "Strand");
+
+quick_error! {
+    /// Errors that occur when writing SAF files.
+    #[derive(Debug)]
+    pub enum SafError {
+        /// Occurs when a model to write has neither a gene identifier nor a transcript
+        /// identifier.
+        MissingId {
+            description("gene and transcript identifiers not found")
+        }
+        /// Errors propagated from the underlying `csv` crate.
+        Csv(err: csv::Error) {
+            description(err.description())
+            from()
+            cause(err)
+        }
+    }
+}
+
+/// Levels of the intervals written as SAF rows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    /// One row per exon.
+    Exon,
+    /// One row per gene, or per transcript if transcripts are written.
+    Gene,
+    /// One row per CDS feature.
+    CDS,
+}
+
+impl Default for Level {
+    fn default() -> Level {
+        Level::Exon
+    }
+}
+
+/// SAF writer.
+///
+/// The header row is written before the first row. Rows are identified by their gene
+/// identifiers, or by their transcript identifiers if they have no gene identifiers.
+pub struct Writer<W: io::Write> {
+    inner: csv::Writer<W>,
+    level: Level,
+    header_written: bool,
+}
+
+impl<W: io::Write> Writer<W> {
+
+    /// Creates a SAF writer from another writer.
+    pub fn from_writer(in_writer: W) -> Writer<W> {
+        Writer {
+            inner: csv::Writer::from_writer(in_writer)
+                .delimiter(b'\t')
+                .quote_style(csv::QuoteStyle::Never),
+            level: Level::default(),
+            header_written: false,
+        }
+    }
+
+    /// Sets the level of the written intervals.
+    pub fn level(&mut self, level: Level) -> &mut Self {
+        self.level = level;
+        self
+    }
+
+    /// Writes the given transcript as SAF rows.
+    pub fn write_transcript(&mut self, transcript: &Transcript) -> ::Result<()> {
+        let id = transcript.gene_id().or_else(|| transcript.id())
+            .ok_or(::Error::Saf(SafError::MissingId))?;
+        let coords = match self.level {
+            Level::Gene => vec![(transcript.start(), transcript.end())],
+            level => level_coords(transcript.exons(), level),
+        };
+        self.write_rows(id, transcript.seq_name(), transcript.strand(), coords)
+    }
+
+    /// Writes the given gene as SAF rows.
+    ///
+    /// Exon and CDS intervals that are shared by multiple transcripts are only written once.
+    pub fn write_gene(&mut self, gene: &Gene) -> ::Result<()> {
+        let id = gene.id()
+            .or_else(|| gene.transcripts().values().filter_map(|trx| trx.gene_id()).next())
+            .ok_or(::Error::Saf(SafError::MissingId))?;
+        let coords = match self.level {
+            Level::Gene => vec![(gene.start(), gene.end())],
+            level => gene.transcripts().values()
+                .flat_map(|trx| level_coords(trx.exons(), level))
+                .collect::<BTreeSet<Coord<u64>>>()
+                .into_iter()
+                .collect(),
+        };
+        self.write_rows(id, gene.seq_name(), gene.strand(), coords)
+    }
+
+    /// Writes the given exon as SAF rows.
+    ///
+    /// At the CDS level, only the CDS features of the exon are written.
+    pub fn write_exon(&mut self, exon: &Exon) -> ::Result<()> {
+        let id = exon.gene_id().or_else(|| exon.transcript_id())
+            .ok_or(::Error::Saf(SafError::MissingId))?;
+        let coords = match self.level {
+            Level::CDS => exon.features().iter()
+                .filter(|fx| is_cds(fx.kind()))
+                .map(|fx| (fx.start(), fx.end()))
+                .collect(),
+            _ => vec![(exon.start(), exon.end())],
+        };
+        self.write_rows(id, exon.seq_name(), exon.strand(), coords)
+    }
+
+    /// Writes rows of the given zero-based, half-open coordinates.
+    fn write_rows(
+        &mut self,
+        id: &str,
+        seq_name: &str,
+        strand: &Strand,
+        coords: Vec<Coord<u64>>,
+    ) -> ::Result<()> {
+        if !self.header_written {
+            self.inner.encode(HEADER)
+                .map_err(|e| ::Error::from(SafError::from(e)))?;
+            self.header_written = true;
+        }
+        let strand_char = match strand {
+            &Strand::Forward => '+',
+            &Strand::Reverse => '-',
+            &Strand::Unknown => '.',
+        };
+        for (start, end) in coords {
+            self.inner.encode((id, seq_name, start + 1, end, strand_char))
+                .map_err(|e| ::Error::from(SafError::from(e)))?;
+        }
+        Ok(())
+    }
+
+    /// Writes all buffered rows to the underlying writer.
+    pub fn flush(&mut self) -> ::Result<()> {
+        self.inner.flush()
+            .map_err(|e| ::Error::from(SafError::from(e)))
+    }
+}
+
+impl Writer<fs::File> {
+
+    /// Creates a SAF writer that writes to the given path.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let f = fs::File::create(path)?;
+        Ok(Writer::from_writer(f))
+    }
+}
+
+impl Writer<Vec<u8>> {
+
+    /// Creates a SAF writer that writes to an in-memory buffer.
+    ///
+    /// The initial capacity of the buffer is 64 KiB.
+    pub fn from_memory() -> Writer<Vec<u8>> {
+        Writer::from_writer(Vec::with_capacity(1024 * 64))
+    }
+
+    /// Returns the values of the in-memory buffer as a string.
+    pub fn as_string(&mut self) -> &str {
+        self.inner.as_string()
+    }
+}
+
+/// Helper function to collect the coordinates of the given exons at the exon or CDS level.
+fn level_coords(exons: &[Exon], level: Level) -> Vec<Coord<u64>> {
+    match level {
+        Level::CDS => exons.iter()
+            .flat_map(|exon| exon.features().iter())
+            .filter(|fx| is_cds(fx.kind()))
+            .map(|fx| (fx.start(), fx.end()))
+            .collect(),
+        _ => exons.iter().map(|exon| (exon.start(), exon.end())).collect(),
+    }
+}
+
+/// Helper function to check whether an exon feature kind is a CDS.
+#[inline]
+fn is_cds(kind: &ExonFeatureKind) -> bool {
+    match kind {
+        &ExonFeatureKind::CDS { .. } => true,
+        _ => false,
+    }
+}
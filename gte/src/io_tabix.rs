@@ -0,0 +1,714 @@
+/*! Reader and builder of tabix indices of BGZF-compressed annotation files.
+
+A tabix index allows reading only the lines of a BGZF-compressed, position-sorted file that
+overlap a given region, instead of the whole file. Both the TBI format and the CSI format, which
+supports longer sequences, can be read and written. Their specifications can be found
+[here](https://samtools.github.io/hts-specs/tabix.pdf) and
+[here](https://samtools.github.io/hts-specs/CSIv1.pdf).
+
+The index divides each sequence into a hierarchy of bins and assigns each line to the smallest
+bin that contains it. For each bin, it stores the chunks of the BGZF data that contain its lines.
+TBI indices additionally store the offset of the first line that overlaps each 16 kbp window of
+the sequence, which allows chunks that end before a region to be skipped. CSI indices store this
+offset for each bin instead.
+*/
+use std::cmp::{max, min};
+use std::collections::BTreeMap;
+use std::convert::AsRef;
+use std::error::Error;
+use std::fs;
+use std::io::{self, Read, Seek, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use {BgzfWriter, GzipReader};
+use io_gzip::{BlockReader, push_le};
+
+
+/// Magic bytes at the beginning of TBI indices.
+const TBI_MAGIC: &'static [u8] = b"TBI\x01";
+
+/// Magic bytes at the beginning of CSI indices.
+const CSI_MAGIC: &'static [u8] = b"CSI\x01";
+
+/// Extension of TBI index files.
+const TBI_EXT: &'static str = ".tbi";
+
+/// Extension of CSI index files.
+const CSI_EXT: &'static str = ".csi";
+
+/// Number of bits of the coordinates of the smallest bins, which are 16 kbp long.
+const MIN_SHIFT: u32 = 14;
+
+/// Number of levels below the root bin in TBI indices, which cover 512 Mbp.
+const TBI_DEPTH: u32 = 5;
+
+/// Number of levels below the root bin in CSI indices, which cover 4 Gbp.
+const CSI_DEPTH: u32 = 6;
+
+/// Format flag of files with zero-based, half-open coordinates.
+const ZERO_BASED_FLAG: i32 = 0x10000;
+
+/// Placeholder of windows of the linear index without any lines.
+const UNSET_OFFSET: u64 = ::std::u64::MAX;
+
+quick_error! {
+    /// Errors that occur when reading or building tabix indices.
+    #[derive(Debug)]
+    pub enum TabixError {
+        /// Occurs when no index file is found for an indexed file.
+        MissingIndex(path: String) {
+            description("no '.tbi' or '.csi' index file found")
+            display(self_) -> ("{} for '{}'", self_.description(), path)
+        }
+        /// Occurs when an index file is not a valid TBI or CSI index.
+        InvalidIndex(reason: &'static str) {
+            description("invalid index")
+            display(self_) -> ("{}: {}", self_.description(), reason)
+        }
+        /// Occurs when the sequence name or the coordinates of a line can not be parsed.
+        InvalidLine(raw: String) {
+            description("sequence name and coordinates not found")
+            display(self_) -> ("{}: '{}'", self_.description(), raw)
+        }
+        /// Occurs when the lines to index are not sorted by sequence name and start coordinate.
+        UnsortedInput(seq_name: String, start: u64) {
+            description("lines are not grouped by sequence name and sorted by start coordinate")
+            display(self_) -> ("{}, at {}:{}", self_.description(), seq_name, start + 1)
+        }
+        /// Occurs when a coordinate is larger than the maximum supported by the index format.
+        CoordTooLarge(coord: u64) {
+            description("coordinate too large for the index format")
+            display(self_) -> ("{}: {}", self_.description(), coord)
+        }
+        /// Errors that occur when reading or writing indices and indexed files.
+        Io(err: io::Error) {
+            description(err.description())
+            display("{}", err)
+            from()
+            cause(err)
+        }
+    }
+}
+
+/// Column layouts of indexed files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Preset {
+    /// GFF and GTF files, with one-based, fully-closed coordinates in columns 4 and 5.
+    Gff,
+    /// BED files, with zero-based, half-open coordinates in columns 2 and 3.
+    Bed,
+}
+
+/// Locations of the sequence name and coordinates in the lines of an indexed file.
+///
+/// Column numbers are one-based, as they are stored in the index.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Columns {
+    format: i32,
+    seq: i32,
+    beg: i32,
+    end: i32,
+    meta: i32,
+    skip: i32,
+}
+
+impl Columns {
+
+    /// Creates the columns of the given preset.
+    fn from_preset(preset: Preset) -> Columns {
+        let (format, beg, end) = match preset {
+            Preset::Gff => (0, 4, 5),
+            Preset::Bed => (ZERO_BASED_FLAG, 2, 3),
+        };
+        Columns { format: format, seq: 1, beg: beg, end: end, meta: b'#' as i32, skip: 0 }
+    }
+
+    /// Parses the sequence name and the zero-based, half-open interval of a line.
+    ///
+    /// Returns `None` for empty lines and lines starting with the meta character.
+    fn parse_line<'a>(&self, line: &'a str) -> Option<Result<(&'a str, u64, u64), TabixError>> {
+        match line.as_bytes().first() {
+            None => return None,
+            Some(&c) if c as i32 == self.meta => return None,
+            _ => {},
+        }
+        let cols = line.split('\t').collect::<Vec<&str>>();
+        let col = |num: i32| if num > 0 { cols.get(num as usize - 1).cloned() } else { None };
+        let coord = |num: i32| col(num).and_then(|raw| u64::from_str(raw).ok());
+
+        let zero_based = self.format & ZERO_BASED_FLAG != 0;
+        let beg = match coord(self.beg) {
+            Some(beg) if zero_based => Some(beg),
+            Some(beg) if beg > 0 => Some(beg - 1),
+            _ => None,
+        };
+        match (col(self.seq), beg) {
+            (Some(seq_name), Some(beg)) => {
+                let end = coord(self.end).unwrap_or(beg + 1);
+                Some(Ok((seq_name, beg, max(end, beg + 1))))
+            },
+            _ => Some(Err(TabixError::InvalidLine(line.to_owned()))),
+        }
+    }
+}
+
+/// Region of BGZF data, denoted by the virtual offsets of its start and end.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct Chunk {
+    start: u64,
+    end: u64,
+}
+
+/// Chunks of the lines in a bin, along with the offset of the first line that overlaps it.
+#[derive(Debug, Default)]
+struct Bin {
+    loffset: u64,
+    chunks: Vec<Chunk>,
+}
+
+/// Bins and linear index of a sequence.
+#[derive(Debug, Default)]
+struct SeqIndex {
+    bins: BTreeMap<u32, Bin>,
+    linear: Vec<u64>,
+}
+
+/// Tabix index of a BGZF-compressed file.
+#[derive(Debug)]
+pub struct Index {
+    columns: Columns,
+    csi: bool,
+    min_shift: u32,
+    depth: u32,
+    seq_names: Vec<String>,
+    seqs: Vec<SeqIndex>,
+}
+
+impl Index {
+
+    /// Reads an index from another reader.
+    ///
+    /// The format of the index, TBI or CSI, is detected from its magic bytes.
+    pub fn from_reader<R: Read>(in_reader: R) -> ::Result<Index> {
+        let mut data = Vec::new();
+        let _ = GzipReader::from_reader(in_reader)
+            .and_then(|mut reader| reader.read_to_end(&mut data))
+            .map_err(TabixError::from)?;
+        Index::parse(&data).map_err(::Error::from)
+    }
+
+    /// Reads an index from the given path.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> ::Result<Index> {
+        let file = fs::File::open(path).map_err(TabixError::from)?;
+        Index::from_reader(file)
+    }
+
+    /// Writes the index, BGZF-compressed, to another writer.
+    pub fn write<W: Write>(&self, out_writer: W) -> ::Result<()> {
+        let mut writer = BgzfWriter::from_writer(out_writer);
+        writer.write_all(&self.to_bytes())
+            .and_then(|_| writer.finish())
+            .map_err(TabixError::from)?;
+        Ok(())
+    }
+
+    /// Writes the index to the given path.
+    pub fn to_file<P: AsRef<Path>>(&self, path: P) -> ::Result<()> {
+        let file = fs::File::create(path).map_err(TabixError::from)?;
+        self.write(file)
+    }
+
+    /// Returns the names of the indexed sequences, in the order of the indexed file.
+    pub fn seq_names(&self) -> &[String] {
+        self.seq_names.as_slice()
+    }
+
+    /// Returns whether the index is in the CSI format instead of the TBI format.
+    pub fn is_csi(&self) -> bool {
+        self.csi
+    }
+
+    /// Returns the merged chunks that may contain lines overlapping the given interval.
+    fn chunks(&self, seq_name: &str, start: u64, end: u64) -> Vec<Chunk> {
+        let seq = match self.seq_names.iter().position(|name| name == seq_name) {
+            Some(idx) => &self.seqs[idx],
+            None => return Vec::new(),
+        };
+        let end = min(end, 1 << (self.min_shift + 3 * self.depth));
+        if start >= end {
+            return Vec::new();
+        }
+
+        let min_offset = self.min_offset(seq, start);
+        let mut chunks = reg2bins(start, end, self.min_shift, self.depth).into_iter()
+            .filter_map(|bin| seq.bins.get(&bin))
+            .flat_map(|bin| bin.chunks.iter().cloned())
+            .filter(|chunk| chunk.end > min_offset)
+            .collect::<Vec<Chunk>>();
+        chunks.sort();
+
+        let mut merged: Vec<Chunk> = Vec::with_capacity(chunks.len());
+        for chunk in chunks {
+            if let Some(last) = merged.last_mut() {
+                if chunk.start <= last.end {
+                    last.end = max(last.end, chunk.end);
+                    continue;
+                }
+            }
+            merged.push(chunk);
+        }
+        merged
+    }
+
+    /// Returns the offset before which no lines overlap the given start coordinate.
+    fn min_offset(&self, seq: &SeqIndex, start: u64) -> u64 {
+        if !self.csi {
+            return match seq.linear.len() {
+                0 => 0,
+                n => seq.linear[min((start >> self.min_shift) as usize, n - 1)],
+            };
+        }
+        let mut bin = bin_first(self.depth) + (start >> self.min_shift) as u32;
+        loop {
+            if let Some(found) = seq.bins.get(&bin) {
+                return found.loffset;
+            }
+            if bin == 0 {
+                return 0;
+            }
+            bin = bin_parent(bin);
+        }
+    }
+
+    /// Parses the decompressed bytes of an index.
+    fn parse(data: &[u8]) -> Result<Index, TabixError> {
+        let mut buf = data;
+        let csi = match take(&mut buf, TBI_MAGIC.len())? {
+            magic if magic == TBI_MAGIC => false,
+            magic if magic == CSI_MAGIC => true,
+            _ => return Err(TabixError::InvalidIndex("unknown magic bytes")),
+        };
+
+        let (min_shift, depth, num_seqs, columns, seq_names) =
+            if csi {
+                let min_shift = read_count(&mut buf)? as u32;
+                let depth = read_count(&mut buf)? as u32;
+                let aux_len = read_count(&mut buf)?;
+                let mut aux = take(&mut buf, aux_len)?;
+                let (columns, seq_names) = read_header(&mut aux)?;
+                let num_seqs = read_count(&mut buf)?;
+                (min_shift, depth, num_seqs, columns, seq_names)
+            } else {
+                let num_seqs = read_count(&mut buf)?;
+                let (columns, seq_names) = read_header(&mut buf)?;
+                (MIN_SHIFT, TBI_DEPTH, num_seqs, columns, seq_names)
+            };
+        if seq_names.len() != num_seqs {
+            return Err(TabixError::InvalidIndex("number of sequence names does not match"));
+        }
+        if depth > 9 || min_shift + 3 * depth > 62 {
+            return Err(TabixError::InvalidIndex("unsupported number of bin levels"));
+        }
+
+        let mut seqs = Vec::with_capacity(num_seqs);
+        for _ in 0..num_seqs {
+            let mut seq = SeqIndex::default();
+            for _ in 0..read_count(&mut buf)? {
+                let bin_id = read_u32(&mut buf)?;
+                let loffset = if csi { read_u64(&mut buf)? } else { 0 };
+                let mut chunks = Vec::new();
+                for _ in 0..read_count(&mut buf)? {
+                    let start = read_u64(&mut buf)?;
+                    let end = read_u64(&mut buf)?;
+                    chunks.push(Chunk { start: start, end: end });
+                }
+                let _ = seq.bins.insert(bin_id, Bin { loffset: loffset, chunks: chunks });
+            }
+            if !csi {
+                for _ in 0..read_count(&mut buf)? {
+                    seq.linear.push(read_u64(&mut buf)?);
+                }
+            }
+            seqs.push(seq);
+        }
+
+        Ok(Index {
+            columns: columns,
+            csi: csi,
+            min_shift: min_shift,
+            depth: depth,
+            seq_names: seq_names,
+            seqs: seqs,
+        })
+    }
+
+    /// Returns the uncompressed bytes of the index.
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut header = Vec::new();
+        for value in &[self.columns.format, self.columns.seq, self.columns.beg,
+                       self.columns.end, self.columns.meta, self.columns.skip] {
+            push_le(&mut header, *value as u64, 4);
+        }
+        let names_len = self.seq_names.iter().map(|name| name.len() + 1).sum::<usize>();
+        push_le(&mut header, names_len as u64, 4);
+        for name in self.seq_names.iter() {
+            header.extend_from_slice(name.as_bytes());
+            header.push(0);
+        }
+
+        let mut bytes = Vec::new();
+        if self.csi {
+            bytes.extend_from_slice(CSI_MAGIC);
+            push_le(&mut bytes, self.min_shift as u64, 4);
+            push_le(&mut bytes, self.depth as u64, 4);
+            push_le(&mut bytes, header.len() as u64, 4);
+            bytes.extend_from_slice(&header);
+            push_le(&mut bytes, self.seqs.len() as u64, 4);
+        } else {
+            bytes.extend_from_slice(TBI_MAGIC);
+            push_le(&mut bytes, self.seqs.len() as u64, 4);
+            bytes.extend_from_slice(&header);
+        }
+
+        for seq in self.seqs.iter() {
+            push_le(&mut bytes, seq.bins.len() as u64, 4);
+            for (bin_id, bin) in seq.bins.iter() {
+                push_le(&mut bytes, *bin_id as u64, 4);
+                if self.csi {
+                    push_le(&mut bytes, bin.loffset, 8);
+                }
+                push_le(&mut bytes, bin.chunks.len() as u64, 4);
+                for chunk in bin.chunks.iter() {
+                    push_le(&mut bytes, chunk.start, 8);
+                    push_le(&mut bytes, chunk.end, 8);
+                }
+            }
+            if !self.csi {
+                push_le(&mut bytes, seq.linear.len() as u64, 4);
+                for offset in seq.linear.iter() {
+                    push_le(&mut bytes, *offset, 8);
+                }
+            }
+        }
+        bytes
+    }
+}
+
+/// Builder of tabix indices.
+///
+/// Lines are added in the order in which they are written to the BGZF data, along with the
+/// virtual offsets of their starts and ends as returned by `BgzfWriter::virtual_offset`. The lines
+/// of each sequence must be grouped together and sorted by their start coordinates. Empty lines
+/// and lines starting with `#` are not indexed.
+pub struct IndexBuilder {
+    columns: Columns,
+    csi: bool,
+    seq_names: Vec<String>,
+    seqs: Vec<SeqIndex>,
+    last_start: u64,
+}
+
+impl IndexBuilder {
+
+    /// Creates a builder of a TBI index, for files with the given column layout.
+    ///
+    /// TBI indices support coordinates of up to 2^29 (512 Mbp).
+    pub fn tbi(preset: Preset) -> IndexBuilder {
+        IndexBuilder::new(preset, false)
+    }
+
+    /// Creates a builder of a CSI index, for files with the given column layout.
+    ///
+    /// CSI indices support coordinates of up to 2^32 (4 Gbp).
+    pub fn csi(preset: Preset) -> IndexBuilder {
+        IndexBuilder::new(preset, true)
+    }
+
+    /// Creates a builder of an index in the given format.
+    fn new(preset: Preset, csi: bool) -> IndexBuilder {
+        IndexBuilder {
+            columns: Columns::from_preset(preset),
+            csi: csi,
+            seq_names: Vec::new(),
+            seqs: Vec::new(),
+            last_start: 0,
+        }
+    }
+
+    /// Adds a line, which starts and ends at the given virtual offsets, to the index.
+    pub fn add_line(&mut self, line: &str, start_offset: u64, end_offset: u64) -> ::Result<()> {
+        let (seq_name, start, end) = match self.columns.parse_line(line) {
+            Some(result) => result?,
+            None => return Ok(()),
+        };
+        if end > 1 << (MIN_SHIFT + 3 * self.depth()) {
+            return Err(TabixError::CoordTooLarge(end).into());
+        }
+
+        let is_new_seq = self.seq_names.last().map_or(true, |last| last != seq_name);
+        if is_new_seq {
+            if self.seq_names.iter().any(|name| name == seq_name) {
+                return Err(TabixError::UnsortedInput(seq_name.to_owned(), start).into());
+            }
+            self.seq_names.push(seq_name.to_owned());
+            self.seqs.push(SeqIndex::default());
+        } else if start < self.last_start {
+            return Err(TabixError::UnsortedInput(seq_name.to_owned(), start).into());
+        }
+        self.last_start = start;
+
+        let bin_id = reg2bin(start, end, MIN_SHIFT, self.depth());
+        let seq = self.seqs.last_mut().expect("a sequence index");
+        {
+            let chunks = &mut seq.bins.entry(bin_id).or_insert_with(Bin::default).chunks;
+            let extended = match chunks.last_mut() {
+                Some(last) if last.end == start_offset => {
+                    last.end = end_offset;
+                    true
+                },
+                _ => false,
+            };
+            if !extended {
+                chunks.push(Chunk { start: start_offset, end: end_offset });
+            }
+        }
+
+        let first_win = (start >> MIN_SHIFT) as usize;
+        let last_win = ((end - 1) >> MIN_SHIFT) as usize;
+        if seq.linear.len() <= last_win {
+            seq.linear.resize(last_win + 1, UNSET_OFFSET);
+        }
+        for offset in seq.linear[first_win..last_win + 1].iter_mut() {
+            if *offset == UNSET_OFFSET {
+                *offset = start_offset;
+            }
+        }
+        Ok(())
+    }
+
+    /// Creates the index of all added lines.
+    pub fn build(self) -> Index {
+        let depth = self.depth();
+        let mut seqs = self.seqs;
+        for seq in seqs.iter_mut() {
+            let mut prev = 0;
+            for offset in seq.linear.iter_mut() {
+                if *offset == UNSET_OFFSET {
+                    *offset = prev;
+                } else {
+                    prev = *offset;
+                }
+            }
+            if self.csi {
+                {
+                    let linear = &seq.linear;
+                    for (bin_id, bin) in seq.bins.iter_mut() {
+                        let window = (bin_start(*bin_id, depth) >> MIN_SHIFT) as usize;
+                        bin.loffset = linear.get(window).cloned().unwrap_or(0);
+                    }
+                }
+                seq.linear.clear();
+            }
+        }
+
+        Index {
+            columns: self.columns,
+            csi: self.csi,
+            min_shift: MIN_SHIFT,
+            depth: depth,
+            seq_names: self.seq_names,
+            seqs: seqs,
+        }
+    }
+
+    /// Returns the number of levels below the root bin of the index.
+    fn depth(&self) -> u32 {
+        if self.csi { CSI_DEPTH } else { TBI_DEPTH }
+    }
+}
+
+/// Reader of BGZF-compressed files with a tabix index.
+pub struct Reader<R: Read + Seek> {
+    inner: BlockReader<R>,
+    index: Index,
+}
+
+impl<R: Read + Seek> Reader<R> {
+
+    /// Creates a reader from another reader of BGZF data and the index of the data.
+    pub fn from_reader(in_reader: R, index: Index) -> Reader<R> {
+        Reader {
+            inner: BlockReader::new(in_reader),
+            index: index,
+        }
+    }
+
+    /// Returns the index of the reader.
+    pub fn index(&self) -> &Index {
+        &self.index
+    }
+
+    /// Returns the lines that overlap the given zero-based, half-open interval of a sequence.
+    ///
+    /// Lines are returned in the order of the file, without their newline characters. No lines
+    /// are returned for sequences that are not in the index.
+    pub fn fetch(&mut self, seq_name: &str, start: u64, end: u64) -> ::Result<Vec<String>> {
+        let mut lines = Vec::new();
+        let mut buf = Vec::new();
+        for chunk in self.index.chunks(seq_name, start, end) {
+            self.inner.seek(chunk.start).map_err(TabixError::from)?;
+            while self.inner.virtual_offset().map_err(TabixError::from)? < chunk.end {
+                buf.clear();
+                if self.inner.read_line(&mut buf).map_err(TabixError::from)? == 0 {
+                    break;
+                }
+                while buf.last() == Some(&b'\n') || buf.last() == Some(&b'\r') {
+                    let _ = buf.pop();
+                }
+                let line = String::from_utf8(buf.clone())
+                    .map_err(|_| TabixError::InvalidLine(
+                        String::from_utf8_lossy(&buf).into_owned()))?;
+                let (is_past, overlaps) = match self.index.columns.parse_line(&line) {
+                    Some(result) => {
+                        let (name, line_start, line_end) = result?;
+                        (name == seq_name && line_start >= end,
+                         name == seq_name && line_start < end && line_end > start)
+                    },
+                    None => continue,
+                };
+                if is_past {
+                    break;
+                }
+                if overlaps {
+                    lines.push(line);
+                }
+            }
+        }
+        Ok(lines)
+    }
+}
+
+impl Reader<fs::File> {
+
+    /// Creates a reader of the BGZF-compressed file at the given path.
+    ///
+    /// The index is read from the path with a `.tbi` extension added or, if it does not exist,
+    /// from the path with a `.csi` extension added.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> ::Result<Self> {
+        let path = path.as_ref();
+        let index = Index::from_file(index_path(path)?)?;
+        let file = fs::File::open(path).map_err(TabixError::from)?;
+        Ok(Reader::from_reader(file, index))
+    }
+}
+
+/// Helper function to find the index file of the given path.
+fn index_path(path: &Path) -> Result<PathBuf, TabixError> {
+    for ext in &[TBI_EXT, CSI_EXT] {
+        let mut raw = path.as_os_str().to_owned();
+        raw.push(ext);
+        let candidate = PathBuf::from(raw);
+        if candidate.exists() {
+            return Ok(candidate);
+        }
+    }
+    Err(TabixError::MissingIndex(path.display().to_string()))
+}
+
+/// Helper function to read the column layout and the sequence names of an index.
+fn read_header(buf: &mut &[u8]) -> Result<(Columns, Vec<String>), TabixError> {
+    let mut values = [0; 6];
+    for value in values.iter_mut() {
+        *value = read_u32(buf)? as i32;
+    }
+    let names_len = read_count(buf)?;
+    let seq_names = take(buf, names_len)?
+        .split(|&b| b == 0)
+        .filter(|name| !name.is_empty())
+        .map(|name| String::from_utf8_lossy(name).into_owned())
+        .collect();
+    let columns = Columns {
+        format: values[0],
+        seq: values[1],
+        beg: values[2],
+        end: values[3],
+        meta: values[4],
+        skip: values[5],
+    };
+    Ok((columns, seq_names))
+}
+
+/// Helper function to take the given number of bytes from the front of a buffer.
+fn take<'a>(buf: &mut &'a [u8], len: usize) -> Result<&'a [u8], TabixError> {
+    if buf.len() < len {
+        return Err(TabixError::InvalidIndex("unexpected end of data"));
+    }
+    let (head, tail) = buf.split_at(len);
+    *buf = tail;
+    Ok(head)
+}
+
+/// Helper function to read a little-endian u32 value from the front of a buffer.
+fn read_u32(buf: &mut &[u8]) -> Result<u32, TabixError> {
+    take(buf, 4).map(|bytes| bytes.iter().rev().fold(0, |acc, &b| (acc << 8) | b as u32))
+}
+
+/// Helper function to read a little-endian u64 value from the front of a buffer.
+fn read_u64(buf: &mut &[u8]) -> Result<u64, TabixError> {
+    take(buf, 8).map(|bytes| bytes.iter().rev().fold(0, |acc, &b| (acc << 8) | b as u64))
+}
+
+/// Helper function to read a non-negative i32 value from the front of a buffer.
+fn read_count(buf: &mut &[u8]) -> Result<usize, TabixError> {
+    match read_u32(buf)? as i32 {
+        count if count >= 0 => Ok(count as usize),
+        _ => Err(TabixError::InvalidIndex("negative count")),
+    }
+}
+
+/// Helper function to compute the identifier of the first bin of a level.
+fn bin_first(level: u32) -> u32 {
+    ((1 << (3 * level)) - 1) / 7
+}
+
+/// Helper function to compute the identifier of the parent of a bin.
+fn bin_parent(bin_id: u32) -> u32 {
+    (bin_id - 1) >> 3
+}
+
+/// Helper function to compute the start coordinate of a bin.
+fn bin_start(bin_id: u32, depth: u32) -> u64 {
+    let mut level = 0;
+    while level < depth && bin_first(level + 1) <= bin_id {
+        level += 1;
+    }
+    ((bin_id - bin_first(level)) as u64) << (MIN_SHIFT + 3 * (depth - level))
+}
+
+/// Helper function to compute the smallest bin that contains the given interval.
+fn reg2bin(start: u64, end: u64, min_shift: u32, depth: u32) -> u32 {
+    let end = end - 1;
+    let mut shift = min_shift;
+    for level in (1..depth + 1).rev() {
+        if start >> shift == end >> shift {
+            return bin_first(level) + (start >> shift) as u32;
+        }
+        shift += 3;
+    }
+    0
+}
+
+/// Helper function to compute all bins that may contain lines overlapping the given interval.
+fn reg2bins(start: u64, end: u64, min_shift: u32, depth: u32) -> Vec<u32> {
+    let end = end - 1;
+    let mut bins = Vec::new();
+    for level in 0..depth + 1 {
+        let shift = min_shift + 3 * (depth - level);
+        let first = bin_first(level);
+        for offset in (start >> shift)..(end >> shift) + 1 {
+            bins.push(first + offset as u32);
+        }
+    }
+    bins
+}
@@ -0,0 +1,193 @@
+/*! Unique splice junctions of transcripts, for building aligner indices.
+
+A `JunctionCollector` gathers the unique introns and exons of the transcripts added to it,
+along with the identifiers of the transcripts and genes that support each junction. The
+collected junctions can then be written in the formats expected by common aligners and tools:
+
+* `Star`: the `sjdbFileChrStartEnd` file of STAR. Its coordinates are the one-based first and
+  last intron bases. The supporting transcript and gene identifiers are written as two extra
+  columns, which STAR ignores.
+* `Hisat2SpliceSites`: the splice site file of `hisat2-build`. Its coordinates are the
+  zero-based last base of the left exon and the zero-based first base of the right exon.
+* `Hisat2Exons`: the exon file of `hisat2-build`. Its coordinates are the zero-based first and
+  last exon bases.
+* `Regtools`: the BED12 junctions of `regtools junctions extract`. Each row spans the junction
+  and its shortest flanking exons, which are written as two blocks. Rows are named
+  `JUNC00000001`, `JUNC00000002`, and so on, and scored by the number of supporting
+  transcripts.
+
+Junctions are written sorted by sequence name, coordinates, and strand.
+*/
+use std::cmp::min;
+use std::collections::{BTreeMap, BTreeSet};
+use std::io;
+
+use {Strand, Transcript};
+
+
+/// Value written for junctions without any supporting transcript or gene identifiers.
+const MISSING: &'static str = ".";
+
+/// Item RGB value of regtools junctions.
+const REGTOOLS_RGB: &'static str = "255,0,0";
+
+/// Output formats of junctions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JunctionFormat {
+    /// STAR `sjdbFileChrStartEnd` file.
+    Star,
+    /// `hisat2-build` splice site file.
+    Hisat2SpliceSites,
+    /// `hisat2-build` exon file.
+    Hisat2Exons,
+    /// regtools BED12 junctions.
+    Regtools,
+}
+
+/// Key of junctions and exons: sequence name, zero-based half-open coordinates, and strand.
+type Key = (String, u64, u64, char);
+
+/// Values of a single unique junction.
+#[derive(Debug, Default)]
+struct Junction {
+    transcript_ids: BTreeSet<String>,
+    gene_ids: BTreeSet<String>,
+    /// Length of the shortest exon ending at the junction start.
+    left_flank: u64,
+    /// Length of the shortest exon starting at the junction end.
+    right_flank: u64,
+}
+
+/// Collector of the unique junctions and exons of transcripts.
+#[derive(Debug, Default)]
+pub struct JunctionCollector {
+    junctions: BTreeMap<Key, Junction>,
+    exons: BTreeSet<Key>,
+}
+
+impl JunctionCollector {
+
+    /// Creates an empty collector.
+    pub fn new() -> JunctionCollector {
+        JunctionCollector::default()
+    }
+
+    /// Returns the number of unique junctions.
+    pub fn len(&self) -> usize {
+        self.junctions.len()
+    }
+
+    /// Returns whether no junctions have been collected.
+    pub fn is_empty(&self) -> bool {
+        self.junctions.is_empty()
+    }
+
+    /// Adds the junctions and exons of the given transcript.
+    pub fn add_transcript(&mut self, trx: &Transcript) {
+        let strand = strand_to_char(trx.strand());
+        let mut exon_coords = trx.exons().iter()
+            .map(|exon| (exon.start(), exon.end()))
+            .collect::<Vec<(u64, u64)>>();
+        exon_coords.sort();
+
+        for &(start, end) in exon_coords.iter() {
+            let _ = self.exons.insert((trx.seq_name().to_owned(), start, end, strand));
+        }
+
+        for intron in trx.introns() {
+            let left_flank = exon_coords.iter()
+                .find(|&&(_, exn_end)| exn_end == intron.start())
+                .map(|&(exn_start, exn_end)| exn_end - exn_start)
+                .unwrap_or(0);
+            let right_flank = exon_coords.iter()
+                .find(|&&(exn_start, _)| exn_start == intron.end())
+                .map(|&(exn_start, exn_end)| exn_end - exn_start)
+                .unwrap_or(0);
+            let key = (trx.seq_name().to_owned(), intron.start(), intron.end(), strand);
+            let junction = self.junctions.entry(key).or_insert_with(|| Junction {
+                left_flank: left_flank,
+                right_flank: right_flank,
+                ..Junction::default()
+            });
+            junction.left_flank = min(junction.left_flank, left_flank);
+            junction.right_flank = min(junction.right_flank, right_flank);
+            if let Some(tid) = trx.id() {
+                let _ = junction.transcript_ids.insert(tid.to_owned());
+            }
+            if let Some(gid) = trx.gene_id() {
+                let _ = junction.gene_ids.insert(gid.to_owned());
+            }
+        }
+    }
+
+    /// Writes the collected junctions, or exons, in the given format.
+    pub fn write<W: io::Write>(&self, writer: &mut W, format: JunctionFormat) -> io::Result<()> {
+        match format {
+            JunctionFormat::Star => self.write_star(writer),
+            JunctionFormat::Hisat2SpliceSites => self.write_hisat2_ss(writer),
+            JunctionFormat::Hisat2Exons => self.write_hisat2_exons(writer),
+            JunctionFormat::Regtools => self.write_regtools(writer),
+        }
+    }
+
+    fn write_star<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
+        for (&(ref seq_name, start, end, strand), junction) in self.junctions.iter() {
+            writeln!(writer, "{}\t{}\t{}\t{}\t{}\t{}",
+                     seq_name, start + 1, end, strand,
+                     join_ids(&junction.transcript_ids), join_ids(&junction.gene_ids))?;
+        }
+        Ok(())
+    }
+
+    fn write_hisat2_ss<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
+        for &(ref seq_name, start, end, strand) in self.junctions.keys() {
+            if start == 0 {
+                continue;
+            }
+            writeln!(writer, "{}\t{}\t{}\t{}", seq_name, start - 1, end, strand)?;
+        }
+        Ok(())
+    }
+
+    fn write_hisat2_exons<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
+        for &(ref seq_name, start, end, strand) in self.exons.iter() {
+            if start == end {
+                continue;
+            }
+            writeln!(writer, "{}\t{}\t{}\t{}", seq_name, start, end - 1, strand)?;
+        }
+        Ok(())
+    }
+
+    fn write_regtools<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
+        for (idx, (&(ref seq_name, start, end, strand), junction)) in
+            self.junctions.iter().enumerate()
+        {
+            let left_flank = min(junction.left_flank, start);
+            let right_flank = junction.right_flank;
+            let (row_start, row_end) = (start - left_flank, end + right_flank);
+            writeln!(writer, "{}\t{}\t{}\tJUNC{:08}\t{}\t{}\t{}\t{}\t{}\t2\t{},{}\t0,{}",
+                     seq_name, row_start, row_end, idx + 1, junction.transcript_ids.len(),
+                     strand, row_start, row_end, REGTOOLS_RGB,
+                     left_flank, right_flank, end - row_start)?;
+        }
+        Ok(())
+    }
+}
+
+/// Helper function to join identifiers with commas.
+fn join_ids(ids: &BTreeSet<String>) -> String {
+    if ids.is_empty() {
+        return MISSING.to_owned();
+    }
+    ids.iter().map(|id| id.as_str()).collect::<Vec<&str>>().join(",")
+}
+
+/// Helper function to convert strands into their char representation.
+fn strand_to_char(strand: &Strand) -> char {
+    match strand {
+        &Strand::Forward => '+',
+        &Strand::Reverse => '-',
+        &Strand::Unknown => '.',
+    }
+}
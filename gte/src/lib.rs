@@ -3,7 +3,9 @@ The `gte` crate provides functionalities for working with genes, transcripts, an
 
 It defines simple structs for genes, transcripts, and exons, along with builders of these
 structures that accept a flexible range of arguments. You can create these structs on your own
-or from formats such as GFF and refFlat which are commonly used for storing gene annotations.
+or from formats such as GFF, refFlat, genePred, and BED which are commonly used for storing gene
+annotations. The `AnnotationReader` and `AnnotationWriter` traits allow working with these
+formats without special-casing each of them.
 
 */
 #![deny(missing_docs,
@@ -16,6 +18,7 @@ or from formats such as GFF and refFlat which are commonly used for storing gene
 
 extern crate bio;
 extern crate csv;
+extern crate flate2;
 extern crate itertools;
 extern crate linked_hash_map;
 extern crate multimap;
@@ -34,11 +37,55 @@ pub use model::{Feature, ModelError, FeatureKind,
 
 mod io_refflat;
 pub use io_refflat::{Reader as RefFlatReader, Writer as RefFlatWriter,
+                     Layout as RefFlatLayout, CdsStat,
                      RefFlatError, RefFlatRow, RefFlatRecord,
                      RefFlatRecordsStream, RefFlatTranscriptsStream, RefFlatGenesStream};
 
 mod io_gff;
-pub use io_gff::{Reader as GffReader, GffError, GffTranscripts};
+pub use io_gff::{Reader as GffReader, Writer as GffWriter, IndexedReader as GffIndexedReader,
+                 GffError, GffTranscripts, GffTranscriptsStream, GffGenes};
+
+mod io_bed;
+pub use io_bed::{Reader as BedReader, Writer as BedWriter, IndexedReader as BedIndexedReader,
+                 BedError, BedRecord, BedRecordsStream, BedTranscriptsStream};
+
+mod io_saf;
+pub use io_saf::{Writer as SafWriter, Level as SafLevel, SafError};
+
+mod io_gzip;
+pub use io_gzip::{Reader as GzipReader, Writer as BgzfWriter};
+
+mod io_tabix;
+pub use io_tabix::{Reader as TabixReader, Index as TabixIndex, IndexBuilder as TabixIndexBuilder,
+                   Preset as TabixPreset, TabixError};
+
+mod annotation;
+pub use annotation::{AnnotationReader, AnnotationWriter, AnnotationFormat,
+                     AnnotationTranscripts, AnnotationGenes, open_annotation};
+
+mod seq;
+pub use seq::{Reader as SeqReader, SeqError};
+
+mod translation;
+pub use translation::{GeneticCode, Translation, TranslationIssue};
+
+mod index;
+pub use index::{AnnotationIndex, Indexable};
+
+mod mapping;
+pub use mapping::{MappingError, PosAnchor, TranscriptPos};
+
+mod collapse;
+pub use collapse::ExonBin;
+
+mod junctions;
+pub use junctions::{JunctionCollector, JunctionFormat};
+
+mod policy;
+pub use policy::{ErrorPolicy, ErrorReport, RejectedRecord};
+
+mod lines;
+pub use lines::IdLines;
 
 
 quick_error! {
@@ -66,6 +113,91 @@ quick_error! {
             from()
             cause(err)
         }
+        /// Errors that occur when reading or writing BED files.
+        Bed(err: BedError) {
+            description(err.description())
+            display("{}", err)
+            from()
+            cause(err)
+        }
+        /// Errors that occur when writing SAF files.
+        Saf(err: SafError) {
+            description(err.description())
+            display("{}", err)
+            from()
+            cause(err)
+        }
+        /// Errors that occur when reading or building tabix indices.
+        Tabix(err: TabixError) {
+            description(err.description())
+            display("{}", err)
+            from()
+            cause(err)
+        }
+        /// Errors that occur when extracting sequences from a FASTA reference.
+        Seq(err: SeqError) {
+            description(err.description())
+            display("{}", err)
+            from()
+            cause(err)
+        }
+        /// Errors that occur when parsing transcript positions.
+        Mapping(err: MappingError) {
+            description(err.description())
+            display("{}", err)
+            from()
+            cause(err)
+        }
+        /// Occurs when a line of an input file is not valid UTF-8.
+        InvalidUtf8 {
+            description("line is not valid UTF-8")
+        }
+        /// Errors that occur when reading a line of an input file, along with their location.
+        ///
+        /// The line number is one-based. The field is the name of the offending column, when
+        /// the error can be attributed to a single column.
+        Line(line: usize, field: Option<&'static str>, raw: String, err: Box<Error>) {
+            description(err.description())
+            display(self_) -> ("line {}{}: {}",
+                               line, field.map(|f| format!(", field '{}'", f))
+                                   .unwrap_or_else(String::new), err)
+            cause(&**err)
+        }
+    }
+}
+
+impl Error {
+
+    /// Returns the one-based number of the input line at which the error occurred, if known.
+    pub fn line(&self) -> Option<usize> {
+        match *self {
+            Error::Line(line, _, _, _) => Some(line),
+            _ => None,
+        }
+    }
+
+    /// Returns the name of the offending column of the input line, if known.
+    pub fn field(&self) -> Option<&'static str> {
+        match *self {
+            Error::Line(_, field, _, _) => field,
+            _ => None,
+        }
+    }
+
+    /// Returns the raw content of the offending input line, if known.
+    pub fn raw_line(&self) -> Option<&str> {
+        match *self {
+            Error::Line(_, _, ref raw, _) => Some(raw.as_str()),
+            _ => None,
+        }
+    }
+
+    /// Returns the underlying error, without any location.
+    pub fn inner(&self) -> &Error {
+        match *self {
+            Error::Line(_, _, _, ref err) => err.inner(),
+            _ => self,
+        }
     }
 }
 
@@ -104,6 +236,7 @@ const INIT_COORD: (u64, u64) = (INIT_START, INIT_END);
 
 /// Utility functions.
 mod utils {
+    use std::io::{self, BufRead};
     use std::ops::Deref;
 
     // taken from: https://stackoverflow.com/q/31233938/243058
@@ -144,4 +277,86 @@ mod utils {
             }
         }
     }
+
+    /// Helper struct for reading lines along with their one-based line numbers.
+    ///
+    /// The line numbers are kept by the struct, so that they stay correct across iterators
+    /// created from the same reader. Trailing newline characters are removed from the lines.
+    pub(crate) struct LineReader<R: io::Read> {
+        inner: io::BufReader<R>,
+        line_num: usize,
+        failed: bool,
+    }
+
+    impl<R: io::Read> LineReader<R> {
+
+        /// Creates a line reader from another reader.
+        pub(crate) fn new(in_reader: R) -> LineReader<R> {
+            LineReader {
+                inner: io::BufReader::new(in_reader),
+                line_num: 0,
+                failed: false,
+            }
+        }
+
+        /// Returns the next line of the input, or `None` if the input has been exhausted.
+        ///
+        /// Errors from the underlying reader are converted with the given function, and no more
+        /// lines are returned after them. Lines that are not valid UTF-8 are returned as
+        /// `Error::Line` values, so that reading can continue with the next line.
+        pub(crate) fn next_line<F>(&mut self, io_error: F) -> Option<::Result<LinePos>>
+            where F: FnOnce(io::Error) -> ::Error
+        {
+            if self.failed {
+                return None;
+            }
+            let mut buf = Vec::new();
+            match self.inner.read_until(b'\n', &mut buf) {
+                Ok(0) => return None,
+                Ok(_) => self.line_num += 1,
+                Err(e) => {
+                    self.failed = true;
+                    return Some(Err(io_error(e)));
+                },
+            }
+            while buf.last() == Some(&b'\n') || buf.last() == Some(&b'\r') {
+                let _ = buf.pop();
+            }
+            let line = self.line_num;
+            Some(String::from_utf8(buf)
+                .map(|raw| LinePos { line: line, raw: raw })
+                .map_err(|e| {
+                    let raw = String::from_utf8_lossy(&e.into_bytes()).into_owned();
+                    ::Error::Line(line, None, raw, Box::new(::Error::InvalidUtf8))
+                }))
+        }
+    }
+
+    /// Helper struct for the location and the raw content of an input line.
+    #[derive(Debug, Clone)]
+    pub(crate) struct LinePos {
+        /// One-based line number.
+        pub(crate) line: usize,
+        /// Line content, without trailing newline characters.
+        pub(crate) raw: String,
+    }
+
+    impl LinePos {
+
+        /// Returns the tab-separated columns of the line.
+        pub(crate) fn columns(&self) -> Vec<String> {
+            self.raw.split('\t').map(|col| col.to_owned()).collect()
+        }
+
+        /// Wraps the given error with the location of the line and the offending field.
+        pub(crate) fn error<E>(&self, field: Option<&'static str>, err: E) -> ::Error
+            where E: Into<::Error>
+        {
+            let err = err.into();
+            match err {
+                ::Error::Line(..) => err,
+                _ => ::Error::Line(self.line, field, self.raw.clone(), Box::new(err)),
+            }
+        }
+    }
 }
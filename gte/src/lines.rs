@@ -0,0 +1,51 @@
+/*! Line numbers of the identifiers read from annotation files.
+
+The GFF and refFlat readers can be set to record the line numbers of the transcript and gene
+identifiers in their rows. This allows pointing to the rows of transcripts and genes after they
+have been created, for example when reporting problems found in them.
+*/
+use std::collections::{hash_map, BTreeSet, HashMap};
+
+
+/// Line numbers of the transcript and gene identifiers read by a reader.
+#[derive(Debug, Default)]
+pub struct IdLines {
+    lines: HashMap<String, BTreeSet<usize>>,
+    transcript_rows: HashMap<String, usize>,
+}
+
+impl IdLines {
+
+    /// Returns the one-based line numbers of the rows with the given transcript or gene
+    /// identifier, in ascending order.
+    pub fn get(&self, id: &str) -> Option<&BTreeSet<usize>> {
+        self.lines.get(id)
+    }
+
+    /// Returns an iterator over the identifiers of transcripts defined by GFF rows, along with
+    /// their number of rows.
+    ///
+    /// These are the GFF3 transcript rows with the identifier as their `ID` value and the GTF
+    /// or GFF2 `transcript` rows with the identifier. Since rows of the same transcript
+    /// identifier, sequence, and strand are merged into one transcript, the number of rows may
+    /// be larger than the number of transcripts created. RefFlat rows are not counted, since
+    /// each of them creates its own transcript.
+    pub fn transcript_rows(&self) -> hash_map::Iter<String, usize> {
+        self.transcript_rows.iter()
+    }
+
+    /// Returns whether no lines were recorded.
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    /// Adds the line number of a row with the given identifier.
+    pub(crate) fn add(&mut self, id: &str, line: usize) {
+        let _ = self.lines.entry(id.to_owned()).or_insert_with(BTreeSet::new).insert(line);
+    }
+
+    /// Adds a GFF row that defines the transcript with the given identifier.
+    pub(crate) fn add_transcript_row(&mut self, tid: &str) {
+        *self.transcript_rows.entry(tid.to_owned()).or_insert(0) += 1;
+    }
+}
@@ -0,0 +1,334 @@
+/*! Coordinate mapping between the genome and transcripts.
+
+This module provides methods for converting genome-wise positions of a transcript into its cDNA
+and coding sequence (CDS) positions, and back. Transcript positions follow the
+[HGVS](https://varnomen.hgvs.org/bg-material/numbering/) numbering conventions:
+
+* cDNA (`n.`) positions are numbered from the first base of the transcript, while CDS (`c.`)
+  positions are numbered from the first base of the start codon. There is no position 0.
+* Positions before the first base are denoted with `-` (e.g. `c.-15`) and positions after the
+  last base are denoted with `*` (e.g. `c.*30`). For CDS positions, the last base is the last base
+  of the stop codon.
+* Intronic positions are denoted by the closest exonic position and the number of bases from it
+  (e.g. `c.100+5`, `c.101-3`). Positions exactly in the middle of an intron use the upstream
+  exonic position.
+
+Genome-wise positions are zero-based. Transcripts on the unknown strand are treated as if they
+are on the forward strand.
+*/
+use std::error::Error;
+use std::fmt;
+use std::num::ParseIntError;
+use std::str::FromStr;
+
+use {Strand, Transcript};
+
+
+quick_error! {
+    /// Errors that occur when parsing transcript positions.
+    #[derive(Debug)]
+    pub enum MappingError {
+        /// Occurs when a transcript position string can not be parsed.
+        InvalidPosition(raw: String) {
+            description("invalid transcript position")
+            display(self_) -> ("{}: '{}'", self_.description(), raw)
+        }
+        /// Occurs when any of the numbers in a transcript position string is invalid.
+        InvalidNumber(err: ParseIntError) {
+            description(err.description())
+            from()
+            cause(err)
+        }
+    }
+}
+
+/// The base position of a transcript position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PosAnchor {
+    /// Number of bases before the first base of the region (`-N`).
+    Upstream(u64),
+    /// One-based position within the region (`N`).
+    Within(u64),
+    /// Number of bases after the last base of the region (`*N`).
+    Downstream(u64),
+}
+
+/// A position relative to a transcript, in HGVS notation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TranscriptPos {
+    anchor: PosAnchor,
+    offset: i64,
+}
+
+impl TranscriptPos {
+
+    /// Creates a new transcript position.
+    ///
+    /// The offset is the number of bases from the anchor position into the intron. It is positive
+    /// for bases downstream of the anchor and negative for bases upstream of the anchor.
+    pub fn new(anchor: PosAnchor, offset: i64) -> TranscriptPos {
+        TranscriptPos {
+            anchor: anchor,
+            offset: offset,
+        }
+    }
+
+    /// Returns the anchor of the position.
+    pub fn anchor(&self) -> PosAnchor {
+        self.anchor
+    }
+
+    /// Returns the intronic offset of the position.
+    pub fn offset(&self) -> i64 {
+        self.offset
+    }
+
+    /// Returns whether the position lies in an intron.
+    pub fn is_intronic(&self) -> bool {
+        self.offset != 0
+    }
+}
+
+impl fmt::Display for TranscriptPos {
+
+    /// Formats the position without any coordinate type prefix, e.g. `-15+2` or `*30`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.anchor {
+            PosAnchor::Upstream(n) => write!(f, "-{}", n)?,
+            PosAnchor::Within(n) => write!(f, "{}", n)?,
+            PosAnchor::Downstream(n) => write!(f, "*{}", n)?,
+        }
+        if self.offset > 0 {
+            write!(f, "+{}", self.offset)?;
+        } else if self.offset < 0 {
+            write!(f, "{}", self.offset)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for TranscriptPos {
+
+    type Err = ::Error;
+
+    /// Parses the position from a string, with or without a `c.` or `n.` prefix.
+    fn from_str(raw: &str) -> ::Result<TranscriptPos> {
+        let invalid = || ::Error::from(MappingError::InvalidPosition(raw.to_owned()));
+        let value = raw.trim_left_matches("c.").trim_left_matches("n.");
+        let (anchor_kind, value) =
+            if value.starts_with('-') {
+                ('-', &value[1..])
+            } else if value.starts_with('*') {
+                ('*', &value[1..])
+            } else {
+                (' ', value)
+            };
+
+        let split_idx = value.find(|c| c == '+' || c == '-').unwrap_or(value.len());
+        let (raw_anchor, raw_offset) = value.split_at(split_idx);
+        let anchor_pos = u64::from_str(raw_anchor)
+            .map_err(|e| ::Error::from(MappingError::from(e)))?;
+        let offset =
+            if raw_offset.is_empty() {
+                0
+            } else {
+                let magnitude = i64::from_str(&raw_offset[1..])
+                    .map_err(|e| ::Error::from(MappingError::from(e)))?;
+                if raw_offset.starts_with('-') { -magnitude } else { magnitude }
+            };
+        if anchor_pos == 0 {
+            return Err(invalid());
+        }
+
+        let anchor = match anchor_kind {
+            '-' => PosAnchor::Upstream(anchor_pos),
+            '*' => PosAnchor::Downstream(anchor_pos),
+            _ => PosAnchor::Within(anchor_pos),
+        };
+        Ok(TranscriptPos::new(anchor, offset))
+    }
+}
+
+impl Transcript {
+
+    /// Converts the given genome-wise position into a cDNA position.
+    pub fn genome_to_cdna(&self, pos: u64) -> TranscriptPos {
+        let (linear, offset) = self.genome_to_linear(pos);
+        let anchor =
+            if linear < 1 {
+                PosAnchor::Upstream((1 - linear) as u64)
+            } else if linear > self.spliced_len() as i64 {
+                PosAnchor::Downstream((linear - self.spliced_len() as i64) as u64)
+            } else {
+                PosAnchor::Within(linear as u64)
+            };
+        TranscriptPos::new(anchor, offset)
+    }
+
+    /// Converts the given genome-wise position into a CDS position.
+    ///
+    /// `None` is returned if the transcript is not coding.
+    pub fn genome_to_cds(&self, pos: u64) -> Option<TranscriptPos> {
+        let (cds_first, cds_last) = match self.linear_cds_bounds() {
+            Some(bounds) => bounds,
+            None => return None,
+        };
+        let (linear, offset) = self.genome_to_linear(pos);
+        let anchor =
+            if linear < cds_first {
+                PosAnchor::Upstream((cds_first - linear) as u64)
+            } else if linear > cds_last {
+                PosAnchor::Downstream((linear - cds_last) as u64)
+            } else {
+                PosAnchor::Within((linear - cds_first + 1) as u64)
+            };
+        Some(TranscriptPos::new(anchor, offset))
+    }
+
+    /// Converts the given cDNA position into a genome-wise position.
+    ///
+    /// `None` is returned if the position does not exist, for example when its offset extends
+    /// beyond the intron it is in.
+    pub fn cdna_to_genome(&self, pos: &TranscriptPos) -> Option<u64> {
+        let linear = match pos.anchor {
+            PosAnchor::Upstream(n) => 1 - n as i64,
+            PosAnchor::Within(n) => n as i64,
+            PosAnchor::Downstream(n) => self.spliced_len() as i64 + n as i64,
+        };
+        self.linear_to_genome(linear, pos.offset)
+    }
+
+    /// Converts the given CDS position into a genome-wise position.
+    ///
+    /// `None` is returned if the transcript is not coding or if the position does not exist, for
+    /// example when its offset extends beyond the intron it is in.
+    pub fn cds_to_genome(&self, pos: &TranscriptPos) -> Option<u64> {
+        let (cds_first, cds_last) = match self.linear_cds_bounds() {
+            Some(bounds) => bounds,
+            None => return None,
+        };
+        let linear = match pos.anchor {
+            PosAnchor::Upstream(n) => cds_first - n as i64,
+            PosAnchor::Within(n) => cds_first + n as i64 - 1,
+            PosAnchor::Downstream(n) => cds_last + n as i64,
+        };
+        self.linear_to_genome(linear, pos.offset)
+    }
+
+    /// Returns the exon intervals in transcript-wise coordinates, ordered from the transcript-wise
+    /// 5'-most exon.
+    ///
+    /// Transcript-wise coordinates increase in the direction of transcription. The intervals are
+    /// closed.
+    fn tx_exons(&self) -> Vec<(i64, i64)> {
+        let mut exons = self.exons().iter()
+            .map(|exon| (self.to_tx_coord(exon.start()), self.to_tx_coord(exon.end() - 1)))
+            .map(|(a, b)| if a <= b { (a, b) } else { (b, a) })
+            .collect::<Vec<(i64, i64)>>();
+        exons.sort();
+        exons
+    }
+
+    /// Converts a genome-wise position into a transcript-wise coordinate.
+    #[inline]
+    fn to_tx_coord(&self, pos: u64) -> i64 {
+        match self.strand() {
+            &Strand::Reverse => -(pos as i64),
+            _ => pos as i64,
+        }
+    }
+
+    /// Converts a transcript-wise coordinate into a genome-wise position.
+    #[inline]
+    fn from_tx_coord(&self, coord: i64) -> Option<u64> {
+        let pos = match self.strand() {
+            &Strand::Reverse => -coord,
+            _ => coord,
+        };
+        if pos < 0 { None } else { Some(pos as u64) }
+    }
+
+    /// Converts a genome-wise position into a one-based linear cDNA position and an intronic
+    /// offset.
+    ///
+    /// Linear positions less than 1 are upstream of the transcript and linear positions larger
+    /// than the transcript length are downstream of the transcript.
+    fn genome_to_linear(&self, pos: u64) -> (i64, i64) {
+        let coord = self.to_tx_coord(pos);
+        let exons = self.tx_exons();
+        if exons.is_empty() {
+            return (1, 0);
+        }
+        let (first_start, _) = exons[0];
+        if coord < first_start {
+            return (1 - (first_start - coord), 0);
+        }
+
+        let mut exon_first = 1;
+        for (idx, &(start, end)) in exons.iter().enumerate() {
+            if coord <= end {
+                return (exon_first + coord - start, 0);
+            }
+            let exon_last = exon_first + end - start;
+            match exons.get(idx + 1) {
+                Some(&(next_start, _)) if coord < next_start => {
+                    let (dist5, dist3) = (coord - end, next_start - coord);
+                    return if dist5 <= dist3 {
+                        (exon_last, dist5)
+                    } else {
+                        (exon_last + 1, -dist3)
+                    };
+                },
+                Some(_) => {},
+                None => return (exon_last + coord - end, 0),
+            }
+            exon_first = exon_last + 1;
+        }
+        unreachable!()
+    }
+
+    /// Converts a one-based linear cDNA position and an intronic offset into a genome-wise
+    /// position.
+    fn linear_to_genome(&self, linear: i64, offset: i64) -> Option<u64> {
+        let exons = self.tx_exons();
+        if exons.is_empty() {
+            return None;
+        }
+        let (first_start, _) = exons[0];
+        if linear < 1 {
+            return if offset == 0 { self.from_tx_coord(first_start - (1 - linear)) } else { None };
+        }
+
+        let mut exon_first = 1;
+        for (idx, &(start, end)) in exons.iter().enumerate() {
+            let exon_last = exon_first + end - start;
+            let is_last_exon = idx == exons.len() - 1;
+            if linear > exon_last && !is_last_exon {
+                exon_first = exon_last + 1;
+                continue;
+            }
+            let coord = start + linear - exon_first;
+            return match offset {
+                0 => self.from_tx_coord(coord),
+                _ if linear > exon_last => None,
+                o if o > 0 && linear == exon_last && !is_last_exon
+                    && coord + o < exons[idx + 1].0 => self.from_tx_coord(coord + o),
+                o if o < 0 && linear == exon_first && idx > 0
+                    && coord + o > exons[idx - 1].1 => self.from_tx_coord(coord + o),
+                _ => None,
+            };
+        }
+        unreachable!()
+    }
+
+    /// Returns the linear cDNA positions of the first base of the start codon and the last base
+    /// of the stop codon.
+    fn linear_cds_bounds(&self) -> Option<(i64, i64)> {
+        self.coding_coord(true)
+            .map(|(coding_start, coding_end)| match self.strand() {
+                &Strand::Reverse => (coding_end - 1, coding_start),
+                _ => (coding_start, coding_end - 1),
+            })
+            .map(|(first, last)| (self.genome_to_linear(first).0, self.genome_to_linear(last).0))
+    }
+}
@@ -96,6 +96,7 @@ macro_rules! impl_common {
 pub struct Feature<K: FeatureKind> {
     interval: Interval<u64>,
     kind: K,
+    attributes: MultiMap<String, String>,
 }
 
 impl<K: FeatureKind> Feature<K> {
@@ -105,6 +106,7 @@ impl<K: FeatureKind> Feature<K> {
         Feature {
             interval: interval,
             kind: kind,
+            attributes: MultiMap::new(),
         }
     }
 
@@ -133,6 +135,23 @@ impl<K: FeatureKind> Feature<K> {
     pub fn span(&self) -> u64 {
         self.end() - self.start()
     }
+
+    /// Returns a reference of the feature attributes.
+    pub fn attributes(&self) -> &MultiMap<String, String> {
+        &self.attributes
+    }
+
+    /// Returns a mutable reference of the feature attributes.
+    pub fn attributes_mut(&mut self) -> &mut MultiMap<String, String> {
+        &mut self.attributes
+    }
+
+    /// Sets the feature attributes and returns the old value.
+    pub fn set_attributes(&mut self, attributes: MultiMap<String, String>)
+        -> MultiMap<String, String>
+    {
+        mem::replace(&mut self.attributes, attributes)
+    }
 }
 
 /// Marker trait for feature kinds.
@@ -184,7 +203,18 @@ pub type ExonFeature = Feature<ExonFeatureKind>;
 #[derive(Debug, Clone, PartialEq)]
 pub enum TranscriptFeatureKind {
     /// An intron.
-    Intron,
+    Intron {
+        /// Transcript-wise rank of the intron, starting from 1 for the 5'-most intron.
+        rank: usize,
+        /// Genome-wise position of the splice donor site.
+        ///
+        /// This is the transcript-wise 5'-most base of the intron.
+        donor: u64,
+        /// Genome-wise position of the splice acceptor site.
+        ///
+        /// This is the transcript-wise 3'-most base of the intron.
+        acceptor: u64,
+    },
     /// Other features that may exist within transcripts.
     Any(String),
 }
@@ -427,6 +457,7 @@ pub struct Transcript {
     gene_id: Option<String>,
     attributes: MultiMap<String, String>,
     exons: Vec<Exon>,
+    features: Vec<TranscriptFeature>,
 }
 
 impl_common!(Transcript);
@@ -465,15 +496,92 @@ impl Transcript {
         self.exons.as_slice()
     }
 
+    /// Returns a mutable slice of the transcript's exons.
+    pub fn exons_mut(&mut self) -> &mut [Exon] {
+        self.exons.as_mut_slice()
+    }
+
     /// Consumes the transcript and returns its exons.
     pub fn take_exons(self) -> Vec<Exon> {
         self.exons
     }
 
+    /// Returns the introns of the transcript, inferred from the gaps between its exons.
+    ///
+    /// The introns are ordered genome-wise. Their ranks and splice sites follow the transcript
+    /// strand, with transcripts on the unknown strand treated as if they are on the forward
+    /// strand. Adjacent exons without any gap between them do not create any introns.
+    pub fn introns(&self) -> Vec<TranscriptFeature> {
+        let mut exon_coords = self.exons.iter()
+            .map(|exon| (exon.start(), exon.end()))
+            .collect::<Vec<Coord<u64>>>();
+        exon_coords.sort();
+        let gaps = exon_coords.iter()
+            .zip(exon_coords.iter().skip(1))
+            .filter(|&(&(_, prev_end), &(next_start, _))| prev_end < next_start)
+            .map(|(&(_, prev_end), &(next_start, _))| (prev_end, next_start))
+            .collect::<Vec<Coord<u64>>>();
+
+        let num_introns = gaps.len();
+        gaps.into_iter().enumerate()
+            .filter_map(|(idx, (start, end))| {
+                let kind = match &self.strand {
+                    &Strand::Reverse => TranscriptFeatureKind::Intron {
+                        rank: num_introns - idx,
+                        donor: end - 1,
+                        acceptor: start,
+                    },
+                    _ => TranscriptFeatureKind::Intron {
+                        rank: idx + 1,
+                        donor: start,
+                        acceptor: end - 1,
+                    },
+                };
+                coord_to_interval(start, end).ok().map(|interval| Feature::new(interval, kind))
+            })
+            .collect()
+    }
+
+    /// Returns a slice of the features within the transcript that are not exon features.
+    pub fn features(&self) -> &[TranscriptFeature] {
+        self.features.as_slice()
+    }
+
+    /// Returns a mutable slice of the features within the transcript.
+    pub fn features_mut(&mut self) -> &mut [TranscriptFeature] {
+        self.features.as_mut_slice()
+    }
+
+    /// Returns the number of bases spanned by all the transcript's exons.
+    pub fn spliced_len(&self) -> u64 {
+        self.exons.iter()
+            .map(|exon| exon.span())
+            .fold(0, |acc, x| acc + x)
+    }
+
+    /// Returns the number of bases spanned by all the transcript's CDS features.
+    ///
+    /// Following the GTF convention, the returned value does not include the stop codon.
+    pub fn coding_len(&self) -> u64 {
+        self.exons.iter()
+            .flat_map(|exon| exon.features.iter())
+            .filter(|fx| match fx.kind { CDS { .. } => true, _ => false })
+            .map(|fx| fx.span())
+            .fold(0, |acc, x| acc + x)
+    }
+
+    /// Returns whether the transcript has any CDS features.
+    pub fn is_coding(&self) -> bool {
+        self.exons.iter()
+            .flat_map(|exon| exon.features.iter())
+            .any(|fx| match fx.kind { CDS { .. } => true, _ => false })
+    }
+
     /// Returns the genome-wise 5' and 3'-most coordinate of the coding region.
     ///
     /// The returned coding region coordinates may include the stop codon, depending on the value
-    /// of the `incl_stop` argument.
+    /// of the `incl_stop` argument. Ends of the coding region without codon features, such as
+    /// incomplete ends, are taken from the CDS features.
     ///
     /// The returned value is `None` in any of these cases:
     ///
@@ -500,7 +608,7 @@ impl Transcript {
                         }
                     }
                 }
-                None
+                self.cds_start_coord()
             },
             &Strand::Reverse => {
                 let mut codon_rem = if incl_stop { 0 } else { 3 };
@@ -517,7 +625,7 @@ impl Transcript {
                         }
                     }
                 }
-                None
+                self.cds_start_coord()
             },
             &Strand::Unknown if incl_stop => {
                 for exon in self.exons.iter() {
@@ -551,7 +659,7 @@ impl Transcript {
                         }
                     }
                 }
-                None
+                self.cds_end_coord()
             },
             &Strand::Reverse => {
                 for exon in self.exons.iter().rev() {
@@ -561,7 +669,7 @@ impl Transcript {
                         }
                     }
                 }
-                None
+                self.cds_end_coord()
             },
             &Strand::Unknown if incl_stop => {
                 for exon in self.exons.iter().rev() {
@@ -577,6 +685,23 @@ impl Transcript {
         }
     }
 
+    /// Returns the genome-wise 5'-most coordinate of the CDS features.
+    fn cds_start_coord(&self) -> Option<u64> {
+        self.exons.iter()
+            .flat_map(|exon| exon.features.iter())
+            .filter(|fx| match fx.kind { CDS { .. } => true, _ => false })
+            .map(|fx| fx.interval.start)
+            .min()
+    }
+
+    /// Returns the genome-wise 3'-most coordinate of the CDS features.
+    fn cds_end_coord(&self) -> Option<u64> {
+        self.exons.iter()
+            .flat_map(|exon| exon.features.iter())
+            .filter(|fx| match fx.kind { CDS { .. } => true, _ => false })
+            .map(|fx| fx.interval.end)
+            .max()
+    }
 }
 
 /// Builder for transcripts.
@@ -600,6 +725,7 @@ pub struct TBuilder {
     exon_coords: Option<Vec<Coord<u64>>>,
     coding_coord: Option<Coord<u64>>,
     coding_incl_stop: bool,
+    features: Vec<TranscriptFeature>,
 }
 
 impl TBuilder {
@@ -621,6 +747,7 @@ impl TBuilder {
             exon_coords: None,
             coding_coord: None,
             coding_incl_stop: false,
+            features: Vec::new(),
         }
     }
 
@@ -721,6 +848,18 @@ impl TBuilder {
         self
     }
 
+    /// Adds a feature to the transcript.
+    pub fn feature(mut self, feature: TranscriptFeature) -> Self {
+        self.features.push(feature);
+        self
+    }
+
+    /// Sets all the features of the transcript.
+    pub fn features(mut self, features: Vec<TranscriptFeature>) -> Self {
+        self.features = features;
+        self
+    }
+
     /// Validates the input data and builds a transcript.
     pub fn build(self) -> ::Result<Transcript> {
         let interval = coord_to_interval(self.start, self.end)
@@ -741,6 +880,7 @@ impl TBuilder {
             gene_id: self.gene_id,
             attributes: self.attributes,
             exons: exons,
+            features: self.features,
         };
         Ok(transcript)
     }
@@ -1181,9 +1321,9 @@ fn infer_exons(
             // There must be room for stop codons (which is not inclusive in coding_coord)
             let stop_codon_ok = match transcript_strand {
                 &Strand::Forward => coding_r.1 + 3 <= exon_r.1,
-                &Strand::Reverse => coding_r.0 - 3 >= exon_r.0,
+                &Strand::Reverse => coding_r.0 >= exon_r.0 + 3,
                 &Strand::Unknown =>
-                    coding_r.0 - 3 >= exon_r.0 && coding_r.1 + 3 <= exon_r.1,
+                    coding_r.0 >= exon_r.0 + 3 && coding_r.1 + 3 <= exon_r.1,
             };
             if !stop_codon_ok {
                 return Err(ModelError::CodingTooLarge(tid));
@@ -1223,7 +1363,7 @@ fn adjust_coding_coord(mut start: u64, mut end: u64,
         &Strand::Forward => {
             for &(exon_start, exon_end) in exon_coords.iter().rev() {
                 if exon_start <= end && end <= exon_end {
-                    let adj_end = max(end - codon_rem, exon_start);
+                    let adj_end = max(end.saturating_sub(codon_rem), exon_start);
                     codon_rem -= end - adj_end;
                     end = adj_end;
                     if codon_rem == 0 {
@@ -1290,6 +1430,7 @@ fn infer_exon_features(
         ExonFeature {
             interval: Interval::new(start..end).unwrap(),
             kind: kind,
+            attributes: MultiMap::new(),
         }
     };
 
@@ -1651,7 +1792,7 @@ where F: Fn(u64, u64, ExonFeatureKind) -> ExonFeature
         if codon_rem == 0 {
             break;
         };
-        let fx = feature_maker(max(exon.start(), exon.end() - codon_rem),
+        let fx = feature_maker(max(exon.start(), exon.end().saturating_sub(codon_rem)),
                                exon.end(), efk.clone());
         codon_rem -= fx.span();
         let ofxp_start = exon.features.last()
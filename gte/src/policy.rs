@@ -0,0 +1,155 @@
+/*! Policies for handling records that can not be read.
+
+The GFF and refFlat readers can be set to handle errors of individual records in one of three
+ways:
+
+* `Strict`: the first error is returned by the iterator, which then stops.
+* `Skip`: errors are returned by the iterator, which then continues with the next record. This
+  allows the caller to emit a warning before moving on. This is the default policy.
+* `Collect`: errors are not returned by the iterator. Instead, they are stored in the error
+  report of the reader, along with the identifiers of the rejected records, for inspection
+  after iteration.
+
+Errors that occur when reading from the underlying reader always stop the iteration, regardless
+of the policy.
+*/
+use {BedError, GffError, RefFlatError};
+
+
+/// Policies for handling errors of records that can not be read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorPolicy {
+    /// Return the first error and stop reading.
+    Strict,
+    /// Return each error and continue with the next record.
+    Skip,
+    /// Store each error in the error report and continue with the next record.
+    Collect,
+}
+
+impl Default for ErrorPolicy {
+    fn default() -> ErrorPolicy {
+        ErrorPolicy::Skip
+    }
+}
+
+/// A record that was rejected by a reader, along with its error.
+#[derive(Debug)]
+pub struct RejectedRecord {
+    id: Option<String>,
+    error: ::Error,
+}
+
+impl RejectedRecord {
+
+    /// Returns the identifier of the rejected transcript or gene, if known.
+    pub fn id(&self) -> Option<&str> {
+        self.id.as_ref().map(|id| id.as_str())
+    }
+
+    /// Returns the error of the rejected record.
+    pub fn error(&self) -> &::Error {
+        &self.error
+    }
+}
+
+/// Records rejected by a reader with the `Collect` error policy.
+#[derive(Debug, Default)]
+pub struct ErrorReport {
+    rejected: Vec<RejectedRecord>,
+}
+
+impl ErrorReport {
+
+    /// Returns the rejected records, in the order they were encountered.
+    pub fn rejected(&self) -> &[RejectedRecord] {
+        self.rejected.as_slice()
+    }
+
+    /// Returns the number of rejected records.
+    pub fn len(&self) -> usize {
+        self.rejected.len()
+    }
+
+    /// Returns whether no records were rejected.
+    pub fn is_empty(&self) -> bool {
+        self.rejected.is_empty()
+    }
+}
+
+/// Helper struct for applying an error policy to the results of an iterator.
+pub(crate) struct ErrorHandler<'a> {
+    policy: ErrorPolicy,
+    report: &'a mut ErrorReport,
+    stopped: bool,
+}
+
+impl<'a> ErrorHandler<'a> {
+
+    /// Creates a handler that stores rejected records in the given report.
+    pub(crate) fn new(policy: ErrorPolicy, report: &'a mut ErrorReport) -> ErrorHandler<'a> {
+        ErrorHandler {
+            policy: policy,
+            report: report,
+            stopped: false,
+        }
+    }
+
+    /// Returns whether the iterator must stop returning results.
+    pub(crate) fn is_stopped(&self) -> bool {
+        self.stopped
+    }
+
+    /// Returns the result to be returned by the iterator, or `None` if it was collected.
+    ///
+    /// The identifier is the identifier of the record of the result, if known.
+    pub(crate) fn handle<T>(&mut self, id: Option<String>, result: ::Result<T>)
+        -> Option<::Result<T>>
+    {
+        let err = match result {
+            Ok(value) => return Some(Ok(value)),
+            Err(err) => err,
+        };
+        if is_fatal(&err) {
+            self.stopped = true;
+            return Some(Err(err));
+        }
+        match self.policy {
+            ErrorPolicy::Strict => {
+                self.stopped = true;
+                Some(Err(err))
+            },
+            ErrorPolicy::Skip => Some(Err(err)),
+            ErrorPolicy::Collect => {
+                self.report.rejected.push(RejectedRecord { id: id, error: err });
+                None
+            },
+        }
+    }
+}
+
+/// Helper function for deferring errors of rows that are read before iteration starts.
+///
+/// The error is returned if reading must stop. Otherwise, it is stored in the given errors, to
+/// be handled by the iterator later on.
+pub(crate) fn defer_error(
+    policy: ErrorPolicy,
+    deferred: &mut Vec<::Error>,
+    err: ::Error,
+) -> ::Result<()> {
+    if policy == ErrorPolicy::Strict || is_fatal(&err) {
+        return Err(err);
+    }
+    deferred.push(err);
+    Ok(())
+}
+
+/// Helper function to check whether an error is unrelated to the record being read.
+fn is_fatal(err: &::Error) -> bool {
+    match *err.inner() {
+        ::Error::Gff(GffError::Io(_))
+            | ::Error::RefFlat(RefFlatError::Io(_))
+            | ::Error::Bed(BedError::Io(_)) => true,
+        _ => false,
+    }
+}
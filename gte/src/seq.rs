@@ -0,0 +1,193 @@
+/*! Sequence extraction from a FASTA reference.
+
+This module provides a reader that extracts the sequences of transcripts and their features from
+an indexed FASTA file. The index is the `.fai` file created by
+[samtools faidx](http://www.htslib.org/doc/faidx.html).
+
+All returned sequences are oriented according to the transcript strand: sequences of transcripts
+on the reverse strand are reverse-complemented, while sequences of transcripts on the forward or
+unknown strand are returned as they are in the reference.
+*/
+use std::convert::AsRef;
+use std::error::Error;
+use std::ffi::OsString;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use bio::alphabets::dna;
+use bio::io::fasta;
+use csv;
+
+use {Exon, ExonFeatureKind, Strand, Transcript};
+use ExonFeatureKind::*;
+
+
+/// Extension of FASTA index files.
+const FAI_EXT: &'static str = ".fai";
+
+quick_error! {
+    /// Errors that occur when extracting sequences.
+    #[derive(Debug)]
+    pub enum SeqError {
+        /// Occurs when the requested interval extends beyond the end of the reference sequence.
+        IntervalOutOfRange(seq_name: String, start: u64, end: u64) {
+            description("interval extends beyond the reference sequence")
+            display(self_) -> ("{}: {}:{}-{}", self_.description(), seq_name, start, end)
+        }
+        /// Occurs when a translation table identifier does not denote any known table.
+        InvalidTranslationTable(raw: String) {
+            description("unknown translation table")
+            display(self_) -> ("{}: '{}'", self_.description(), raw)
+        }
+        /// Errors propagated from reading the FASTA index.
+        Index(err: csv::Error) {
+            description(err.description())
+            from()
+            cause(err)
+        }
+        /// Errors propagated from reading the FASTA file.
+        Io(err: io::Error) {
+            description(err.description())
+            from()
+            cause(err)
+        }
+    }
+}
+
+/// Reader for transcript sequences.
+pub struct Reader<R: io::Read + io::Seek> {
+    inner: fasta::IndexedReader<R>,
+}
+
+impl<R: io::Read + io::Seek> Reader<R> {
+
+    /// Creates a sequence reader from a FASTA reader and its index reader.
+    pub fn from_readers<I: io::Read>(fasta_reader: R, fai_reader: I) -> ::Result<Reader<R>> {
+        fasta::IndexedReader::new(fasta_reader, fai_reader)
+            .map(|inner| Reader { inner: inner })
+            .map_err(|e| ::Error::from(SeqError::from(e)))
+    }
+
+    /// Returns the sequence of the given genome-wise interval, as it is in the reference.
+    ///
+    /// The interval coordinates are zero-based, half-open.
+    pub fn interval_seq(&mut self, seq_name: &str, start: u64, end: u64) -> ::Result<Vec<u8>> {
+        let mut seq = Vec::with_capacity((end - start) as usize);
+        self.inner.read(seq_name, start, end, &mut seq)
+            .map_err(|e| ::Error::from(SeqError::from(e)))?;
+        if (seq.len() as u64) < end - start {
+            let err = SeqError::IntervalOutOfRange(seq_name.to_owned(), start, end);
+            return Err(::Error::from(err));
+        }
+        Ok(seq)
+    }
+
+    /// Returns the sequences of the transcript exons.
+    ///
+    /// The exons are ordered from the transcript-wise 5'-most exon.
+    pub fn exon_seqs(&mut self, transcript: &Transcript) -> ::Result<Vec<Vec<u8>>> {
+        let mut seqs = Vec::with_capacity(transcript.exons().len());
+        for exon in transcript.exons() {
+            let seq = self.interval_seq(exon.seq_name(), exon.start(), exon.end())?;
+            seqs.push(orient(seq, transcript.strand()));
+        }
+        if transcript.strand() == &Strand::Reverse {
+            seqs.reverse();
+        }
+        Ok(seqs)
+    }
+
+    /// Returns the spliced sequence of the transcript.
+    pub fn transcript_seq(&mut self, transcript: &Transcript) -> ::Result<Vec<u8>> {
+        let coords = transcript.exons().iter()
+            .map(|exon| (exon.start(), exon.end()))
+            .collect::<Vec<(u64, u64)>>();
+        self.spliced_seq(transcript, coords)
+    }
+
+    /// Returns the sequence of the coding region of the transcript.
+    ///
+    /// The sequence is created from the CDS features of the transcript exons, and may or may not
+    /// include the stop codon depending on the value of the `incl_stop` argument. An empty
+    /// sequence is returned for non-coding transcripts.
+    pub fn coding_seq(&mut self, transcript: &Transcript, incl_stop: bool) -> ::Result<Vec<u8>> {
+        let coords = feature_coords(transcript.exons(), |kind| match kind {
+            &CDS { .. } => true,
+            &StopCodon { .. } => incl_stop,
+            _ => false,
+        });
+        self.spliced_seq(transcript, coords)
+    }
+
+    /// Returns the sequence of the 5'UTR of the transcript.
+    ///
+    /// The sequence is created from the 5'UTR features of the transcript exons. An empty sequence
+    /// is returned for transcripts without any 5'UTR features, which includes transcripts on the
+    /// unknown strand.
+    pub fn utr5_seq(&mut self, transcript: &Transcript) -> ::Result<Vec<u8>> {
+        let coords = feature_coords(transcript.exons(), |kind| kind == &UTR5);
+        self.spliced_seq(transcript, coords)
+    }
+
+    /// Returns the sequence of the 3'UTR of the transcript.
+    ///
+    /// The sequence is created from the 3'UTR features of the transcript exons. An empty sequence
+    /// is returned for transcripts without any 3'UTR features, which includes transcripts on the
+    /// unknown strand.
+    pub fn utr3_seq(&mut self, transcript: &Transcript) -> ::Result<Vec<u8>> {
+        let coords = feature_coords(transcript.exons(), |kind| kind == &UTR3);
+        self.spliced_seq(transcript, coords)
+    }
+
+    /// Returns the concatenated and oriented sequence of the given intervals of a transcript.
+    fn spliced_seq(
+        &mut self,
+        transcript: &Transcript,
+        mut coords: Vec<(u64, u64)>,
+    ) -> ::Result<Vec<u8>> {
+        coords.sort();
+        let mut seq = Vec::new();
+        for (start, end) in coords {
+            seq.extend(self.interval_seq(transcript.seq_name(), start, end)?);
+        }
+        Ok(orient(seq, transcript.strand()))
+    }
+}
+
+impl Reader<fs::File> {
+
+    /// Creates a sequence reader that reads from the given FASTA path.
+    ///
+    /// The FASTA index is expected to be present at the same path, with an added `.fai`
+    /// extension.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> ::Result<Self> {
+        let mut fai_path = OsString::from(path.as_ref());
+        fai_path.push(FAI_EXT);
+        let fasta_file = fs::File::open(&path)
+            .map_err(|e| ::Error::from(SeqError::from(e)))?;
+        let fai_file = fs::File::open(&fai_path)
+            .map_err(|e| ::Error::from(SeqError::from(e)))?;
+        Reader::from_readers(fasta_file, fai_file)
+    }
+}
+
+/// Helper function to collect the coordinates of exon features of the given kinds.
+fn feature_coords<F>(exons: &[Exon], is_kind: F) -> Vec<(u64, u64)>
+    where F: Fn(&ExonFeatureKind) -> bool
+{
+    exons.iter()
+        .flat_map(|exon| exon.features().iter())
+        .filter(|fx| is_kind(fx.kind()))
+        .map(|fx| (fx.start(), fx.end()))
+        .collect()
+}
+
+/// Helper function to reverse-complement sequences on the reverse strand.
+#[inline]
+fn orient(seq: Vec<u8>, strand: &Strand) -> Vec<u8> {
+    match strand {
+        &Strand::Reverse => dna::revcomp(&seq),
+        _ => seq,
+    }
+}
@@ -0,0 +1,343 @@
+/*! Protein translation of transcript coding sequences.
+
+This module provides the translation tables published by the
+[NCBI](https://www.ncbi.nlm.nih.gov/Taxonomy/Utils/wprintgc.cgi) and translation of the coding
+region of transcripts using sequences from an indexed FASTA reference.
+*/
+use std::cmp::min;
+use std::io;
+use std::str::FromStr;
+
+use {ExonFeatureKind, Strand, Transcript};
+use seq::{Reader, SeqError};
+
+
+/// Name for attribute key of the per-transcript translation table override.
+const TRANSL_TABLE_STR: &'static str = "transl_table";
+
+/// Amino acid symbol used for codons containing ambiguous or unknown bases.
+const UNK_AA: u8 = b'X';
+
+/// Amino acid symbol of stop codons.
+const STOP_AA: u8 = b'*';
+
+/// Amino acid symbol of start codons.
+const START_AA: u8 = b'M';
+
+/// Marker of start codons in the starts string of a table.
+const START_MARKER: u8 = b'M';
+
+/// Translation tables, as their identifiers, amino acids, and starts strings.
+///
+/// The strings follow the NCBI notation, in which codons are ordered by their first, second, and
+/// third bases, each in the order of `T`, `C`, `A`, and `G`.
+const TABLES: &'static [(u8, &'static str, &'static [u8], &'static [u8])] = &[
+    (1, "Standard",
+     b"FFLLSSSSYY**CC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+     b"---M------**--*----M---------------M----------------------------"),
+    (2, "Vertebrate Mitochondrial",
+     b"FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIMMTTTTNNKKSS**VVVVAAAADDEEGGGG",
+     b"----------**--------------------MMMM----------**---M------------"),
+    (3, "Yeast Mitochondrial",
+     b"FFLLSSSSYY**CCWWTTTTPPPPHHQQRRRRIIMMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+     b"----------**----------------------MM---------------M------------"),
+    (4, "Mold, Protozoan, and Coelenterate Mitochondrial and Mycoplasma/Spiroplasma",
+     b"FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+     b"--MM------**-------M------------MMMM---------------M------------"),
+    (5, "Invertebrate Mitochondrial",
+     b"FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIMMTTTTNNKKSSSSVVVVAAAADDEEGGGG",
+     b"---M------**--------------------MMMM---------------M------------"),
+    (6, "Ciliate, Dasycladacean and Hexamita Nuclear",
+     b"FFLLSSSSYYQQCC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+     b"--------------*--------------------M----------------------------"),
+    (9, "Echinoderm and Flatworm Mitochondrial",
+     b"FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIIMTTTTNNNKSSSSVVVVAAAADDEEGGGG",
+     b"----------**-----------------------M---------------M------------"),
+    (10, "Euplotid Nuclear",
+     b"FFLLSSSSYY**CCCWLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+     b"----------**-----------------------M----------------------------"),
+    (11, "Bacterial, Archaeal and Plant Plastid",
+     b"FFLLSSSSYY**CC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+     b"---M------**--*----M------------MMMM---------------M------------"),
+    (12, "Alternative Yeast Nuclear",
+     b"FFLLSSSSYY**CC*WLLLSPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+     b"----------**--*----M---------------M----------------------------"),
+    (13, "Ascidian Mitochondrial",
+     b"FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIMMTTTTNNKKSSGGVVVVAAAADDEEGGGG",
+     b"---M------**----------------------MM---------------M------------"),
+    (14, "Alternative Flatworm Mitochondrial",
+     b"FFLLSSSSYYY*CCWWLLLLPPPPHHQQRRRRIIIMTTTTNNNKSSSSVVVVAAAADDEEGGGG",
+     b"-----------*-----------------------M----------------------------"),
+    (16, "Chlorophycean Mitochondrial",
+     b"FFLLSSSSYY*LCC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+     b"----------*---*--------------------M----------------------------"),
+    (21, "Trematode Mitochondrial",
+     b"FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIMMTTTTNNNKSSSSVVVVAAAADDEEGGGG",
+     b"----------**-----------------------M---------------M------------"),
+    (22, "Scenedesmus obliquus Mitochondrial",
+     b"FFLLSS*SYY*LCC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+     b"------*---*---*--------------------M----------------------------"),
+    (23, "Thraustochytrium Mitochondrial",
+     b"FF*LSSSSYY**CC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+     b"--*-------**--*-----------------M--M---------------M------------"),
+    (24, "Rhabdopleuridae Mitochondrial",
+     b"FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSSKVVVVAAAADDEEGGGG",
+     b"---M------**-------M---------------M---------------M------------"),
+    (25, "Candidate Division SR1 and Gracilibacteria",
+     b"FFLLSSSSYY**CCGWLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+     b"---M------**-----------------------M---------------M------------"),
+];
+
+/// Genetic code used for translating codons into amino acids.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GeneticCode {
+    id: u8,
+    name: &'static str,
+    amino_acids: &'static [u8],
+    starts: &'static [u8],
+}
+
+impl GeneticCode {
+
+    /// Returns the genetic code of the given NCBI translation table identifier.
+    ///
+    /// `None` is returned if the identifier does not denote any known table.
+    pub fn from_id(id: u8) -> Option<GeneticCode> {
+        TABLES.iter()
+            .find(|table| table.0 == id)
+            .map(|&(id, name, amino_acids, starts)| GeneticCode {
+                id: id,
+                name: name,
+                amino_acids: amino_acids,
+                starts: starts,
+            })
+    }
+
+    /// Returns the standard genetic code (NCBI translation table 1).
+    pub fn standard() -> GeneticCode {
+        GeneticCode::from_id(1).unwrap()
+    }
+
+    /// Returns the NCBI translation table identifier of the genetic code.
+    pub fn id(&self) -> u8 {
+        self.id
+    }
+
+    /// Returns the name of the genetic code.
+    pub fn name(&self) -> &str {
+        self.name
+    }
+
+    /// Translates the given codon into its amino acid.
+    ///
+    /// Stop codons are translated into `*`, while codons with bases other than `A`, `C`, `G`,
+    /// `T`, or `U` are translated into `X`. Bases may be in upper or lower case.
+    pub fn translate_codon(&self, codon: &[u8]) -> u8 {
+        codon_index(codon)
+            .map(|idx| self.amino_acids[idx])
+            .unwrap_or(UNK_AA)
+    }
+
+    /// Returns whether the given codon is a start codon.
+    pub fn is_start_codon(&self, codon: &[u8]) -> bool {
+        codon_index(codon)
+            .map(|idx| self.starts[idx] == START_MARKER)
+            .unwrap_or(false)
+    }
+
+    /// Returns whether the given codon is a stop codon.
+    pub fn is_stop_codon(&self, codon: &[u8]) -> bool {
+        self.translate_codon(codon) == STOP_AA
+    }
+}
+
+impl Default for GeneticCode {
+    fn default() -> GeneticCode {
+        GeneticCode::standard()
+    }
+}
+
+/// Issues found when translating a coding sequence.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TranslationIssue {
+    /// The first codon is not a start codon.
+    MissingStartCodon,
+    /// The last codon is not a stop codon.
+    MissingStopCodon,
+    /// A stop codon is present before the last codon, at the given zero-based codon position.
+    InternalStopCodon(usize),
+    /// The length of the translated coding sequence, after any frame offset is removed, is not a
+    /// multiple of three.
+    ///
+    /// The contained value is the length of the coding sequence. The trailing incomplete codon
+    /// is not translated.
+    InvalidLength(u64),
+}
+
+/// Protein translation of a transcript coding sequence.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Translation {
+    protein: Vec<u8>,
+    genetic_code: GeneticCode,
+    frame_offset: u8,
+    issues: Vec<TranslationIssue>,
+}
+
+impl Translation {
+
+    /// Returns the protein sequence.
+    ///
+    /// The terminal stop codon, if present, is not included in the sequence. Internal stop
+    /// codons are denoted with `*`.
+    pub fn protein(&self) -> &[u8] {
+        self.protein.as_slice()
+    }
+
+    /// Consumes the translation and returns the protein sequence.
+    pub fn take_protein(self) -> Vec<u8> {
+        self.protein
+    }
+
+    /// Returns the genetic code used for the translation.
+    pub fn genetic_code(&self) -> &GeneticCode {
+        &self.genetic_code
+    }
+
+    /// Returns the number of bases skipped from the transcript-wise 5' end of the coding
+    /// sequence before translation.
+    pub fn frame_offset(&self) -> u8 {
+        self.frame_offset
+    }
+
+    /// Returns the issues found during the translation.
+    pub fn issues(&self) -> &[TranslationIssue] {
+        self.issues.as_slice()
+    }
+
+    /// Returns whether any issues were found during the translation.
+    pub fn has_issues(&self) -> bool {
+        !self.issues.is_empty()
+    }
+
+    /// Translates the given coding sequence, including its stop codon.
+    ///
+    /// The given number of bases are skipped from the beginning of the sequence before
+    /// translation.
+    pub fn from_coding_seq(coding_seq: &[u8], genetic_code: GeneticCode, frame_offset: u8)
+        -> Translation
+    {
+        let seq = &coding_seq[min(frame_offset as usize, coding_seq.len())..];
+        let codons = seq.chunks(3).filter(|codon| codon.len() == 3).collect::<Vec<&[u8]>>();
+        let has_start = codons.first()
+            .map(|codon| genetic_code.is_start_codon(codon))
+            .unwrap_or(false);
+        let has_stop = codons.last()
+            .map(|codon| genetic_code.is_stop_codon(codon))
+            .unwrap_or(false);
+
+        let mut issues = vec![];
+        if !has_start {
+            issues.push(TranslationIssue::MissingStartCodon);
+        }
+        let mut protein = Vec::with_capacity(codons.len());
+        for (idx, codon) in codons.iter().enumerate() {
+            if idx == 0 && has_start {
+                protein.push(START_AA);
+                continue;
+            }
+            let aa = genetic_code.translate_codon(codon);
+            if aa == STOP_AA {
+                if idx == codons.len() - 1 {
+                    continue;
+                }
+                issues.push(TranslationIssue::InternalStopCodon(idx));
+            }
+            protein.push(aa);
+        }
+        if !has_stop {
+            issues.push(TranslationIssue::MissingStopCodon);
+        }
+        if seq.len() % 3 != 0 {
+            issues.push(TranslationIssue::InvalidLength(seq.len() as u64));
+        }
+
+        Translation {
+            protein: protein,
+            genetic_code: genetic_code,
+            frame_offset: frame_offset,
+            issues: issues,
+        }
+    }
+}
+
+impl<R: io::Read + io::Seek> Reader<R> {
+
+    /// Translates the coding region of the given transcript.
+    ///
+    /// The genetic code is taken from the `transl_table` attribute of the transcript if present,
+    /// or from the `genetic_code` argument otherwise. If `use_frame` is true, the frame of the
+    /// transcript-wise 5'-most CDS feature is used to skip the bases of any incomplete codon at
+    /// the start of the coding region.
+    ///
+    /// `None` is returned for non-coding transcripts. An error is returned if the `transl_table`
+    /// attribute does not denote any known table.
+    pub fn translate(
+        &mut self,
+        transcript: &Transcript,
+        genetic_code: GeneticCode,
+        use_frame: bool,
+    ) -> ::Result<Option<Translation>> {
+        let genetic_code = match transcript.attributes().get(TRANSL_TABLE_STR) {
+            Some(raw) => u8::from_str(raw).ok()
+                .and_then(GeneticCode::from_id)
+                .ok_or_else(|| SeqError::InvalidTranslationTable(raw.clone()))?,
+            None => genetic_code,
+        };
+        let coding_seq = self.coding_seq(transcript, true)?;
+        if coding_seq.is_empty() {
+            return Ok(None);
+        }
+        let frame_offset =
+            if use_frame {
+                first_cds_frame(transcript).unwrap_or(0)
+            } else {
+                0
+            };
+        Ok(Some(Translation::from_coding_seq(&coding_seq, genetic_code, frame_offset)))
+    }
+}
+
+/// Helper function to get the frame of the transcript-wise 5'-most CDS feature.
+fn first_cds_frame(transcript: &Transcript) -> Option<u8> {
+    let cds_fxs = transcript.exons().iter()
+        .flat_map(|exon| exon.features().iter())
+        .filter_map(|fx| match fx.kind() {
+            &ExonFeatureKind::CDS { frame } => Some((fx.start(), fx.end(), frame)),
+            _ => None,
+        });
+    let first_fx = match transcript.strand() {
+        &Strand::Reverse => cds_fxs.max_by_key(|&(_, end, _)| end),
+        _ => cds_fxs.min_by_key(|&(start, _, _)| start),
+    };
+    first_fx.and_then(|(_, _, frame)| frame)
+}
+
+/// Helper function to compute the index of a codon within the translation table strings.
+#[inline]
+fn codon_index(codon: &[u8]) -> Option<usize> {
+    if codon.len() != 3 {
+        return None;
+    }
+    let mut idx = 0;
+    for base in codon {
+        let base_idx = match *base {
+            b'T' | b't' | b'U' | b'u' => 0,
+            b'C' | b'c' => 1,
+            b'A' | b'a' => 2,
+            b'G' | b'g' => 3,
+            _ => return None,
+        };
+        idx = idx * 4 + base_idx;
+    }
+    Some(idx)
+}
@@ -0,0 +1,129 @@
+extern crate gte;
+#[macro_use]
+extern crate matches;
+
+use gte::{open_annotation, AnnotationFormat, AnnotationReader, AnnotationWriter, BedReader,
+          GffReader, GffType, RefFlatLayout, RefFlatReader, RefFlatWriter};
+
+
+static SINGLE_ROW_NO_CDS: &'static str = include_str!("data/single_row_no_cds.refFlat");
+static SINGLE_GENE_GTF: &'static str = include_str!("data/single_gene.gtf");
+static TWO_GENES_REFSEQ_GFF3: &'static str = include_str!("data/two_genes_refseq.gff3");
+static TWO_TRANSCRIPTS_BED: &'static str = include_str!("data/two_transcripts.bed");
+static TWO_ROWS_WITH_BIN_GENEPREDEXT: &'static str =
+    include_str!("data/two_rows_with_bin.genePredExt");
+
+fn data_path(name: &str) -> String {
+    format!("{}/tests/data/{}", env!("CARGO_MANIFEST_DIR"), name)
+}
+
+fn read_tids(reader: &mut AnnotationReader) -> Vec<String> {
+    reader.transcripts().expect("transcripts")
+        .map(|result| result.expect("a transcript").id().unwrap().to_owned())
+        .collect()
+}
+
+fn count_genes(reader: &mut AnnotationReader) -> usize {
+    reader.genes().expect("genes")
+        .map(|result| result.expect("a gene"))
+        .count()
+}
+
+#[test]
+fn annotation_reader_formats() {
+    let mut refflat = RefFlatReader::from_reader(SINGLE_ROW_NO_CDS.as_bytes());
+    assert_eq!(read_tids(&mut refflat), vec!["NR_046018"]);
+
+    let mut gff = GffReader::from_reader(TWO_GENES_REFSEQ_GFF3.as_bytes(), GffType::GFF3);
+    assert_eq!(read_tids(&mut gff), vec!["rna-1", "rna-2", "rna-3"]);
+
+    let mut bed = BedReader::from_reader(TWO_TRANSCRIPTS_BED.as_bytes());
+    assert_eq!(read_tids(&mut bed), vec!["rna-1", "rna-3"]);
+}
+
+#[test]
+fn annotation_reader_genes() {
+    let mut gff = GffReader::from_reader(TWO_GENES_REFSEQ_GFF3.as_bytes(), GffType::GFF3);
+    assert_eq!(count_genes(&mut gff), 2);
+
+    let mut gtf = GffReader::from_reader(SINGLE_GENE_GTF.as_bytes(), GffType::GTF2);
+    assert_eq!(count_genes(&mut gtf), 1);
+
+    let mut refflat = RefFlatReader::from_reader(SINGLE_ROW_NO_CDS.as_bytes());
+    assert_eq!(count_genes(&mut refflat), 1);
+}
+
+#[test]
+fn annotation_reader_bed_genes() {
+    let mut bed = BedReader::from_reader(TWO_TRANSCRIPTS_BED.as_bytes());
+    let genes = AnnotationReader::genes(&mut bed).expect("genes")
+        .map(|result| result.expect("a gene"))
+        .collect::<Vec<_>>();
+    assert_eq!(genes.len(), 2);
+    assert_eq!(genes[0].id(), Some("rna-1"));
+    assert_eq!(genes[0].start(), 1000);
+    assert_eq!(genes[0].end(), 5000);
+    assert_eq!(genes[0].transcripts().len(), 1);
+    assert_eq!(genes[1].id(), Some("rna-3"));
+}
+
+#[test]
+fn annotation_writer_roundtrip() {
+    let mut reader = RefFlatReader::from_reader(SINGLE_ROW_NO_CDS.as_bytes());
+    let mut writer = RefFlatWriter::from_memory();
+    {
+        let reader: &mut AnnotationReader = &mut reader;
+        let writer: &mut AnnotationWriter = &mut writer;
+        for result in reader.transcripts().expect("transcripts") {
+            writer.write_transcript(&result.expect("a transcript")).expect("a successful write");
+        }
+    }
+    assert_eq!(writer.as_string(), SINGLE_ROW_NO_CDS);
+}
+
+#[test]
+fn annotation_format_from_extension() {
+    assert!(matches!(AnnotationFormat::from_extension("a.gtf"),
+                     Some(AnnotationFormat::Gff(GffType::GTF2))));
+    assert!(matches!(AnnotationFormat::from_extension("a.GFF3.gz"),
+                     Some(AnnotationFormat::Gff(GffType::GFF3))));
+    assert!(matches!(AnnotationFormat::from_extension("a.bed.bgz"),
+                     Some(AnnotationFormat::Bed)));
+    assert!(matches!(AnnotationFormat::from_extension("a.refFlat"),
+                     Some(AnnotationFormat::RefFlat { layout: RefFlatLayout::RefFlat,
+                                                      has_bin: false })));
+    assert!(AnnotationFormat::from_extension("a.txt").is_none());
+    assert!(AnnotationFormat::from_extension("a.gz").is_none());
+}
+
+#[test]
+fn annotation_format_sniff() {
+    assert!(matches!(AnnotationFormat::sniff(SINGLE_GENE_GTF),
+                     Some(AnnotationFormat::Gff(GffType::GTF2))));
+    assert!(matches!(AnnotationFormat::sniff(TWO_GENES_REFSEQ_GFF3),
+                     Some(AnnotationFormat::Gff(GffType::GFF3))));
+    assert!(matches!(AnnotationFormat::sniff(TWO_TRANSCRIPTS_BED),
+                     Some(AnnotationFormat::Bed)));
+    assert!(matches!(AnnotationFormat::sniff(SINGLE_ROW_NO_CDS),
+                     Some(AnnotationFormat::RefFlat { layout: RefFlatLayout::RefFlat,
+                                                      has_bin: false })));
+    assert!(matches!(AnnotationFormat::sniff(TWO_ROWS_WITH_BIN_GENEPREDEXT),
+                     Some(AnnotationFormat::RefFlat { layout: RefFlatLayout::GenePredExt,
+                                                      has_bin: true })));
+    assert!(AnnotationFormat::sniff("# only a comment\n").is_none());
+    assert!(AnnotationFormat::sniff("not\tan\tannotation\n").is_none());
+}
+
+#[test]
+fn open_annotation_sniffed() {
+    let mut reader = open_annotation(data_path("two_rows_with_bin.genePredExt"))
+        .expect("an annotation reader");
+    assert_eq!(read_tids(&mut *reader), vec!["NM_001297605", "NM_001164824"]);
+}
+
+#[test]
+fn open_annotation_extension() {
+    let mut reader = open_annotation(data_path("single_gene.gtf"))
+        .expect("an annotation reader");
+    assert_eq!(count_genes(&mut *reader), 1);
+}
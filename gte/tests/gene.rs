@@ -6,7 +6,7 @@ extern crate multimap;
 use linked_hash_map::LinkedHashMap;
 use multimap::MultiMap;
 
-use gte::{Strand, GBuilder};
+use gte::{ExonBin, Gene, Strand, GBuilder};
 use Strand::*;
 
 #[test]
@@ -38,3 +38,69 @@ fn gbuilder_basic() {
     assert_eq!(gx.attributes().len(), 2);
     assert_eq!(gx.transcripts().len(), 2);
 }
+
+fn bin_values(bins: &[ExonBin]) -> Vec<(u64, u64, Vec<&str>)> {
+    bins.iter()
+        .map(|bin| (bin.start(), bin.end(),
+                    bin.transcript_ids().iter().map(|tid| tid.as_str()).collect()))
+        .collect()
+}
+
+fn two_isoform_gene(id: &str, seq_name: &str) -> Gene {
+    let mut coords = LinkedHashMap::new();
+    coords.insert("trx01".to_owned(),
+                  ((100, 1000), vec![(100, 300), (400, 500), (700, 1000)], None));
+    coords.insert("trx02".to_owned(),
+                  ((150, 1000), vec![(150, 300), (400, 550), (700, 1000)], None));
+    GBuilder::new(seq_name, 100, 1000)
+        .strand(Forward)
+        .id(id)
+        .transcript_coords(coords)
+        .build()
+        .expect("a gene")
+}
+
+#[test]
+fn gene_collapsed_exons() {
+    let gx = two_isoform_gene("gene-1", "chrT");
+    assert_eq!(bin_values(&gx.collapsed_exons()), vec![
+        (100, 300, vec!["trx01", "trx02"]),
+        (400, 550, vec!["trx01", "trx02"]),
+        (700, 1000, vec!["trx01", "trx02"]),
+    ]);
+    assert_eq!(gx.effective_len(), 650);
+}
+
+#[test]
+fn gene_counting_bins() {
+    let gx = two_isoform_gene("gene-1", "chrT");
+    assert_eq!(bin_values(&gx.counting_bins()), vec![
+        (100, 150, vec!["trx01"]),
+        (150, 300, vec!["trx01", "trx02"]),
+        (400, 500, vec!["trx01", "trx02"]),
+        (500, 550, vec!["trx02"]),
+        (700, 1000, vec!["trx01", "trx02"]),
+    ]);
+}
+
+#[test]
+fn exon_bins_flag_shared() {
+    let mut bins = two_isoform_gene("gene-1", "chrT").collapsed_exons();
+    let other = GBuilder::new("chrT", 520, 600)
+        .strand(Reverse)
+        .id("gene-2")
+        .transcript_coords(vec![("trx03".to_owned(), ((520, 600), vec![(520, 600)], None))]
+                               .into_iter().collect())
+        .build()
+        .expect("a gene");
+    bins.extend(other.collapsed_exons());
+    bins.extend(two_isoform_gene("gene-3", "chrU").collapsed_exons());
+    ExonBin::flag_shared(&mut bins);
+    let shared = bins.iter()
+        .map(|bin| (bin.seq_name(), bin.start(), bin.is_shared()))
+        .collect::<Vec<(&str, u64, bool)>>();
+    assert_eq!(shared, vec![
+        ("chrT", 100, false), ("chrT", 400, true), ("chrT", 520, true), ("chrT", 700, false),
+        ("chrU", 100, false), ("chrU", 400, false), ("chrU", 700, false),
+    ]);
+}
@@ -0,0 +1,60 @@
+extern crate gte;
+
+use gte::{AnnotationIndex, GffReader, GffType, Strand, TBuilder, Transcript};
+use Strand::*;
+
+
+static TWO_GENES_REFSEQ_GFF3: &'static str = include_str!("data/two_genes_refseq.gff3");
+
+
+fn transcript(id: &str, start: u64, end: u64, strand: Strand) -> Transcript {
+    TBuilder::new("chrT", start, end)
+        .strand(strand)
+        .id(id)
+        .coords(vec![(start, end)], None)
+        .build()
+        .expect("a transcript")
+}
+
+fn ids(transcripts: Vec<&Transcript>) -> Vec<&str> {
+    transcripts.into_iter().filter_map(|trx| trx.id()).collect()
+}
+
+#[test]
+fn annotation_index_transcripts() {
+    let index = AnnotationIndex::new(vec![
+        transcript("trx03", 700, 900, Reverse),
+        transcript("trx01", 100, 1000, Forward),
+        transcript("trx02", 200, 300, Forward),
+        transcript("trx04", 950, 1200, Forward),
+    ]);
+    assert_eq!(index.len(), 4);
+
+    assert_eq!(ids(index.overlapping("chrT", 250, 800, None)), vec!["trx01", "trx02", "trx03"]);
+    assert_eq!(ids(index.overlapping("chrT", 300, 700, None)), vec!["trx01"]);
+    assert_eq!(ids(index.overlapping("chrT", 250, 800, Some(&Reverse))), vec!["trx03"]);
+    assert_eq!(ids(index.contained("chrT", 150, 950, None)), vec!["trx02", "trx03"]);
+    assert_eq!(ids(index.containing("chrT", 960, 1000, None)), vec!["trx01", "trx04"]);
+    assert_eq!(ids(index.at("chrT", 999, Some(&Forward))), vec!["trx01", "trx04"]);
+    assert!(index.at("chrT", 1200, None).is_empty());
+    assert!(index.overlapping("chrU", 0, 2000, None).is_empty());
+}
+
+#[test]
+fn annotation_index_genes() {
+    let mut reader = GffReader::from_reader(TWO_GENES_REFSEQ_GFF3.as_bytes(), GffType::GFF3);
+    let index = AnnotationIndex::from_results(reader.genes().expect("genes"))
+        .expect("an annotation index");
+    assert_eq!(index.len(), 2);
+
+    let genes = index.overlapping("chr1", 4200, 6100, None);
+    assert_eq!(genes.iter().filter_map(|gx| gx.id()).collect::<Vec<&str>>(),
+               vec!["gene-ABC", "gene-XYZ"]);
+
+    let transcripts = index.overlapping_transcripts("chr1", 4200, 6100, Some(&Forward));
+    assert_eq!(ids(transcripts), vec!["rna-1"]);
+
+    let exons = index.overlapping_exons("chr1", 1400, 2100, None);
+    assert_eq!(exons.iter().map(|ex| (ex.start(), ex.end())).collect::<Vec<(u64, u64)>>(),
+               vec![(1000, 1500), (1000, 1500), (2000, 2500)]);
+}
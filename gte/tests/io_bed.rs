@@ -0,0 +1,150 @@
+extern crate gte;
+
+use gte::{BedReader, BedWriter, BedError, Error as GteError, EBuilder, Strand, TBuilder};
+use Strand::*;
+
+
+static TWO_TRANSCRIPTS_BED: &'static str = include_str!("data/two_transcripts.bed");
+
+
+#[test]
+fn bed_reader_records() {
+    let mut reader = BedReader::from_reader(TWO_TRANSCRIPTS_BED.as_bytes());
+    let mut records = reader.records_stream();
+
+    let rec1 = records.next().expect("a bed record result").expect("a bed record");
+    assert_eq!(rec1.seq_name(), "chr1");
+    assert_eq!(rec1.name(), Some("rna-1"));
+    assert_eq!(rec1.num_blocks(), 3);
+    assert_eq!(rec1.block_coords(), vec![(1000, 1500), (2000, 2500), (4500, 5000)]);
+
+    let rec2 = records.next().expect("a bed record result").expect("a bed record");
+    assert_eq!(rec2.name(), Some("rna-3"));
+    assert_eq!(rec2.strand(), '-');
+
+    assert!(records.next().is_none());
+}
+
+#[test]
+fn bed_reader_transcripts() {
+    let mut reader = BedReader::from_reader(TWO_TRANSCRIPTS_BED.as_bytes());
+    let mut transcripts = reader.transcripts_stream();
+
+    let trx1 = transcripts.next().expect("a transcript result").expect("a transcript");
+    assert_eq!(trx1.id(), Some("rna-1"));
+    assert_eq!(trx1.start(), 1000);
+    assert_eq!(trx1.end(), 5000);
+    assert_eq!(trx1.strand(), &Forward);
+    assert_eq!(trx1.exons().len(), 3);
+    assert_eq!(trx1.coding_coord(true), Some((1100, 4602)));
+
+    let trx2 = transcripts.next().expect("a transcript result").expect("a transcript");
+    assert_eq!(trx2.strand(), &Reverse);
+    assert_eq!(trx2.exons().len(), 1);
+    assert!(!trx2.is_coding());
+
+    assert!(transcripts.next().is_none());
+}
+
+#[test]
+fn bed_writer_transcripts() {
+    let mut reader = BedReader::from_reader(TWO_TRANSCRIPTS_BED.as_bytes());
+    let mut writer = BedWriter::from_memory();
+    for result in reader.transcripts_stream() {
+        writer.write_transcript(&result.expect("a transcript")).expect("a successful write");
+    }
+    let expected: Vec<&str> = TWO_TRANSCRIPTS_BED.lines().skip(1).collect();
+    assert_eq!(writer.as_string(), expected.join("\n") + "\n");
+}
+
+#[test]
+fn bed6_reader_writer() {
+    let bed6 = "chr1\t100\t200\tfeat1\t5\t-\n";
+    let mut reader = BedReader::from_reader(bed6.as_bytes());
+    let mut records = reader.records_stream();
+    let rec = records.next().expect("a bed record result").expect("a bed record");
+    assert_eq!(rec.num_blocks(), 0);
+    assert_eq!(rec.score(), Some("5"));
+
+    let mut writer = BedWriter::from_memory();
+    writer.write_record(&rec).expect("a successful write");
+    assert_eq!(writer.as_string(), bed6);
+
+    let trx = rec.into_transcript().expect("a transcript");
+    assert_eq!(trx.exons().len(), 1);
+    assert_eq!(trx.attributes().get("score"), Some(&"5".to_owned()));
+}
+
+#[test]
+fn bed6_writer_exon() {
+    let exon = EBuilder::new("chr2", 10, 20)
+        .strand(Forward)
+        .id("exon01")
+        .build()
+        .expect("an exon");
+    let mut writer = BedWriter::from_memory();
+    writer.write_exon(&exon).expect("a successful write");
+    assert_eq!(writer.as_string(), "chr2\t10\t20\texon01\t0\t+\n");
+}
+
+#[test]
+fn bed6_writer_introns() {
+    let trx = TBuilder::new("chr2", 10, 100)
+        .strand(Reverse)
+        .id("trx01")
+        .coords(vec![(10, 20), (40, 50), (90, 100)], None)
+        .build()
+        .expect("a transcript");
+    let mut writer = BedWriter::from_memory();
+    writer.write_introns(&trx).expect("a successful write");
+    assert_eq!(writer.as_string(),
+               "chr2\t20\t40\ttrx01_intron2\t0\t-\nchr2\t50\t90\ttrx01_intron1\t0\t-\n");
+}
+
+#[test]
+fn bed_reader_block_count_mismatch() {
+    let bed12 = "chr1\t0\t100\tx\t0\t+\t0\t0\t0\t2\t100,\t0,\n";
+    let mut reader = BedReader::from_reader(bed12.as_bytes());
+    let result = reader.records_stream().next().expect("a bed record result");
+    let err = result.expect_err("a bed record error");
+    assert_eq!(err.line(), Some(1));
+    assert_eq!(err.field(), Some("blockCount"));
+    match err.inner() {
+        &GteError::Bed(BedError::BlockCountMismatch(Some(ref name))) => assert_eq!(name, "x"),
+        _ => panic!("expected a block count mismatch error"),
+    }
+}
+
+#[test]
+fn bed_reader_invalid_coord_line() {
+    let bed6 = "track name=x\nchr1\t0\t100\ta\t0\t+\n\nchr1\tten\t100\tb\t0\t+\n";
+    let mut reader = BedReader::from_reader(bed6.as_bytes());
+    let mut records = reader.records_stream();
+    assert!(records.next().expect("a bed record result").is_ok());
+    let err = records.next().expect("a bed record result").expect_err("a bed record error");
+    assert_eq!(err.line(), Some(4));
+    assert_eq!(err.field(), Some("chromStart"));
+    assert_eq!(err.raw_line(), Some("chr1\tten\t100\tb\t0\t+"));
+    assert!(records.next().is_none());
+}
+
+#[test]
+fn bed_reader_extra_columns() {
+    // BED9 columns after the sixth are ignored, while BED13 rows are rejected.
+    let bed = "chr1\t100\t200\tfeat1\t5\t-\t120\t180\t255,0,0\n\
+               chr1\t0\t100\tx\t0\t+\t0\t0\t0\t1\t100,\t0,\textra\n";
+    let mut reader = BedReader::from_reader(bed.as_bytes());
+    let mut records = reader.records_stream();
+
+    let rec = records.next().expect("a bed record result").expect("a bed record");
+    assert_eq!(rec.name(), Some("feat1"));
+    assert_eq!(rec.strand(), '-');
+    assert_eq!(rec.num_blocks(), 0);
+    assert!(!rec.into_transcript().expect("a transcript").is_coding());
+
+    let err = records.next().expect("a bed record result").expect_err("a bed record error");
+    match err.inner() {
+        &GteError::Bed(BedError::InvalidColumnCount(13)) => {},
+        _ => panic!("expected an invalid column count error"),
+    }
+}
@@ -1,12 +1,13 @@
 extern crate bio;
 extern crate gte;
 
-use gte::{GffType, GffReader,
-          ExonFeatureKind as EFK, Strand};
+use gte::{GffType, GffReader, GffWriter, GffError, Error as GteError, ErrorPolicy, TBuilder,
+          Transcript, ExonFeatureKind as EFK, Strand};
 use Strand::*;
 
 
 static SINGLE_GENE_GTF: &'static str = include_str!("data/single_gene.gtf");
+static TWO_GENES_REFSEQ_GFF3: &'static str = include_str!("data/two_genes_refseq.gff3");
 
 
 #[test]
@@ -49,3 +50,626 @@ fn gtf_reader_multiple_transcripts() {
 
     assert!(transcripts.next().is_none());
 }
+
+fn make_coding_trx() -> Transcript {
+    TBuilder::new("chr1", 100, 500)
+        .strand(Forward)
+        .id("trx01")
+        .gene_id("gene01")
+        .coords(vec![(100, 200), (300, 500)], Some((150, 400)))
+        .build()
+        .expect("a transcript")
+}
+
+#[test]
+fn gtf_writer_transcript() {
+    let mut writer = GffWriter::from_memory(GffType::GTF2);
+    writer.write_transcript(&make_coding_trx()).expect("a successful write");
+    let attribs = "gene_id \"gene01\"; transcript_id \"trx01\";";
+    let expected = [
+        "##gff-version 2".to_owned(),
+        format!("chr1\t.\ttranscript\t101\t500\t.\t+\t.\t{}", attribs),
+        format!("chr1\t.\texon\t101\t200\t.\t+\t.\t{}", attribs),
+        format!("chr1\t.\tUTR5\t101\t150\t.\t+\t.\t{}", attribs),
+        format!("chr1\t.\tstart_codon\t151\t153\t.\t+\t0\t{}", attribs),
+        format!("chr1\t.\tCDS\t151\t200\t.\t+\t0\t{}", attribs),
+        format!("chr1\t.\texon\t301\t500\t.\t+\t.\t{}", attribs),
+        format!("chr1\t.\tCDS\t301\t400\t.\t+\t1\t{}", attribs),
+        format!("chr1\t.\tstop_codon\t401\t403\t.\t+\t0\t{}", attribs),
+        format!("chr1\t.\tUTR3\t404\t500\t.\t+\t.\t{}", attribs),
+    ];
+    assert_eq!(writer.as_string(), expected.join("\n") + "\n");
+}
+
+#[test]
+fn gff3_writer_header() {
+    let mut writer = GffWriter::from_memory(GffType::GFF3);
+    writer.write_transcript(&make_coding_trx()).expect("a successful write");
+    assert!(writer.as_string().starts_with("##gff-version 3\n"));
+    assert_eq!(writer.as_string().matches("##gff-version").count(), 1);
+}
+
+#[test]
+fn gff3_writer_transcript() {
+    let mut writer = GffWriter::from_memory(GffType::GFF3);
+    writer.write_transcript(&make_coding_trx()).expect("a successful write");
+    let child = "Parent=trx01";
+    let expected = [
+        "##gff-version 3".to_owned(),
+        "chr1\t.\tmRNA\t101\t500\t.\t+\t.\tID=trx01;Parent=gene01".to_owned(),
+        format!("chr1\t.\texon\t101\t200\t.\t+\t.\t{}", child),
+        format!("chr1\t.\tfive_prime_UTR\t101\t150\t.\t+\t.\t{}", child),
+        format!("chr1\t.\tstart_codon\t151\t153\t.\t+\t0\t{}", child),
+        format!("chr1\t.\tCDS\t151\t200\t.\t+\t0\t{}", child),
+        format!("chr1\t.\texon\t301\t500\t.\t+\t.\t{}", child),
+        format!("chr1\t.\tCDS\t301\t400\t.\t+\t1\t{}", child),
+        format!("chr1\t.\tstop_codon\t401\t403\t.\t+\t0\t{}", child),
+        format!("chr1\t.\tthree_prime_UTR\t404\t500\t.\t+\t.\t{}", child),
+    ];
+    assert_eq!(writer.as_string(), expected.join("\n") + "\n");
+}
+
+#[test]
+fn gff3_writer_round_trip() {
+    let mut reader = GffReader::from_reader(TWO_GENES_REFSEQ_GFF3.as_bytes(), GffType::GFF3);
+    let genes = reader.genes().expect("genes")
+        .map(|result| result.expect("a gene"))
+        .collect::<Vec<_>>();
+
+    let mut writer = GffWriter::from_memory(GffType::GFF3);
+    for gene in genes.iter() {
+        writer.write_gene(gene).expect("a successful write");
+    }
+    let written = writer.as_string();
+    assert!(written.contains("\tgene\t1001\t5000\t.\t+\t.\tID=gene-ABC;"));
+    assert!(written.contains("\tmRNA\t1001\t5000\t.\t+\t.\tID=rna-1;Parent=gene-ABC;"));
+    assert!(written.contains("\tncRNA\t6001\t6500\t.\t-\t.\tID=rna-3;Parent=gene-XYZ;"));
+
+    let mut reader = GffReader::from_reader(written.as_bytes(), GffType::GFF3);
+    let read_genes = reader.genes().expect("genes")
+        .map(|result| result.expect("a gene"))
+        .collect::<Vec<_>>();
+    assert_eq!(read_genes.len(), genes.len());
+    for (gene, read_gene) in genes.iter().zip(read_genes.iter()) {
+        assert_eq!(read_gene.id(), gene.id());
+        assert_eq!((read_gene.start(), read_gene.end()), (gene.start(), gene.end()));
+        assert_eq!(read_gene.attributes().get("Name"), gene.attributes().get("Name"));
+        let tids: Vec<&String> = read_gene.transcripts().keys().collect();
+        assert_eq!(tids, gene.transcripts().keys().collect::<Vec<_>>());
+        for (trx, read_trx) in gene.transcripts().values().zip(read_gene.transcripts().values()) {
+            assert_eq!(read_trx.gene_id(), trx.gene_id());
+            assert_eq!(read_trx.strand(), trx.strand());
+            assert_eq!(read_trx.coding_coord(true), trx.coding_coord(true));
+            let coords = |t: &Transcript| t.exons().iter()
+                .map(|exn| (exn.start(), exn.end()))
+                .collect::<Vec<_>>();
+            assert_eq!(coords(read_trx), coords(trx));
+        }
+    }
+}
+
+#[test]
+fn gtf_writer_missing_gene_id() {
+    let trx = TBuilder::new("chr1", 100, 500)
+        .strand(Forward)
+        .id("trx01")
+        .coords(vec![(100, 500)], None)
+        .build()
+        .expect("a transcript");
+    let mut writer = GffWriter::from_memory(GffType::GTF2);
+    assert!(writer.write_transcript(&trx).is_err());
+}
+
+#[test]
+fn gtf_reader_genes_with_gene_record() {
+    let mut reader = GffReader::from_reader(SINGLE_GENE_GTF.as_bytes(), GffType::GTF2);
+    let mut genes = reader.genes().expect("genes");
+
+    let gx = genes.next().expect("a gene result").expect("a gene");
+    assert_eq!(gx.id(), Some("ENSG00000128645.13"));
+    assert_eq!(gx.seq_name(), "chr2");
+    assert_eq!(gx.start(), 176188578);
+    assert_eq!(gx.end(), 176190907);
+    assert_eq!(gx.strand(), &Forward);
+    assert_eq!(gx.attributes().get("gene_name"), Some(&"HOXD1".to_owned()));
+    assert_eq!(gx.attributes().get("gene_type"), Some(&"protein_coding".to_owned()));
+    assert_eq!(gx.attributes().get("level"), Some(&"2".to_owned()));
+    assert!(gx.attributes().get("gene_id").is_none());
+    assert_eq!(gx.transcripts().len(), 2);
+    let tids: Vec<&str> = gx.transcripts().keys().map(|k| k.as_str()).collect();
+    assert_eq!(tids, vec!["ENST00000331462.5", "ENST00000610524.1"]);
+
+    assert!(genes.next().is_none());
+}
+
+#[test]
+fn gtf_reader_genes_with_gene_record_suffix_attribute() {
+    let mut lines = SINGLE_GENE_GTF.lines();
+    let gene_line = lines.next().expect("a gene line")
+        .replacen("gene_id", "havana_gene_id \"OTTHUMG00000132512\"; gene_id", 1);
+    let gtf = Some(gene_line).into_iter()
+        .chain(lines.map(|line| line.to_owned()))
+        .collect::<Vec<String>>()
+        .join("\n");
+    let mut reader = GffReader::from_reader(gtf.as_bytes(), GffType::GTF2);
+    let mut genes = reader.genes().expect("genes");
+
+    let gx = genes.next().expect("a gene result").expect("a gene");
+    assert_eq!(gx.id(), Some("ENSG00000128645.13"));
+    assert_eq!(gx.attributes().get("gene_name"), Some(&"HOXD1".to_owned()));
+    assert_eq!(gx.attributes().get("havana_gene_id"), Some(&"OTTHUMG00000132512".to_owned()));
+    assert!(genes.next().is_none());
+}
+
+#[test]
+fn gtf_reader_genes_transcript_errors_and_strands() {
+    let row = |feature: &str, start: u64, end: u64, strand: char, attribs: &str| {
+        format!("chr1\tsrc\t{}\t{}\t{}\t.\t{}\t.\tgene_id \"g1\"; {}",
+                feature, start, end, strand, attribs)
+    };
+    let rows = [
+        row("gene", 101, 1000, '+', "gene_name \"G1\";"),
+        row("transcript", 101, 500, '+', "transcript_id \"t1\";"),
+        row("exon", 101, 500, '+', "transcript_id \"t1\";"),
+        row("exon", 601, 700, '+', "transcript_id \"t2\";"),
+        row("transcript", 801, 1000, '-', "transcript_id \"t3\";"),
+        row("exon", 801, 1000, '-', "transcript_id \"t3\";"),
+    ];
+    let gtf = rows.join("\n") + "\n";
+    let mut reader = GffReader::from_reader(gtf.as_bytes(), GffType::GTF2);
+    reader.error_policy(ErrorPolicy::Collect);
+    let genes = reader.genes().expect("genes")
+        .map(|result| result.expect("a gene"))
+        .collect::<Vec<_>>();
+
+    // Both strand groups of the gene keep the gene record, and the transcript that can not be
+    // created does not take its gene down.
+    assert_eq!(genes.len(), 2);
+    for (gx, (strand, tid)) in genes.iter().zip(vec![(Forward, "t1"), (Reverse, "t3")]) {
+        assert_eq!(gx.id(), Some("g1"));
+        assert_eq!(gx.strand(), &strand);
+        assert_eq!((gx.start(), gx.end()), (100, 1000));
+        assert_eq!(gx.attributes().get("gene_name"), Some(&"G1".to_owned()));
+        assert_eq!(gx.transcripts().keys().collect::<Vec<_>>(), vec![tid]);
+    }
+
+    let rejected = reader.error_report().rejected();
+    assert_eq!(rejected.len(), 1);
+    assert_eq!(rejected[0].id(), Some("t2"));
+    assert_eq!(rejected[0].error().line(), Some(4));
+}
+
+#[test]
+fn gtf_reader_genes_without_gene_record() {
+    let no_gene_gtf = SINGLE_GENE_GTF.lines().skip(1).collect::<Vec<&str>>().join("\n");
+    let mut reader = GffReader::from_reader(no_gene_gtf.as_bytes(), GffType::GTF2);
+    let mut genes = reader.genes().expect("genes");
+
+    let gx = genes.next().expect("a gene result").expect("a gene");
+    assert_eq!(gx.id(), Some("ENSG00000128645.13"));
+    assert_eq!(gx.start(), 176188578);
+    assert_eq!(gx.end(), 176190907);
+    assert_eq!(gx.attributes().len(), 0);
+    assert_eq!(gx.transcripts().len(), 2);
+
+    assert!(genes.next().is_none());
+}
+
+#[test]
+fn gtf_reader_transcripts_stream() {
+    let mut reader = GffReader::from_reader(SINGLE_GENE_GTF.as_bytes(), GffType::GTF2);
+    let mut transcripts = reader.transcripts_stream().expect("transcripts");
+
+    let trx1 = transcripts.next().expect("a transcript result").expect("a transcript");
+    assert_eq!(trx1.id(), Some("ENST00000331462.5"));
+    assert_eq!(trx1.start(), 176188578);
+    assert_eq!(trx1.end(), 176190907);
+    assert_eq!(trx1.exons().len(), 2);
+    assert_eq!(trx1.coding_coord(true), Some((176188801, 176190142)));
+
+    let trx2 = transcripts.next().expect("a transcript result").expect("a transcript");
+    assert_eq!(trx2.id(), Some("ENST00000610524.1"));
+    assert_eq!(trx2.exons().len(), 1);
+
+    assert!(transcripts.next().is_none());
+}
+
+#[test]
+fn gtf_reader_transcripts_stream_ungrouped() {
+    let lines: Vec<&str> = SINGLE_GENE_GTF.lines().collect();
+    let mut ungrouped = vec![lines[1], lines[2]];
+    ungrouped.extend_from_slice(&lines[9..]);
+    ungrouped.extend_from_slice(&lines[3..9]);
+    let ungrouped_gtf = ungrouped.join("\n");
+
+    let mut reader = GffReader::from_reader(ungrouped_gtf.as_bytes(), GffType::GTF2);
+    let results: Vec<_> = reader.transcripts_stream().expect("transcripts").collect();
+    let ungrouped_err = results.iter()
+        .filter_map(|res| res.as_ref().err())
+        .find(|err| match err.inner() {
+            &GteError::Gff(GffError::UngroupedTranscript(Some(ref tid))) =>
+                tid == "ENST00000331462.5",
+            _ => false,
+        })
+        .expect("an ungrouped transcript error");
+    assert_eq!(ungrouped_err.line(), Some(6));
+}
+
+#[test]
+fn gtf_reader_transcript_error_line() {
+    let gtf = "chr1\tsrc\ttranscript\t101\t500\t.\t+\t.\tgene_id \"g1\"; transcript_id \"t1\";\n\
+               chr1\tsrc\texon\t101\t500\t.\t+\t.\tgene_id \"g1\"; transcript_id \"t1\";\n\
+               chr1\tsrc\texon\t601\t700\t.\t+\t.\tgene_id \"g1\"; transcript_id \"t2\";\n";
+
+    let mut reader = GffReader::from_reader(gtf.as_bytes(), GffType::GTF2);
+    let errs: Vec<_> = reader.transcripts().expect("transcripts")
+        .filter_map(|res| res.err())
+        .collect();
+    assert_eq!(errs.len(), 1);
+    assert_eq!(errs[0].line(), Some(3));
+    assert!(errs[0].raw_line().map_or(false, |raw| raw.contains("\"t2\"")));
+    match errs[0].inner() {
+        &GteError::Gff(GffError::MissingTranscript(Some(ref tid))) => assert_eq!(tid, "t2"),
+        _ => panic!("expected a missing transcript error"),
+    }
+
+    let mut reader = GffReader::from_reader(gtf.as_bytes(), GffType::GTF2);
+    let errs: Vec<_> = reader.transcripts_stream().expect("transcripts")
+        .filter_map(|res| res.err())
+        .collect();
+    assert_eq!(errs.len(), 1);
+    assert_eq!(errs[0].line(), Some(3));
+}
+
+#[test]
+fn gtf_reader_id_lines() {
+    let gtf = "chr1\tsrc\tgene\t101\t700\t.\t+\t.\tgene_id \"g1\";\n\
+               chr1\tsrc\ttranscript\t101\t500\t.\t+\t.\tgene_id \"g1\"; transcript_id \"t1\";\n\
+               chr1\tsrc\texon\t101\t500\t.\t+\t.\tgene_id \"g1\"; transcript_id \"t1\";\n\
+               # comment\n\
+               chr1\tsrc\ttranscript\t601\t700\t.\t+\t.\tgene_id \"g1\"; transcript_id \"t2\";\n\
+               chr1\tsrc\texon\t601\t700\t.\t+\t.\tgene_id \"g1\"; transcript_id \"t2\";\n";
+
+    let mut reader = GffReader::from_reader(gtf.as_bytes(), GffType::GTF2);
+    assert_eq!(reader.genes().expect("genes").count(), 1);
+    assert!(reader.id_lines().is_empty());
+
+    let mut reader = GffReader::from_reader(gtf.as_bytes(), GffType::GTF2);
+    assert_eq!(reader.record_id_lines(true).genes().expect("genes").count(), 1);
+    {
+        let lines = |id: &str| reader.id_lines().get(id)
+            .map(|lines| lines.iter().cloned().collect::<Vec<usize>>());
+        assert_eq!(lines("g1"), Some(vec![1, 2, 3, 5, 6]));
+        assert_eq!(lines("t1"), Some(vec![2, 3]));
+        assert_eq!(lines("t2"), Some(vec![5, 6]));
+    }
+    let mut rows = reader.id_lines().transcript_rows()
+        .map(|(tid, &count)| (tid.as_str(), count))
+        .collect::<Vec<(&str, usize)>>();
+    rows.sort();
+    assert_eq!(rows, vec![("t1", 1), ("t2", 1)]);
+
+    let mut reader = GffReader::from_reader(gtf.as_bytes(), GffType::GTF2);
+    assert_eq!(reader.record_id_lines(true).transcripts_stream().expect("transcripts").count(), 2);
+    let lines = reader.id_lines().get("g1")
+        .map(|lines| lines.iter().cloned().collect::<Vec<usize>>());
+    assert_eq!(lines, Some(vec![2, 3, 5, 6]));
+}
+
+#[test]
+fn gtf_reader_transcripts_stream_sequences() {
+    // The same transcript may end one sequence and start the next one, but its records may not
+    // reappear after those of another sequence.
+    let row = |chrom: &str, feature: &str, start: u64, end: u64| {
+        format!("{}\tsrc\t{}\t{}\t{}\t.\t+\t.\tgene_id \"g1\"; transcript_id \"t1\";",
+                chrom, feature, start, end)
+    };
+    let rows = [
+        row("chrX", "transcript", 101, 500),
+        row("chrX", "exon", 101, 500),
+        row("chrY", "transcript", 101, 500),
+        row("chrY", "exon", 101, 500),
+        row("chrX", "exon", 601, 700),
+    ];
+    let gtf = rows.join("\n") + "\n";
+    let mut reader = GffReader::from_reader(gtf.as_bytes(), GffType::GTF2);
+    let mut transcripts = reader.transcripts_stream().expect("transcripts");
+
+    let trx1 = transcripts.next().expect("a transcript result").expect("a transcript");
+    assert_eq!((trx1.id(), trx1.seq_name()), (Some("t1"), "chrX"));
+    let trx2 = transcripts.next().expect("a transcript result").expect("a transcript");
+    assert_eq!((trx2.id(), trx2.seq_name()), (Some("t1"), "chrY"));
+    let err = transcripts.next().expect("a transcript result").expect_err("an error");
+    match err.inner() {
+        &GteError::Gff(GffError::UngroupedTranscript(Some(ref tid))) => assert_eq!(tid, "t1"),
+        _ => panic!("expected an ungrouped transcript error"),
+    }
+}
+
+#[test]
+fn gff3_reader_transcripts_stream_id_parent() {
+    let gff3 = "chrX\tsrc\tgene\t1001\t2000\t.\t+\t.\tID=gene-PAR\n\
+                chrX\tsrc\tmRNA\t1001\t2000\t.\t+\t.\tID=rna-PAR;Parent=gene-PAR\n\
+                chrX\tsrc\texon\t1001\t1200\t.\t+\t.\tID=exon-1;Parent=rna-PAR\n\
+                chrX\tsrc\texon\t1801\t2000\t.\t+\t.\tID=exon-2;Parent=rna-PAR\n\
+                chrX\tsrc\tmRNA\t1001\t1500\t.\t+\t.\tID=rna-2;Parent=gene-PAR\n\
+                chrX\tsrc\texon\t1001\t1500\t.\t+\t.\tID=exon-3;Parent=rna-2\n\
+                chrY\tsrc\tgene\t1001\t2000\t.\t+\t.\tID=gene-PAR\n\
+                chrY\tsrc\tmRNA\t1001\t2000\t.\t+\t.\tID=rna-PAR;Parent=gene-PAR\n\
+                chrY\tsrc\texon\t1001\t1200\t.\t+\t.\tID=exon-1;Parent=rna-PAR\n\
+                chrY\tsrc\texon\t1801\t2000\t.\t+\t.\tID=exon-2;Parent=rna-PAR\n";
+    let mut reader = GffReader::from_reader(gff3.as_bytes(), GffType::GFF3);
+    let mut transcripts = reader.transcripts_stream().expect("transcripts");
+
+    let trx1 = transcripts.next().expect("a transcript result").expect("a transcript");
+    assert_eq!(trx1.id(), Some("rna-PAR"));
+    assert_eq!(trx1.gene_id(), Some("gene-PAR"));
+    assert_eq!(trx1.seq_name(), "chrX");
+    assert_eq!(trx1.exons().len(), 2);
+
+    let trx2 = transcripts.next().expect("a transcript result").expect("a transcript");
+    assert_eq!(trx2.id(), Some("rna-2"));
+    assert_eq!(trx2.exons().len(), 1);
+
+    let trx3 = transcripts.next().expect("a transcript result").expect("a transcript");
+    assert_eq!(trx3.id(), Some("rna-PAR"));
+    assert_eq!(trx3.seq_name(), "chrY");
+    assert_eq!(trx3.exons().len(), 2);
+
+    assert!(transcripts.next().is_none());
+}
+
+#[test]
+fn gff3_reader_transcripts_stream_missing_parent() {
+    let gff3 = "chr1\tRefSeq\tmRNA\t1001\t1500\t.\t+\t.\tID=rna-1;Parent=gene-1\n\
+                chr1\tRefSeq\texon\t1001\t1500\t.\t+\t.\tID=exon-1;Parent=rna-9\n";
+    let mut reader = GffReader::from_reader(gff3.as_bytes(), GffType::GFF3);
+    let mut transcripts = reader.transcripts_stream().expect("transcripts");
+
+    let err = transcripts.next().expect("a transcript result").expect_err("an error");
+    assert_eq!(err.line(), Some(2));
+    match err.inner() {
+        &GteError::Gff(GffError::MissingTranscript(Some(ref tid))) => assert_eq!(tid, "rna-9"),
+        _ => panic!("expected a missing transcript error"),
+    }
+}
+
+#[test]
+fn gff3_reader_transcripts_id_parent() {
+    let mut reader = GffReader::from_reader(TWO_GENES_REFSEQ_GFF3.as_bytes(), GffType::GFF3);
+    let mut transcripts = reader.transcripts().expect("transcripts");
+
+    let trx1 = transcripts.next().expect("a transcript result").expect("a transcript");
+    assert_eq!(trx1.id(), Some("rna-1"));
+    assert_eq!(trx1.gene_id(), Some("gene-ABC"));
+    assert_eq!(trx1.start(), 1000);
+    assert_eq!(trx1.end(), 5000);
+    assert_eq!(trx1.strand(), &Forward);
+    assert_eq!(trx1.exons().len(), 3);
+    assert_eq!(trx1.coding_coord(true), Some((1100, 4602)));
+
+    let trx2 = transcripts.next().expect("a transcript result").expect("a transcript");
+    assert_eq!(trx2.id(), Some("rna-2"));
+    assert_eq!(trx2.gene_id(), Some("gene-ABC"));
+    assert_eq!(trx2.start(), 1000);
+    assert_eq!(trx2.end(), 4000);
+    assert_eq!(trx2.exons().len(), 2);
+    assert_eq!(trx2.exons()[0].start(), 1000);
+    assert_eq!(trx2.exons()[0].end(), 1500);
+    assert_eq!(trx2.coding_coord(true), Some((1100, 3200)));
+
+    let trx3 = transcripts.next().expect("a transcript result").expect("a transcript");
+    assert_eq!(trx3.id(), Some("rna-3"));
+    assert_eq!(trx3.gene_id(), Some("gene-XYZ"));
+    assert_eq!(trx3.strand(), &Reverse);
+    assert_eq!(trx3.exons().len(), 1);
+    assert_eq!(trx3.coding_coord(true), None);
+
+    assert!(transcripts.next().is_none());
+}
+
+#[test]
+fn gff3_reader_genes_id_parent() {
+    let mut reader = GffReader::from_reader(TWO_GENES_REFSEQ_GFF3.as_bytes(), GffType::GFF3);
+    let mut genes = reader.genes().expect("genes");
+
+    let gx1 = genes.next().expect("a gene result").expect("a gene");
+    assert_eq!(gx1.id(), Some("gene-ABC"));
+    assert_eq!(gx1.start(), 1000);
+    assert_eq!(gx1.end(), 5000);
+    assert_eq!(gx1.attributes().get("Name"), Some(&"ABC".to_owned()));
+    assert!(gx1.attributes().get("ID").is_none());
+    let tids: Vec<&str> = gx1.transcripts().keys().map(|k| k.as_str()).collect();
+    assert_eq!(tids, vec!["rna-1", "rna-2"]);
+
+    let gx2 = genes.next().expect("a gene result").expect("a gene");
+    assert_eq!(gx2.id(), Some("gene-XYZ"));
+    assert_eq!(gx2.attributes().get("gene_biotype"), Some(&"lncRNA".to_owned()));
+    assert_eq!(gx2.transcripts().len(), 1);
+
+    assert!(genes.next().is_none());
+}
+
+#[test]
+fn gff3_reader_missing_parent() {
+    let gff3 = "chr1\tRefSeq\texon\t1001\t1500\t.\t+\t.\tID=exon-1;Parent=rna-9";
+    let mut reader = GffReader::from_reader(gff3.as_bytes(), GffType::GFF3);
+    reader.error_policy(ErrorPolicy::Strict);
+    let err = match reader.transcripts() {
+        Err(err) => err,
+        Ok(_) => panic!("expected a missing transcript error"),
+    };
+    assert_eq!(err.line(), Some(1));
+    match err.inner() {
+        &GteError::Gff(GffError::MissingTranscript(Some(ref tid))) => assert_eq!(tid, "rna-9"),
+        _ => panic!("expected a missing transcript error"),
+    }
+}
+
+#[test]
+fn gtf_reader_invalid_strand_line() {
+    let lines: Vec<&str> = SINGLE_GENE_GTF.lines().collect();
+    let invalid_line = lines[3].replace("\t+\t", "\t*\t");
+    let mut invalid = lines.clone();
+    invalid[3] = invalid_line.as_str();
+    let invalid_gtf = invalid.join("\n");
+
+    let mut reader = GffReader::from_reader(invalid_gtf.as_bytes(), GffType::GTF2);
+    reader.error_policy(ErrorPolicy::Strict);
+    let err = match reader.transcripts() {
+        Err(err) => err,
+        Ok(_) => panic!("expected an invalid strand error"),
+    };
+    assert_eq!(err.line(), Some(4));
+    assert_eq!(err.field(), Some("strand"));
+    assert_eq!(err.raw_line(), Some(invalid_line.as_str()));
+    match err.inner() {
+        &GteError::Gff(GffError::InvalidStrand(ref raw)) => assert_eq!(raw, "*"),
+        _ => panic!("expected an invalid strand error"),
+    }
+}
+
+#[test]
+fn gtf_reader_error_policy_collect() {
+    let lines: Vec<&str> = SINGLE_GENE_GTF.lines().collect();
+    let invalid_line = lines[9].replace("\t+\t", "\t*\t");
+    let mut invalid = lines.clone();
+    invalid[9] = invalid_line.as_str();
+    let invalid_gtf = invalid.join("\n");
+
+    let mut reader = GffReader::from_reader(invalid_gtf.as_bytes(), GffType::GTF2);
+    reader.error_policy(ErrorPolicy::Collect);
+    let num_transcripts = reader.transcripts().expect("transcripts")
+        .map(|result| result.expect("a transcript"))
+        .count();
+    assert_eq!(num_transcripts, 2);
+
+    let rejected = reader.error_report().rejected();
+    assert_eq!(rejected.len(), 1);
+    assert_eq!(rejected[0].id(), None);
+    assert_eq!(rejected[0].error().line(), Some(10));
+    assert_eq!(rejected[0].error().field(), Some("strand"));
+}
+
+#[test]
+fn gtf_reader_transcripts_stream_zero_start() {
+    let gtf = "chr1\ttest\texon\t0\t100\t.\t+\t.\tgene_id \"g1\"; transcript_id \"t1\";";
+    let mut reader = GffReader::from_reader(gtf.as_bytes(), GffType::GTF2);
+    let mut transcripts = reader.transcripts_stream().expect("transcripts");
+    let err = transcripts.next().expect("a transcript result").expect_err("an error");
+    assert_eq!(err.line(), Some(1));
+    assert_eq!(err.field(), Some("start"));
+    match err.inner() {
+        &GteError::Gff(GffError::InvalidCoord(ref raw)) => assert_eq!(raw, "0"),
+        _ => panic!("expected an invalid coordinate error"),
+    }
+}
+
+#[test]
+fn gtf_reader_transcript_attributes() {
+    let mut reader = GffReader::from_reader(SINGLE_GENE_GTF.as_bytes(), GffType::GTF2);
+    let mut transcripts = reader.transcripts().expect("transcripts");
+
+    let trx1 = transcripts.next().expect("a transcript result").expect("a transcript");
+    assert_eq!(trx1.attributes().get("transcript_name"), Some(&"HOXD1-001".to_owned()));
+    assert_eq!(trx1.attributes().get("level"), Some(&"2".to_owned()));
+    assert_eq!(trx1.attributes().get_vec("tag"),
+               Some(&vec!["basic".to_owned(), "appris_principal_1".to_owned(),
+                          "CCDS".to_owned()]));
+    assert!(trx1.attributes().get("gene_id").is_none());
+    assert!(trx1.attributes().get("transcript_id").is_none());
+    assert_eq!(trx1.exons()[0].attributes().get("exon_number"), Some(&"1".to_owned()));
+    assert_eq!(trx1.exons()[1].attributes().get("exon_id"),
+               Some(&"ENSE00002391549.2".to_owned()));
+
+    let trx2 = transcripts.next().expect("a transcript result").expect("a transcript");
+    assert_eq!(trx2.attributes().get("transcript_type"), Some(&"miRNA".to_owned()));
+}
+
+#[test]
+fn gff3_reader_transcript_attributes() {
+    let mut reader = GffReader::from_reader(TWO_GENES_REFSEQ_GFF3.as_bytes(), GffType::GFF3);
+    let mut transcripts = reader.transcripts().expect("transcripts");
+
+    let trx1 = transcripts.next().expect("a transcript result").expect("a transcript");
+    assert_eq!(trx1.attributes().get("Name"), Some(&"NM_0001.1".to_owned()));
+    assert!(trx1.attributes().get("ID").is_none());
+    assert!(trx1.attributes().get("Parent").is_none());
+    assert_eq!(trx1.exons()[1].attributes().get("ID"), Some(&"exon-2".to_owned()));
+}
+
+/// Normalizes GTF lines so that records can be compared regardless of attribute order and
+/// value quoting.
+fn normalize_gtf_lines<'a, I: Iterator<Item=&'a str>>(lines: I) -> Vec<String> {
+    let mut normalized: Vec<String> = lines
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            let cols: Vec<&str> = line.splitn(9, '\t').collect();
+            let mut attribs: Vec<String> = cols[8].split(';')
+                .map(|item| item.trim().replace('"', ""))
+                .filter(|item| !item.is_empty())
+                .collect();
+            attribs.sort();
+            format!("{}\t{}", cols[..8].join("\t"), attribs.join(";"))
+        })
+        .collect();
+    normalized.sort();
+    normalized
+}
+
+#[test]
+fn gtf_lossless_round_trip() {
+    let mut reader = GffReader::from_reader(SINGLE_GENE_GTF.as_bytes(), GffType::GTF2);
+    reader.lossless(true);
+    let mut writer = GffWriter::from_memory(GffType::GTF2);
+    for result in reader.genes().expect("genes") {
+        writer.write_gene(&result.expect("a gene")).expect("a successful write");
+    }
+    assert_eq!(normalize_gtf_lines(writer.as_string().lines()),
+               normalize_gtf_lines(SINGLE_GENE_GTF.lines()));
+}
+
+#[test]
+fn gtf_lossless_features() {
+    let mut reader = GffReader::from_reader(SINGLE_GENE_GTF.as_bytes(), GffType::GTF2);
+    reader.lossless(true);
+    let mut transcripts = reader.transcripts().expect("transcripts");
+
+    let trx1 = transcripts.next().expect("a transcript result").expect("a transcript");
+    assert_eq!(trx1.attributes().get("source"), Some(&"HAVANA".to_owned()));
+    assert!(trx1.attributes().get("score").is_none());
+    assert_eq!(trx1.coding_coord(true), Some((176188801, 176190142)));
+    let kinds: Vec<&EFK> = trx1.exons()[1].features().iter().map(|fx| fx.kind()).collect();
+    assert_eq!(kinds, vec![&EFK::CDS { frame: Some(2) }, &EFK::StopCodon { frame: Some(0) },
+                           &EFK::UTR]);
+}
+
+#[test]
+fn gtf_lossless_feature_records() {
+    let rows = [
+        "chr1\tsrc\ttranscript\t101\t500\t.\t+\t.\tgene_id \"g1\"; transcript_id \"t1\";",
+        "chr1\tsrc\texon\t101\t500\t0.5\t+\t.\tgene_id \"g1\"; transcript_id \"t1\"; \
+         exon_number \"1\";",
+        "chr1\tsrc\tCDS\t151\t400\t12.75\t+\t0\tgene_id \"g1\"; transcript_id \"t1\"; \
+         protein_id \"p1\";",
+        "chr1\tother\trepeat\t201\t250\t.\t+\t.\tgene_id \"g1\"; transcript_id \"t1\"; \
+         note \"x\";",
+    ];
+    let gtf = rows.join("\n") + "\n";
+    let mut reader = GffReader::from_reader(gtf.as_bytes(), GffType::GTF2);
+    reader.lossless(true);
+    let trx = reader.transcripts().expect("transcripts")
+        .next().expect("a transcript result").expect("a transcript");
+
+    let features = trx.exons()[0].features();
+    assert_eq!(features.len(), 2);
+    assert_eq!(features[0].attributes().get("protein_id"), Some(&"p1".to_owned()));
+    assert_eq!(features[0].attributes().get("score"), Some(&"12.75".to_owned()));
+    assert!(features[0].attributes().get("exon_number").is_none());
+    assert_eq!(features[1].attributes().get("source"), Some(&"other".to_owned()));
+
+    let mut writer = GffWriter::from_memory(GffType::GTF2);
+    writer.write_transcript(&trx).expect("a successful write");
+    assert_eq!(normalize_gtf_lines(writer.as_string().lines()),
+               normalize_gtf_lines(gtf.lines()));
+}
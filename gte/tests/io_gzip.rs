@@ -0,0 +1,83 @@
+extern crate gte;
+
+use std::io::{Read, Write};
+
+use gte::{BgzfWriter, GzipReader, RefFlatReader};
+
+
+static SINGLE_ROW_NO_CDS: &'static str = include_str!("data/single_row_no_cds.refFlat");
+
+/// Empty BGZF block that marks the end of BGZF data.
+static EOF_BLOCK: [u8; 28] = [0x1f, 0x8b, 0x08, 0x04, 0x00, 0x00, 0x00, 0x00,
+                              0x00, 0xff, 0x06, 0x00, 0x42, 0x43, 0x02, 0x00,
+                              0x1b, 0x00, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00,
+                              0x00, 0x00, 0x00, 0x00];
+
+fn compress(data: &[u8]) -> Vec<u8> {
+    let mut writer = BgzfWriter::from_writer(Vec::new());
+    writer.write_all(data).expect("a successful write");
+    writer.finish().expect("a finished writer");
+    writer.get_ref().clone()
+}
+
+#[test]
+fn gzip_reader_plain() {
+    let mut reader = GzipReader::from_reader(SINGLE_ROW_NO_CDS.as_bytes()).unwrap();
+    assert!(!reader.is_compressed());
+    let mut contents = String::new();
+    reader.read_to_string(&mut contents).unwrap();
+    assert_eq!(contents, SINGLE_ROW_NO_CDS);
+}
+
+#[test]
+fn gzip_reader_empty() {
+    let mut reader = GzipReader::from_reader(&b""[..]).unwrap();
+    assert!(!reader.is_compressed());
+    let mut contents = Vec::new();
+    reader.read_to_end(&mut contents).unwrap();
+    assert!(contents.is_empty());
+}
+
+#[test]
+fn bgzf_writer_empty() {
+    let compressed = compress(b"");
+    assert_eq!(compressed.as_slice(), &EOF_BLOCK[..]);
+}
+
+#[test]
+fn bgzf_writer_gzip_reader_roundtrip() {
+    let compressed = compress(SINGLE_ROW_NO_CDS.as_bytes());
+    assert_eq!(&compressed[..4], &[0x1f, 0x8b, 0x08, 0x04]);
+    assert!(compressed.ends_with(&EOF_BLOCK));
+
+    let mut reader = GzipReader::from_reader(compressed.as_slice()).unwrap();
+    assert!(reader.is_compressed());
+    let mut contents = String::new();
+    reader.read_to_string(&mut contents).unwrap();
+    assert_eq!(contents, SINGLE_ROW_NO_CDS);
+}
+
+#[test]
+fn bgzf_writer_gzip_reader_roundtrip_mult_blocks() {
+    let data = SINGLE_ROW_NO_CDS.repeat(2000);
+    assert!(data.len() > 0xff00);
+    let compressed = compress(data.as_bytes());
+
+    let mut reader = GzipReader::from_reader(compressed.as_slice()).unwrap();
+    let mut contents = String::new();
+    reader.read_to_string(&mut contents).unwrap();
+    assert_eq!(contents, data);
+}
+
+#[test]
+fn refflat_reader_compressed() {
+    let compressed = compress(SINGLE_ROW_NO_CDS.as_bytes());
+    let input = GzipReader::from_reader(compressed.as_slice()).unwrap();
+    let mut reader = RefFlatReader::from_reader(input);
+    let mut transcripts = reader.transcripts_stream();
+
+    let trx1 = transcripts.next().expect("a transcript result").expect("a transcript");
+    assert_eq!(trx1.id(), Some("NR_046018"));
+
+    assert!(transcripts.next().is_none());
+}
@@ -6,15 +6,18 @@ use std::io;
 
 use linked_hash_map::LinkedHashMap;
 
-use gte::{RefFlatReader, RefFlatWriter, RefFlatRecord,
+use gte::{RefFlatReader, RefFlatWriter, RefFlatRecord, RefFlatLayout, CdsStat,
           RefFlatRecordsStream, RefFlatTranscriptsStream, RefFlatGenesStream,
-          Transcript, TBuilder, Gene, GBuilder, Strand};
+          Transcript, TBuilder, Gene, GBuilder, Strand, ErrorPolicy, GffReader, GffWriter,
+          GffType, ExonFeatureKind as EFK};
 
 
 static SINGLE_ROW_NO_CDS: &'static str = include_str!("data/single_row_no_cds.refFlat");
 static MULT_ROWS_NO_CDS: &'static str = include_str!("data/mult_rows_no_cds.refFlat");
 static MULT_ROWS_MULT_GENES_WITH_CDS: &'static str =
     include_str!("data/mult_rows_mult_genes_with_cds.refFlat");
+static TWO_ROWS_WITH_BIN_EXT: &'static str =
+    include_str!("data/two_rows_with_bin.genePredExt");
 
 
 fn next_rec<'a, R>(rr: &mut RefFlatRecordsStream<'a, R>) -> RefFlatRecord where R: io::Read {
@@ -407,3 +410,299 @@ fn refflat_writer_genes_mult_rows_mult_genes_with_cds() {
     }
     assert_eq!(writer.as_string(), MULT_ROWS_MULT_GENES_WITH_CDS);
 }
+
+#[test]
+fn genepred_ext_reader_records_with_bin() {
+    let mut reader = RefFlatReader::from_reader(TWO_ROWS_WITH_BIN_EXT.as_bytes());
+    reader.layout(RefFlatLayout::GenePredExt).has_bin(true);
+    let mut records = reader.records_stream();
+
+    let rec1 = next_rec(&mut records);
+    assert_eq!(rec1.gene_id(), "TNFRSF14");
+    assert_eq!(rec1.transcript_id(), "NM_001297605");
+    assert_eq!(rec1.score(), Some(0));
+    assert_eq!(rec1.cds_start_stat(), Some(CdsStat::Complete));
+    assert_eq!(rec1.cds_end_stat(), Some(CdsStat::Complete));
+    assert_eq!(rec1.exon_frames(), &[0, 0, 1, 1, 1, 2, -1]);
+
+    let rec2 = next_rec(&mut records);
+    assert_eq!(rec2.gene_id(), "SMIM12");
+    assert_eq!(rec2.exon_frames(), &[0, -1, -1]);
+
+    assert!(records.next().is_none());
+}
+
+#[test]
+fn genepred_ext_reader_transcripts_cds_stat_none() {
+    let row = "NR_046018\tchr1\t+\t11873\t14409\t12000\t12100\t3\t\
+               11873,12612,13220,\t12227,12721,14409,\t0\tDDX11L1\tnone\tnone\t-1,-1,-1,\n";
+    let mut reader = RefFlatReader::from_reader(row.as_bytes());
+    reader.layout(RefFlatLayout::GenePredExt);
+    let mut transcripts = reader.transcripts_stream();
+
+    let trx = next_trx(&mut transcripts);
+    assert_eq!(trx.gene_id(), Some("DDX11L1"));
+    assert!(!trx.is_coding());
+    assert_eq!(trx.attributes().get("cdsStartStat").map(|v| v.as_str()), Some("none"));
+}
+
+#[test]
+fn genepred_ext_reader_transcripts_cds_stat_incomplete() {
+    let row = "TX1\tchr1\t+\t100\t400\t150\t350\t2\t100,250,\t200,400,\t0\tG1\t\
+               cmpl\tincmpl\t0,0,\n";
+    let mut reader = RefFlatReader::from_reader(row.as_bytes());
+    reader.layout(RefFlatLayout::GenePredExt);
+    let mut transcripts = reader.transcripts_stream();
+
+    let trx = next_trx(&mut transcripts);
+    let fxs = trx.exons().iter()
+        .map(|exn| exn.features().iter()
+             .map(|fx| (fx.start(), fx.end(), fx.kind().clone()))
+             .collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    assert_eq!(fxs[0], vec![(100, 150, EFK::UTR5),
+                            (150, 153, EFK::StartCodon { frame: Some(0) }),
+                            (150, 200, EFK::CDS { frame: Some(0) })]);
+    // The stop codon bases are part of the CDS and the frame is taken from exonFrames.
+    assert_eq!(fxs[1], vec![(250, 350, EFK::CDS { frame: Some(0) }),
+                            (350, 400, EFK::UTR3)]);
+}
+
+#[test]
+fn genepred_ext_reader_transcripts_cds_stat_incomplete_reverse() {
+    let row = "TX1\tchr1\t-\t100\t400\t150\t350\t2\t100,250,\t200,400,\t0\tG1\t\
+               incmpl\tunk\t1,0,\n";
+    let mut reader = RefFlatReader::from_reader(row.as_bytes());
+    reader.layout(RefFlatLayout::GenePredExt);
+    let mut transcripts = reader.transcripts_stream();
+
+    let trx = next_trx(&mut transcripts);
+    let has_codon = trx.exons().iter()
+        .flat_map(|exn| exn.features().iter())
+        .any(|fx| match *fx.kind() {
+            EFK::StartCodon { .. } | EFK::StopCodon { .. } => true,
+            _ => false,
+        });
+    assert!(!has_codon);
+    assert_eq!(trx.coding_coord(true), Some((150, 350)));
+}
+
+#[test]
+fn genepred_ext_reader_header_line() {
+    let rows = format!("#bin\tname\tchrom\tstrand\ttxStart\ttxEnd\tcdsStart\tcdsEnd\t\
+                        exonCount\texonStarts\texonEnds\tscore\tname2\tcdsStartStat\t\
+                        cdsEndStat\texonFrames\n{}", TWO_ROWS_WITH_BIN_EXT);
+    let mut reader = RefFlatReader::from_reader(rows.as_bytes());
+    reader.layout(RefFlatLayout::GenePredExt).has_bin(true).error_policy(ErrorPolicy::Strict);
+    let tids = reader.transcripts_stream()
+        .map(|result| result.expect("a transcript").id().unwrap().to_owned())
+        .collect::<Vec<String>>();
+    assert_eq!(tids, vec!["NM_001297605", "NM_001164824"]);
+}
+
+#[test]
+fn genepred_ext_reader_invalid_column_count() {
+    let mut reader = RefFlatReader::from_reader(MULT_ROWS_NO_CDS.as_bytes());
+    reader.layout(RefFlatLayout::GenePredExt);
+    let mut records = reader.records_stream();
+
+    assert!(records.next().expect("a refflat record result").is_err());
+}
+
+#[test]
+fn refflat_reader_invalid_number_line() {
+    let rows = "DDX11L1\tNR_046018\tchr1\t+\t11873\t14409\t14409\t14409\t3\t\
+                11873,12612,13220,\t12227,12721,14409,\n\
+                \n\
+                WASH7P\tNR_024540\tchr1\t-\t14361\tend\t29370\t29370\t1\t14361,\t29370,\n";
+    let mut reader = RefFlatReader::from_reader(rows.as_bytes());
+    let mut transcripts = reader.transcripts_stream();
+
+    assert!(transcripts.next().expect("a transcript result").is_ok());
+    let err = transcripts.next().expect("a transcript result").expect_err("an error");
+    assert_eq!(err.line(), Some(3));
+    assert_eq!(err.field(), Some("txEnd"));
+    assert!(err.raw_line().map(|raw| raw.starts_with("WASH7P\t")).unwrap_or(false));
+    assert!(transcripts.next().is_none());
+}
+
+#[test]
+fn refflat_reader_error_policy_strict() {
+    let rows = "WASH7P\tNR_024540\tchr1\t-\t14361\tend\t29370\t29370\t1\t14361,\t29370,\n\
+                DDX11L1\tNR_046018\tchr1\t+\t11873\t14409\t14409\t14409\t3\t\
+                11873,12612,13220,\t12227,12721,14409,\n";
+    let mut reader = RefFlatReader::from_reader(rows.as_bytes());
+    reader.error_policy(ErrorPolicy::Strict);
+    {
+        let mut transcripts = reader.transcripts_stream();
+        let err = transcripts.next().expect("a transcript result").expect_err("an error");
+        assert_eq!(err.line(), Some(1));
+        assert!(transcripts.next().is_none());
+    }
+    assert!(reader.error_report().is_empty());
+}
+
+#[test]
+fn refflat_reader_error_policy_collect() {
+    let rows = "WASH7P\tNR_024540\tchr1\t-\t14361\t29370\t29370\t29370\t2\t14361,\t29370,\n\
+                DDX11L1\tNR_046018\tchr1\t+\t11873\t14409\t14409\t14409\t3\t\
+                11873,12612,13220,\t12227,12721,14409,\n";
+    let mut reader = RefFlatReader::from_reader(rows.as_bytes());
+    reader.error_policy(ErrorPolicy::Collect);
+    let tids = reader.transcripts_stream()
+        .map(|result| result.expect("a transcript").id().map(|id| id.to_owned()))
+        .collect::<Vec<Option<String>>>();
+    assert_eq!(tids, vec![Some("NR_046018".to_owned())]);
+
+    let rejected = reader.error_report().rejected();
+    assert_eq!(rejected.len(), 1);
+    assert_eq!(rejected[0].id(), None);
+    assert_eq!(rejected[0].error().line(), Some(1));
+    assert_eq!(rejected[0].error().field(), Some("exonCount"));
+}
+
+#[test]
+fn refflat_reader_invalid_utf8_line() {
+    let mut rows = b"WASH7P\tNR_024540\tchr1\t-\t14361\t29370\t29370\t29370\t1\t\
+                     \xff,\t29370,\n".to_vec();
+    rows.extend_from_slice(SINGLE_ROW_NO_CDS.as_bytes());
+    let mut reader = RefFlatReader::from_reader(rows.as_slice());
+    reader.error_policy(ErrorPolicy::Collect);
+    let tids = reader.transcripts_stream()
+        .map(|result| result.expect("a transcript").id().map(|id| id.to_owned()))
+        .collect::<Vec<Option<String>>>();
+    assert_eq!(tids, vec![Some("NR_046018".to_owned())]);
+
+    let rejected = reader.error_report().rejected();
+    assert_eq!(rejected.len(), 1);
+    assert_eq!(rejected[0].error().line(), Some(1));
+    assert_eq!(rejected[0].error().inner().to_string(), "line is not valid UTF-8");
+}
+
+#[test]
+fn refflat_reader_genes_error_policy_collect() {
+    let rows = "DDX11L1\tNR_046018\tchr1\t+\t11873\t14409\t14409\t14409\t3\t\
+                11873,12612,13220,\t12227,12721,14409,\n\
+                DDX11L1\tNR_046018\tchr1\t+\t11873\t14409\t14409\t14409\t1\t\
+                11873,\t14409,\n\
+                DDX11L1\tNR_046019\tchr1\t+\t11873\t14409\t14409\t14409\t1\t\
+                11873,\t14409,\n";
+    let mut reader = RefFlatReader::from_reader(rows.as_bytes());
+    reader.error_policy(ErrorPolicy::Collect);
+    let genes = reader.genes_stream()
+        .map(|result| result.expect("a gene"))
+        .collect::<Vec<Gene>>();
+    assert_eq!(genes.len(), 1);
+    assert_eq!(genes[0].id(), Some("DDX11L1"));
+    let tids = genes[0].transcripts().keys().cloned().collect::<Vec<String>>();
+    assert_eq!(tids, vec!["NR_046018".to_owned(), "NR_046019".to_owned()]);
+
+    let rejected = reader.error_report().rejected();
+    assert_eq!(rejected.len(), 1);
+    assert_eq!(rejected[0].id(), Some("NR_046018"));
+    assert_eq!(rejected[0].error().line(), Some(2));
+    assert_eq!(rejected[0].error().field(), Some("name"));
+}
+
+#[test]
+fn genepred_reader_genes_gene_id_fallback() {
+    let row = "NR_046018\tchr1\t+\t11873\t14409\t14409\t14409\t3\t\
+               11873,12612,13220,\t12227,12721,14409,\n";
+    let mut reader = RefFlatReader::from_reader(row.as_bytes());
+    reader.layout(RefFlatLayout::GenePred);
+    let mut genes = reader.genes_stream();
+
+    let gx = next_gx(&mut genes);
+    assert_eq!(gx.id(), Some("NR_046018"));
+    assert!(genes.next().is_none());
+}
+
+#[test]
+fn genepred_writer_records() {
+    let mut reader = RefFlatReader::from_reader(SINGLE_ROW_NO_CDS.as_bytes());
+    let rec = next_rec(&mut reader.records_stream());
+
+    let mut writer = RefFlatWriter::from_memory();
+    writer.layout(RefFlatLayout::GenePred);
+    writer.write_record(&rec).expect("a successful write");
+    assert_eq!(writer.as_string(), SINGLE_ROW_NO_CDS.splitn(2, '\t').nth(1).unwrap());
+}
+
+#[test]
+fn genepred_ext_writer_records_from_refflat_with_bin() {
+    let mut reader = RefFlatReader::from_reader(MULT_ROWS_MULT_GENES_WITH_CDS.as_bytes());
+    let mut records = reader.records_stream();
+    let rec1 = next_rec(&mut records);
+    let _rec2 = next_rec(&mut records);
+    let rec3 = next_rec(&mut records);
+
+    let mut writer = RefFlatWriter::from_memory();
+    writer.layout(RefFlatLayout::GenePredExt).has_bin(true);
+    writer.write_record(&rec1).expect("a successful write");
+    writer.write_record(&rec3).expect("a successful write");
+    assert_eq!(writer.as_string(), TWO_ROWS_WITH_BIN_EXT.replace("cmpl", "unk"));
+}
+
+#[test]
+fn genepred_ext_writer_transcripts_with_bin() {
+    let mut reader = RefFlatReader::from_reader(TWO_ROWS_WITH_BIN_EXT.as_bytes());
+    reader.layout(RefFlatLayout::GenePredExt).has_bin(true);
+    let transcripts = reader.transcripts_stream()
+        .collect::<Result<Vec<_>, _>>()
+        .expect("transcripts");
+
+    let mut writer = RefFlatWriter::from_memory();
+    writer.layout(RefFlatLayout::GenePredExt).has_bin(true);
+    for trx in transcripts.iter() {
+        writer.write_transcript(trx).expect("a successful write");
+    }
+    assert_eq!(writer.as_string(), TWO_ROWS_WITH_BIN_EXT);
+}
+
+#[test]
+fn refflat_reader_id_lines() {
+    let rows = "DDX11L1\tNR_046018\tchr1\t+\t11873\t14409\t14409\t14409\t1\t11873,\t14409,\n\
+                # comment\n\
+                DDX11L1\tNR_046019\tchr1\t+\t11873\t14409\t14409\t14409\t1\t11873,\t14409,\n";
+    let mut reader = RefFlatReader::from_reader(rows.as_bytes());
+    assert_eq!(reader.record_id_lines(true).genes_stream().count(), 1);
+
+    let lines = |id: &str| reader.id_lines().get(id)
+        .map(|lines| lines.iter().cloned().collect::<Vec<usize>>());
+    assert_eq!(lines("DDX11L1"), Some(vec![1, 3]));
+    assert_eq!(lines("NR_046018"), Some(vec![1]));
+    assert_eq!(lines("NR_046019"), Some(vec![3]));
+    assert_eq!(reader.id_lines().transcript_rows().count(), 0);
+}
+
+#[test]
+fn refflat_to_gff3_round_trip() {
+    let mut reader = RefFlatReader::from_reader(MULT_ROWS_MULT_GENES_WITH_CDS.as_bytes());
+    let genes = reader.genes_stream()
+        .map(|result| result.expect("a gene"))
+        .collect::<Vec<Gene>>();
+
+    let mut writer = GffWriter::from_memory(GffType::GFF3);
+    for gene in genes.iter() {
+        writer.write_gene(gene).expect("a successful write");
+    }
+
+    let written = writer.as_string();
+    let mut reader = GffReader::from_reader(written.as_bytes(), GffType::GFF3);
+    let transcripts = reader.transcripts().expect("transcripts")
+        .map(|result| result.expect("a transcript"))
+        .collect::<Vec<Transcript>>();
+
+    let expected = genes.iter()
+        .flat_map(|gx| gx.transcripts().values())
+        .collect::<Vec<&Transcript>>();
+    assert_eq!(transcripts.len(), expected.len());
+    for (trx, exp) in transcripts.iter().zip(expected.into_iter()) {
+        assert_eq!(trx.id(), exp.id());
+        assert_eq!(trx.gene_id(), exp.gene_id());
+        assert_eq!((trx.start(), trx.end()), (exp.start(), exp.end()));
+        assert_eq!(trx.strand(), exp.strand());
+        assert_eq!(trx.coding_coord(true), exp.coding_coord(true));
+        assert_eq!(trx.exons().len(), exp.exons().len());
+    }
+}
@@ -0,0 +1,68 @@
+extern crate gte;
+extern crate linked_hash_map;
+
+use linked_hash_map::LinkedHashMap;
+
+use gte::{EBuilder, GBuilder, Gene, SafLevel, SafWriter, Strand, TBuilder};
+use Strand::*;
+
+
+fn two_transcripts_gene() -> Gene {
+    let mut coords = LinkedHashMap::new();
+    coords.insert("trx01".to_owned(),
+                  ((100, 1000), vec![(100, 300), (400, 500), (700, 1000)], Some((200, 800))));
+    coords.insert("trx02".to_owned(),
+                  ((100, 1000), vec![(100, 300), (400, 550), (700, 1000)], Some((200, 800))));
+    GBuilder::new("chrT", 100, 1000)
+        .strand(Reverse)
+        .id("gene01")
+        .transcript_coords(coords)
+        .build()
+        .expect("a gene")
+}
+
+#[test]
+fn saf_writer_gene_exon_level() {
+    let mut writer = SafWriter::from_memory();
+    writer.write_gene(&two_transcripts_gene()).expect("a successful write");
+    assert_eq!(writer.as_string(),
+               "GeneID\tChr\tStart\tEnd\tStrand\n\
+                gene01\tchrT\t101\t300\t-\n\
+                gene01\tchrT\t401\t500\t-\n\
+                gene01\tchrT\t401\t550\t-\n\
+                gene01\tchrT\t701\t1000\t-\n");
+}
+
+#[test]
+fn saf_writer_gene_gene_level() {
+    let mut writer = SafWriter::from_memory();
+    writer.level(SafLevel::Gene)
+        .write_gene(&two_transcripts_gene()).expect("a successful write");
+    assert_eq!(writer.as_string(), "GeneID\tChr\tStart\tEnd\tStrand\ngene01\tchrT\t101\t1000\t-\n");
+}
+
+#[test]
+fn saf_writer_transcript_cds_level() {
+    let trx = TBuilder::new("chrT", 100, 1000)
+        .strand(Forward)
+        .id("trx01")
+        .gene_id("gene01")
+        .coords(vec![(100, 300), (400, 500), (700, 1000)], Some((200, 800)))
+        .build()
+        .expect("a transcript");
+    let mut writer = SafWriter::from_memory();
+    writer.level(SafLevel::CDS)
+        .write_transcript(&trx).expect("a successful write");
+    assert_eq!(writer.as_string(),
+               "GeneID\tChr\tStart\tEnd\tStrand\n\
+                gene01\tchrT\t201\t300\t+\n\
+                gene01\tchrT\t401\t500\t+\n\
+                gene01\tchrT\t701\t800\t+\n");
+}
+
+#[test]
+fn saf_writer_exon_missing_id() {
+    let exon = EBuilder::new("chrT", 10, 20).build().expect("an exon");
+    let mut writer = SafWriter::from_memory();
+    assert!(writer.write_exon(&exon).is_err());
+}
@@ -0,0 +1,173 @@
+extern crate gte;
+#[macro_use]
+extern crate matches;
+
+use std::io::{Cursor, Write};
+
+use gte::{BedIndexedReader, BgzfWriter, Error, GffIndexedReader, GffType, TabixError,
+          TabixIndex, TabixIndexBuilder, TabixPreset, TabixReader};
+
+
+static SINGLE_GENE_GTF: &'static str = include_str!("data/single_gene.gtf");
+static TWO_TRANSCRIPTS_BED: &'static str = include_str!("data/two_transcripts.bed");
+
+/// Returns the non-empty lines of the input that do not start with any of the given prefixes,
+/// sorted by their start coordinates.
+fn sorted_lines<'a>(input: &'a str, start_col: usize, skipped: &[&str]) -> Vec<&'a str> {
+    let mut lines = input.lines()
+        .filter(|line| !line.is_empty() && !skipped.iter().any(|pre| line.starts_with(pre)))
+        .collect::<Vec<&str>>();
+    lines.sort_by_key(|line| line.split('\t').nth(start_col).unwrap().parse::<u64>().unwrap());
+    lines
+}
+
+/// Writes the lines as BGZF data and indexes them, optionally writing each line in its own
+/// block.
+fn index_lines(
+    lines: &[&str],
+    mut builder: TabixIndexBuilder,
+    block_per_line: bool,
+) -> (Vec<u8>, TabixIndex) {
+    let mut writer = BgzfWriter::from_writer(Vec::new());
+    for line in lines {
+        let start_offset = writer.virtual_offset();
+        writeln!(writer, "{}", line).unwrap();
+        if block_per_line {
+            writer.flush().unwrap();
+        }
+        builder.add_line(line, start_offset, writer.virtual_offset()).unwrap();
+    }
+    writer.finish().unwrap();
+    (writer.get_ref().clone(), builder.build())
+}
+
+fn bed_reader(builder: TabixIndexBuilder, block_per_line: bool) -> TabixReader<Cursor<Vec<u8>>> {
+    let lines = sorted_lines(TWO_TRANSCRIPTS_BED, 1, &["track"]);
+    let (data, index) = index_lines(&lines, builder, block_per_line);
+    TabixReader::from_reader(Cursor::new(data), index)
+}
+
+#[test]
+fn tabix_reader_fetch() {
+    let mut reader = bed_reader(TabixIndexBuilder::tbi(TabixPreset::Bed), false);
+    assert_eq!(reader.index().seq_names(), &["chr1".to_owned()]);
+
+    let lines = reader.fetch("chr1", 4000, 4100).unwrap();
+    assert_eq!(lines.len(), 1);
+    assert!(lines[0].starts_with("chr1\t1000\t5000\trna-1\t"));
+
+    assert_eq!(reader.fetch("chr1", 0, 100000).unwrap().len(), 2);
+    assert_eq!(reader.fetch("chr1", 5000, 6000).unwrap().len(), 0);
+    assert_eq!(reader.fetch("chr1", 6499, 6500).unwrap().len(), 1);
+    assert_eq!(reader.fetch("chrX", 0, 100000).unwrap().len(), 0);
+}
+
+#[test]
+fn tabix_reader_fetch_block_per_line() {
+    let mut reader = bed_reader(TabixIndexBuilder::tbi(TabixPreset::Bed), true);
+    assert_eq!(reader.fetch("chr1", 0, 100000).unwrap().len(), 2);
+    assert_eq!(reader.fetch("chr1", 6000, 6001).unwrap().len(), 1);
+}
+
+#[test]
+fn tabix_index_roundtrip_tbi() {
+    let lines = sorted_lines(TWO_TRANSCRIPTS_BED, 1, &["track"]);
+    let (data, index) = index_lines(&lines, TabixIndexBuilder::tbi(TabixPreset::Bed), false);
+    let mut raw_index = Vec::new();
+    index.write(&mut raw_index).unwrap();
+    assert_eq!(&raw_index[..4], &[0x1f, 0x8b, 0x08, 0x04]);
+
+    let index = TabixIndex::from_reader(raw_index.as_slice()).unwrap();
+    assert!(!index.is_csi());
+    assert_eq!(index.seq_names(), &["chr1".to_owned()]);
+    let mut reader = TabixReader::from_reader(Cursor::new(data), index);
+    assert_eq!(reader.fetch("chr1", 4000, 4100).unwrap().len(), 1);
+}
+
+#[test]
+fn tabix_index_roundtrip_csi() {
+    let lines = sorted_lines(TWO_TRANSCRIPTS_BED, 1, &["track"]);
+    let (data, index) = index_lines(&lines, TabixIndexBuilder::csi(TabixPreset::Bed), true);
+    let mut raw_index = Vec::new();
+    index.write(&mut raw_index).unwrap();
+
+    let index = TabixIndex::from_reader(raw_index.as_slice()).unwrap();
+    assert!(index.is_csi());
+    let mut reader = TabixReader::from_reader(Cursor::new(data), index);
+    assert_eq!(reader.fetch("chr1", 4000, 4100).unwrap().len(), 1);
+    assert_eq!(reader.fetch("chr1", 0, 100000).unwrap().len(), 2);
+}
+
+#[test]
+fn tabix_index_invalid() {
+    let index = TabixIndex::from_reader(&b"BAI\x01"[..]);
+    assert!(matches!(index, Err(Error::Tabix(TabixError::InvalidIndex(_)))));
+}
+
+#[test]
+fn tabix_index_builder_unsorted() {
+    let mut builder = TabixIndexBuilder::tbi(TabixPreset::Bed);
+    builder.add_line("chr1\t100\t200", 0, 14).unwrap();
+    let result = builder.add_line("chr1\t50\t200", 14, 27);
+    assert!(matches!(result, Err(Error::Tabix(TabixError::UnsortedInput(_, 50)))));
+}
+
+#[test]
+fn gff_indexed_reader_fetch_straddling() {
+    let lines = sorted_lines(SINGLE_GENE_GTF, 3, &[]);
+    let (data, index) = index_lines(&lines, TabixIndexBuilder::tbi(TabixPreset::Gff), true);
+    let tabix = TabixReader::from_reader(Cursor::new(data), index);
+    let mut reader = GffIndexedReader::from_reader(tabix, GffType::GTF2);
+
+    // Only the second exon of the first transcript overlaps the region.
+    let transcripts = reader.fetch("chr2", 176189900, 176190000).unwrap();
+    assert_eq!(transcripts.len(), 1);
+    let trx = &transcripts[0];
+    assert_eq!(trx.id(), Some("ENST00000331462.5"));
+    assert_eq!(trx.start(), 176188578);
+    assert_eq!(trx.end(), 176190907);
+    assert_eq!(trx.exons().len(), 2);
+
+    assert_eq!(reader.fetch("chr2", 176188850, 176188860).unwrap().len(), 2);
+    assert_eq!(reader.fetch("chr2", 0, 1000).unwrap().len(), 0);
+}
+
+#[test]
+fn gff3_indexed_reader_fetch_parents() {
+    let gff3 = [
+        "chr1\tRefSeq\tgene\t1001\t9000\t.\t+\t.\tID=gene-1;Name=ABC",
+        "chr1\tRefSeq\tmRNA\t1001\t9000\t.\t+\t.\tID=rna-1;Parent=gene-1",
+        "chr1\tRefSeq\texon\t1001\t2000\t.\t+\t.\tID=exon-1;Parent=rna-1",
+        "chr1\tRefSeq\texon\t8001\t9000\t.\t+\t.\tID=exon-2;Parent=rna-1",
+    ];
+    let (data, index) = index_lines(&gff3, TabixIndexBuilder::tbi(TabixPreset::Gff), true);
+    let tabix = TabixReader::from_reader(Cursor::new(data), index);
+    let mut reader = GffIndexedReader::from_reader(tabix, GffType::GFF3);
+
+    // Only the second exon overlaps the region.
+    let transcripts = reader.fetch("chr1", 8500, 8600).unwrap();
+    assert_eq!(transcripts.len(), 1);
+    let trx = &transcripts[0];
+    assert_eq!(trx.id(), Some("rna-1"));
+    assert_eq!(trx.gene_id(), Some("gene-1"));
+    assert_eq!(trx.start(), 1000);
+    assert_eq!(trx.end(), 9000);
+    assert_eq!(trx.exons().len(), 2);
+
+    // Only the gene and mRNA records overlap the intron.
+    let transcripts = reader.fetch("chr1", 5000, 5100).unwrap();
+    assert_eq!(transcripts.len(), 1);
+    assert_eq!(transcripts[0].gene_id(), Some("gene-1"));
+    assert_eq!(transcripts[0].exons().len(), 2);
+}
+
+#[test]
+fn bed_indexed_reader_fetch() {
+    let tabix = bed_reader(TabixIndexBuilder::tbi(TabixPreset::Bed), false);
+    let mut reader = BedIndexedReader::from_reader(tabix);
+
+    let transcripts = reader.fetch("chr1", 4000, 4100).unwrap();
+    assert_eq!(transcripts.len(), 1);
+    assert_eq!(transcripts[0].id(), Some("rna-1"));
+    assert_eq!(transcripts[0].exons().len(), 3);
+}
@@ -0,0 +1,74 @@
+extern crate gte;
+
+use gte::{JunctionCollector, JunctionFormat, Strand, TBuilder, Transcript};
+
+
+fn transcript(id: &str, exon_coords: Vec<(u64, u64)>) -> Transcript {
+    TBuilder::new("chrT", 100, 1000)
+        .strand(Strand::Forward)
+        .id(id)
+        .gene_id("gene01")
+        .coords(exon_coords, None)
+        .build()
+        .expect("a transcript")
+}
+
+fn two_transcripts_collector() -> JunctionCollector {
+    let mut collector = JunctionCollector::new();
+    collector.add_transcript(&transcript("trx01", vec![(100, 300), (400, 500), (700, 1000)]));
+    collector.add_transcript(&transcript("trx02", vec![(100, 300), (400, 550), (700, 1000)]));
+    collector
+}
+
+fn write_string(collector: &JunctionCollector, format: JunctionFormat) -> String {
+    let mut buf = Vec::new();
+    collector.write(&mut buf, format).expect("a successful write");
+    String::from_utf8(buf).expect("a UTF-8 string")
+}
+
+#[test]
+fn junction_collector_unique() {
+    let collector = two_transcripts_collector();
+    assert_eq!(collector.len(), 3);
+    assert!(JunctionCollector::new().is_empty());
+}
+
+#[test]
+fn junction_collector_star() {
+    // One-based first and last intron bases.
+    let collector = two_transcripts_collector();
+    assert_eq!(write_string(&collector, JunctionFormat::Star),
+               "chrT\t301\t400\t+\ttrx01,trx02\tgene01\n\
+                chrT\t501\t700\t+\ttrx01\tgene01\n\
+                chrT\t551\t700\t+\ttrx02\tgene01\n");
+}
+
+#[test]
+fn junction_collector_hisat2_splice_sites() {
+    // Zero-based last base of the left exon and first base of the right exon.
+    let collector = two_transcripts_collector();
+    assert_eq!(write_string(&collector, JunctionFormat::Hisat2SpliceSites),
+               "chrT\t299\t400\t+\n\
+                chrT\t499\t700\t+\n\
+                chrT\t549\t700\t+\n");
+}
+
+#[test]
+fn junction_collector_hisat2_exons() {
+    // Zero-based first and last exon bases.
+    let collector = two_transcripts_collector();
+    assert_eq!(write_string(&collector, JunctionFormat::Hisat2Exons),
+               "chrT\t100\t299\t+\n\
+                chrT\t400\t499\t+\n\
+                chrT\t400\t549\t+\n\
+                chrT\t700\t999\t+\n");
+}
+
+#[test]
+fn junction_collector_regtools() {
+    let collector = two_transcripts_collector();
+    assert_eq!(write_string(&collector, JunctionFormat::Regtools),
+               "chrT\t100\t500\tJUNC00000001\t2\t+\t100\t500\t255,0,0\t2\t200,100\t0,300\n\
+                chrT\t400\t1000\tJUNC00000002\t1\t+\t400\t1000\t255,0,0\t2\t100,300\t0,300\n\
+                chrT\t400\t1000\tJUNC00000003\t1\t+\t400\t1000\t255,0,0\t2\t150,300\t0,300\n");
+}
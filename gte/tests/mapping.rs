@@ -0,0 +1,126 @@
+extern crate gte;
+
+use gte::{PosAnchor, Strand, TBuilder, Transcript, TranscriptPos};
+use PosAnchor::*;
+use Strand::*;
+
+
+fn coding_transcript(strand: Strand) -> Transcript {
+    TBuilder::new("chrT", 100, 600)
+        .strand(strand)
+        .coords(vec![(100, 200), (300, 400), (500, 600)], Some((150, 550)))
+        .coding_incl_stop(true)
+        .build()
+        .expect("a transcript")
+}
+
+fn pos(raw: &str) -> TranscriptPos {
+    raw.parse().expect("a transcript position")
+}
+
+fn cdna_str(trx: &Transcript, genome_pos: u64) -> String {
+    format!("n.{}", trx.genome_to_cdna(genome_pos))
+}
+
+fn cds_str(trx: &Transcript, genome_pos: u64) -> String {
+    format!("c.{}", trx.genome_to_cds(genome_pos).expect("a CDS position"))
+}
+
+#[test]
+fn transcript_pos_parse() {
+    assert_eq!(pos("c.-15+2"), TranscriptPos::new(Upstream(15), 2));
+    assert_eq!(pos("c.*30"), TranscriptPos::new(Downstream(30), 0));
+    assert_eq!(pos("n.100-3"), TranscriptPos::new(Within(100), -3));
+    assert_eq!(pos("100+5"), TranscriptPos::new(Within(100), 5));
+    assert!(pos("c.*30-5").is_intronic());
+    assert_eq!(pos("c.-15+2").to_string(), "-15+2");
+    assert_eq!(pos("c.*30-5").to_string(), "*30-5");
+    assert!("c.0".parse::<TranscriptPos>().is_err());
+    assert!("c.".parse::<TranscriptPos>().is_err());
+    assert!("c.10+".parse::<TranscriptPos>().is_err());
+    assert!("c.abc".parse::<TranscriptPos>().is_err());
+}
+
+#[test]
+fn genome_to_transcript_fwd() {
+    let trx = coding_transcript(Forward);
+    assert_eq!(cdna_str(&trx, 100), "n.1");
+    assert_eq!(cdna_str(&trx, 90), "n.-10");
+    assert_eq!(cdna_str(&trx, 610), "n.*11");
+    assert_eq!(cdna_str(&trx, 204), "n.100+5");
+    assert_eq!(cds_str(&trx, 150), "c.1");
+    assert_eq!(cds_str(&trx, 149), "c.-1");
+    assert_eq!(cds_str(&trx, 90), "c.-60");
+    assert_eq!(cds_str(&trx, 300), "c.51");
+    assert_eq!(cds_str(&trx, 549), "c.200");
+    assert_eq!(cds_str(&trx, 550), "c.*1");
+    assert_eq!(cds_str(&trx, 610), "c.*61");
+    assert_eq!(cds_str(&trx, 204), "c.50+5");
+    assert_eq!(cds_str(&trx, 297), "c.51-3");
+}
+
+#[test]
+fn genome_to_transcript_rev() {
+    let trx = coding_transcript(Reverse);
+    assert_eq!(cdna_str(&trx, 599), "n.1");
+    assert_eq!(cdna_str(&trx, 610), "n.-11");
+    assert_eq!(cdna_str(&trx, 90), "n.*10");
+    assert_eq!(cds_str(&trx, 549), "c.1");
+    assert_eq!(cds_str(&trx, 550), "c.-1");
+    assert_eq!(cds_str(&trx, 150), "c.200");
+    assert_eq!(cds_str(&trx, 149), "c.*1");
+    assert_eq!(cds_str(&trx, 495), "c.50+5");
+    assert_eq!(cds_str(&trx, 403), "c.51-4");
+}
+
+#[test]
+fn genome_to_transcript_noncoding() {
+    let trx = TBuilder::new("chrT", 100, 600)
+        .strand(Forward)
+        .coords(vec![(100, 200), (500, 600)], None)
+        .build()
+        .expect("a transcript");
+    assert_eq!(cdna_str(&trx, 500), "n.101");
+    assert_eq!(trx.genome_to_cds(500), None);
+    assert_eq!(trx.cds_to_genome(&pos("c.1")), None);
+}
+
+#[test]
+fn transcript_to_genome_fwd() {
+    let trx = coding_transcript(Forward);
+    assert_eq!(trx.cds_to_genome(&pos("c.1")), Some(150));
+    assert_eq!(trx.cds_to_genome(&pos("c.-60")), Some(90));
+    assert_eq!(trx.cds_to_genome(&pos("c.*61")), Some(610));
+    assert_eq!(trx.cds_to_genome(&pos("c.50+5")), Some(204));
+    assert_eq!(trx.cds_to_genome(&pos("c.51-3")), Some(297));
+    assert_eq!(trx.cds_to_genome(&pos("c.50+101")), None);
+    assert_eq!(trx.cds_to_genome(&pos("c.51+2")), None);
+    assert_eq!(trx.cds_to_genome(&pos("c.*1+2")), None);
+    assert_eq!(trx.cdna_to_genome(&pos("n.101")), Some(300));
+    assert_eq!(trx.cdna_to_genome(&pos("n.-10")), Some(90));
+    assert_eq!(trx.cdna_to_genome(&pos("n.-101")), None);
+}
+
+#[test]
+fn transcript_to_genome_rev() {
+    let trx = coding_transcript(Reverse);
+    assert_eq!(trx.cds_to_genome(&pos("c.1")), Some(549));
+    assert_eq!(trx.cds_to_genome(&pos("c.200")), Some(150));
+    assert_eq!(trx.cds_to_genome(&pos("c.50+5")), Some(495));
+    assert_eq!(trx.cds_to_genome(&pos("c.51-4")), Some(403));
+    assert_eq!(trx.cdna_to_genome(&pos("n.-11")), Some(610));
+    assert_eq!(trx.cdna_to_genome(&pos("n.*10")), Some(90));
+}
+
+#[test]
+fn genome_transcript_roundtrip() {
+    for strand in vec![Forward, Reverse] {
+        let trx = coding_transcript(strand);
+        for genome_pos in 0..700 {
+            let cdna = trx.genome_to_cdna(genome_pos);
+            assert_eq!(trx.cdna_to_genome(&cdna), Some(genome_pos));
+            let cds = trx.genome_to_cds(genome_pos).expect("a CDS position");
+            assert_eq!(trx.cds_to_genome(&cds), Some(genome_pos));
+        }
+    }
+}
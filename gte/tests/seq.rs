@@ -0,0 +1,59 @@
+extern crate gte;
+
+use std::io::Cursor;
+
+use gte::{SeqReader, TBuilder, Transcript, Strand};
+
+
+static CHRT_FA: &'static str = include_str!("data/chrT.fa");
+static CHRT_FAI: &'static str = include_str!("data/chrT.fa.fai");
+
+
+fn seq_reader() -> SeqReader<Cursor<&'static [u8]>> {
+    SeqReader::from_readers(Cursor::new(CHRT_FA.as_bytes()), CHRT_FAI.as_bytes())
+        .expect("a sequence reader")
+}
+
+fn transcript(strand: Strand) -> Transcript {
+    TBuilder::new("chrT", 5, 50)
+        .strand(strand)
+        .id("trx01")
+        .coords(vec![(5, 20), (30, 50)], Some((10, 47)))
+        .coding_incl_stop(true)
+        .build()
+        .expect("a transcript")
+}
+
+#[test]
+fn seq_reader_forward() {
+    let mut reader = seq_reader();
+    let trx = transcript(Strand::Forward);
+
+    assert_eq!(reader.transcript_seq(&trx).unwrap(), b"AGACAATTACATAACGCACGAAACTTGTTGGCCCA".to_vec());
+    assert_eq!(reader.exon_seqs(&trx).unwrap(),
+               vec![b"AGACAATTACATAAC".to_vec(), b"GCACGAAACTTGTTGGCCCA".to_vec()]);
+    assert_eq!(reader.coding_seq(&trx, true).unwrap(), b"ATTACATAACGCACGAAACTTGTTGGC".to_vec());
+    assert_eq!(reader.coding_seq(&trx, false).unwrap(), b"ATTACATAACGCACGAAACTTGTT".to_vec());
+    assert_eq!(reader.utr5_seq(&trx).unwrap(), b"AGACA".to_vec());
+    assert_eq!(reader.utr3_seq(&trx).unwrap(), b"CCA".to_vec());
+}
+
+#[test]
+fn seq_reader_reverse() {
+    let mut reader = seq_reader();
+    let trx = transcript(Strand::Reverse);
+
+    assert_eq!(reader.transcript_seq(&trx).unwrap(), b"TGGGCCAACAAGTTTCGTGCGTTATGTAATTGTCT".to_vec());
+    assert_eq!(reader.exon_seqs(&trx).unwrap(),
+               vec![b"TGGGCCAACAAGTTTCGTGC".to_vec(), b"GTTATGTAATTGTCT".to_vec()]);
+    assert_eq!(reader.coding_seq(&trx, true).unwrap(), b"GCCAACAAGTTTCGTGCGTTATGTAAT".to_vec());
+    assert_eq!(reader.coding_seq(&trx, false).unwrap(), b"GCCAACAAGTTTCGTGCGTTATGT".to_vec());
+    assert_eq!(reader.utr5_seq(&trx).unwrap(), b"TGG".to_vec());
+    assert_eq!(reader.utr3_seq(&trx).unwrap(), b"TGTCT".to_vec());
+}
+
+#[test]
+fn seq_reader_interval_out_of_range() {
+    let mut reader = seq_reader();
+    assert!(reader.interval_seq("chrT", 50, 70).is_err());
+}
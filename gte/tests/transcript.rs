@@ -4,8 +4,9 @@ extern crate multimap;
 
 use multimap::MultiMap;
 
-use gte::{ExonFeatureKind, Strand, TBuilder, Transcript};
+use gte::{ExonFeatureKind, Strand, TBuilder, Transcript, TranscriptFeatureKind};
 use ExonFeatureKind::*;
+use TranscriptFeatureKind::Intron;
 use Strand::*;
 
 fn exon_coords(transcript: &Transcript) -> Vec<(u64, u64)> {
@@ -1403,3 +1404,56 @@ fn tbuilder_coords_unk_coding_incl_stop() {
         .build();
     assert!(btrx.is_err());
 }
+
+// Length helpers
+
+#[test]
+fn transcript_lengths_coding() {
+    let (trx, _) = trx_fxs(100, 1000, Forward, vec![(100, 300), (400, 500), (700, 1000)],
+                           Some((150, 750)));
+    assert_eq!(trx.spliced_len(), 600);
+    assert_eq!(trx.coding_len(), 300);
+    assert!(trx.is_coding());
+}
+
+#[test]
+fn transcript_lengths_noncoding() {
+    let (trx, _) = trx_fxs(100, 1000, Reverse, vec![(100, 300), (400, 500), (700, 1000)], None);
+    assert_eq!(trx.spliced_len(), 600);
+    assert_eq!(trx.coding_len(), 0);
+    assert!(!trx.is_coding());
+}
+
+// Intron helpers
+
+fn intron_values(trx: &Transcript) -> Vec<(u64, u64, TranscriptFeatureKind)> {
+    trx.introns().iter()
+        .map(|fx| (fx.start(), fx.end(), fx.kind().clone()))
+        .collect()
+}
+
+#[test]
+fn transcript_introns_fwd() {
+    let (trx, _) = trx_fxs(100, 1000, Forward, vec![(100, 300), (400, 500), (700, 1000)], None);
+    assert_eq!(intron_values(&trx), vec![
+        (300, 400, Intron { rank: 1, donor: 300, acceptor: 399 }),
+        (500, 700, Intron { rank: 2, donor: 500, acceptor: 699 }),
+    ]);
+}
+
+#[test]
+fn transcript_introns_rev() {
+    let (trx, _) = trx_fxs(100, 1000, Reverse, vec![(100, 300), (400, 500), (700, 1000)], None);
+    assert_eq!(intron_values(&trx), vec![
+        (300, 400, Intron { rank: 2, donor: 399, acceptor: 300 }),
+        (500, 700, Intron { rank: 1, donor: 699, acceptor: 500 }),
+    ]);
+}
+
+#[test]
+fn transcript_introns_none() {
+    let (trx, _) = trx_fxs(100, 1000, Forward, vec![(100, 1000)], None);
+    assert!(trx.introns().is_empty());
+    let (trx, _) = trx_fxs(100, 1000, Forward, vec![(100, 300), (300, 1000)], None);
+    assert!(trx.introns().is_empty());
+}
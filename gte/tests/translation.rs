@@ -0,0 +1,73 @@
+extern crate gte;
+
+use std::io::Cursor;
+
+use gte::{GeneticCode, SeqReader, TBuilder, Translation, TranslationIssue, Strand};
+use TranslationIssue::*;
+
+
+static CHRT_FA: &'static str = include_str!("data/chrT.fa");
+static CHRT_FAI: &'static str = include_str!("data/chrT.fa.fai");
+
+
+#[test]
+fn genetic_code_tables() {
+    let standard = GeneticCode::standard();
+    assert_eq!(standard.id(), 1);
+    assert_eq!(standard.translate_codon(b"ATG"), b'M');
+    assert_eq!(standard.translate_codon(b"tga"), b'*');
+    assert_eq!(standard.translate_codon(b"ANG"), b'X');
+    assert!(standard.is_start_codon(b"CTG"));
+    assert!(!standard.is_start_codon(b"ATT"));
+
+    let vert_mito = GeneticCode::from_id(2).expect("a genetic code");
+    assert_eq!(vert_mito.translate_codon(b"TGA"), b'W');
+    assert!(vert_mito.is_stop_codon(b"AGA"));
+
+    assert!(GeneticCode::from_id(7).is_none());
+}
+
+#[test]
+fn translation_complete() {
+    let tl = Translation::from_coding_seq(b"ATGAAACTGTGA", GeneticCode::standard(), 0);
+    assert_eq!(tl.protein(), b"MKL");
+    assert!(!tl.has_issues());
+}
+
+#[test]
+fn translation_frame_offset_and_invalid_length() {
+    let tl = Translation::from_coding_seq(b"CATGAAATAGTGAC", GeneticCode::standard(), 1);
+    assert_eq!(tl.protein(), b"MK*");
+    assert_eq!(tl.frame_offset(), 1);
+    assert_eq!(tl.issues(), &[InternalStopCodon(2), InvalidLength(13)]);
+}
+
+#[test]
+fn seq_reader_translate() {
+    let mut reader = SeqReader::from_readers(Cursor::new(CHRT_FA.as_bytes()),
+                                             CHRT_FAI.as_bytes())
+        .expect("a sequence reader");
+    let builder = || TBuilder::new("chrT", 5, 50)
+        .strand(Strand::Forward)
+        .id("trx01")
+        .coords(vec![(5, 20), (30, 50)], Some((10, 47)))
+        .coding_incl_stop(true);
+
+    let trx = builder().build().expect("a transcript");
+    let tl = reader.translate(&trx, GeneticCode::standard(), false)
+        .expect("a translation result")
+        .expect("a translation");
+    assert_eq!(tl.protein(), b"IT*RTKLVG");
+    assert_eq!(tl.issues(), &[MissingStartCodon, InternalStopCodon(2), MissingStopCodon]);
+
+    let trx = builder().attribute("transl_table", "11").build().expect("a transcript");
+    let tl = reader.translate(&trx, GeneticCode::standard(), false)
+        .expect("a translation result")
+        .expect("a translation");
+    assert_eq!(tl.genetic_code().id(), 11);
+    assert_eq!(tl.protein(), b"MT*RTKLVG");
+    assert_eq!(tl.issues(), &[InternalStopCodon(2), MissingStopCodon]);
+
+    let trx = builder().attribute("transl_table", "99").build().expect("a transcript");
+    assert!(reader.translate(&trx, GeneticCode::standard(), false).is_err());
+}
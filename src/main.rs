@@ -50,6 +50,10 @@ quick_error! {
         Other(msg: &'static str) {
             description(msg)
         }
+        Input(msg: String) {
+            description("invalid input")
+            display("{}", msg)
+        }
     }
 }
 
@@ -69,6 +73,18 @@ fn build_cli<'a, 'b>() -> App<'a, 'b> {
                     AppSettings::DisableHelpSubcommand,
                     AppSettings::VersionlessSubcommands])
         .subcommand(tools::gff_to_refflat::build_cli::<'a, 'b>())
+        .subcommand(tools::refflat_to_gff::build_cli::<'a, 'b>())
+        .subcommand(tools::gff_to_bed::build_cli::<'a, 'b>())
+        .subcommand(tools::refflat_to_bed::build_cli::<'a, 'b>())
+        .subcommand(tools::getseq::build_cli::<'a, 'b>())
+        .subcommand(tools::query::build_cli::<'a, 'b>())
+        .subcommand(tools::map_coords::build_cli::<'a, 'b>())
+        .subcommand(tools::junctions::build_cli::<'a, 'b>())
+        .subcommand(tools::flatten::build_cli::<'a, 'b>())
+        .subcommand(tools::to_saf::build_cli::<'a, 'b>())
+        .subcommand(tools::table::build_cli::<'a, 'b>())
+        .subcommand(tools::validate::build_cli::<'a, 'b>())
+        .subcommand(tools::index::build_cli::<'a, 'b>())
         .subcommand(tools::stats::build_cli::<'a, 'b>())
 }
 
@@ -77,6 +93,18 @@ fn run(matches: ArgMatches) -> ::Result<()> {
     match matches.subcommand() {
         (tools::stats::NAME, Some(m)) => tools::stats::run(m),
         (tools::gff_to_refflat::NAME, Some(m)) => tools::gff_to_refflat::run(m),
+        (tools::refflat_to_gff::NAME, Some(m)) => tools::refflat_to_gff::run(m),
+        (tools::gff_to_bed::NAME, Some(m)) => tools::gff_to_bed::run(m),
+        (tools::refflat_to_bed::NAME, Some(m)) => tools::refflat_to_bed::run(m),
+        (tools::getseq::NAME, Some(m)) => tools::getseq::run(m),
+        (tools::query::NAME, Some(m)) => tools::query::run(m),
+        (tools::map_coords::NAME, Some(m)) => tools::map_coords::run(m),
+        (tools::junctions::NAME, Some(m)) => tools::junctions::run(m),
+        (tools::flatten::NAME, Some(m)) => tools::flatten::run(m),
+        (tools::to_saf::NAME, Some(m)) => tools::to_saf::run(m),
+        (tools::table::NAME, Some(m)) => tools::table::run(m),
+        (tools::validate::NAME, Some(m)) => tools::validate::run(m),
+        (tools::index::NAME, Some(m)) => tools::index::run(m),
         // We should not reach this point since we already require
         // that subcommands must be present in the app settings.
         _ => Err(Error::Other("unexpected command line parsing error")),
@@ -0,0 +1,189 @@
+use std::collections::HashMap;
+use std::io::Write;
+
+use clap::{App, Arg, ArgMatches, SubCommand};
+use gte::{ExonBin, Gene, GffReader, RefFlatReader, Strand};
+
+use tools::TEMPLATE_SUBCMD;
+use utils::{self, InputFormat};
+
+pub const NAME: &'static str = "flatten";
+
+/// Identifier used for genes without any identifiers.
+const UNKNOWN_ID: &'static str = ".";
+
+/// Source column value of GTF output.
+const GTF_SOURCE: &'static str = "gtetools";
+
+/// Feature column value of GTF output.
+const GTF_FEATURE: &'static str = "exonic_part";
+
+
+pub fn build_cli<'a, 'b>() -> App<'a, 'b> {
+    SubCommand::with_name(NAME)
+        .about("Collapses genes into non-overlapping exonic bins for read counting")
+        .template(TEMPLATE_SUBCMD)
+        .arg(Arg::with_name("input")
+                .required(true)
+                .takes_value(true)
+                .help("Path to input annotation file or '-' for stdin"))
+        .arg(Arg::with_name("output")
+                .default_value("-")
+                .takes_value(true)
+                .help("Path to output file or '-' for stdout"))
+        .arg(Arg::with_name("format")
+                .short("-f")
+                .long("--format")
+                .required(true)
+                .takes_value(true)
+                .value_name("FORMAT")
+                .possible_values(&["refflat", "gff3", "gtf"])
+                .display_order(1)
+                .help("Input annotation format"))
+        .arg(Arg::with_name("output_format")
+                .short("-o")
+                .long("--output-format")
+                .takes_value(true)
+                .value_name("FORMAT")
+                .default_value("gtf")
+                .possible_values(&["gtf", "saf", "bed"])
+                .display_order(2)
+                .long_help(
+                    "Output format. Bins shared with other genes are flagged with the 'shared' \
+                     attribute in GTF output and with a score of 1 in BED output. SAF output \
+                     has no flags, so '--exclude-shared' may be used to remove these bins."))
+        .arg(Arg::with_name("bins")
+                .long("--bins")
+                .display_order(3)
+                .help("Split the collapsed exons wherever the transcripts differ, as DEXSeq does"))
+        .arg(Arg::with_name("exclude_shared")
+                .long("--exclude-shared")
+                .display_order(4)
+                .help("Do not write bins that overlap bins of other genes"))
+        .arg(Arg::with_name("lengths")
+                .long("--lengths")
+                .takes_value(true)
+                .value_name("FILE")
+                .display_order(5)
+                .help("Path to output TSV file of gene effective lengths"))
+        .args(&utils::error_args(6))
+}
+
+pub fn run(args: &ArgMatches) -> ::Result<()> {
+
+    let input_fmt = utils::resolve_input_format(args.value_of("format").unwrap())?;
+    let input_path = args.value_of("input").unwrap();
+    let mut errors = utils::RecordErrors::from_args(args, input_path)?;
+    let input = utils::resolve_reader(input_path)?;
+    let mut writer = utils::resolve_writer(args.value_of("output").unwrap())?;
+    let mut lengths_writer = match args.value_of("lengths") {
+        Some(path) => Some(utils::resolve_writer(path)?),
+        None => None,
+    };
+    let split_bins = args.is_present("bins");
+
+    let mut bins: Vec<ExonBin> = vec![];
+    {
+        let mut add_gene = |gx: Gene| -> ::Result<()> {
+            if let Some(ref mut lw) = lengths_writer {
+                writeln!(lw, "{}\t{}", gx.id().unwrap_or(UNKNOWN_ID), gx.effective_len())?;
+            }
+            if split_bins {
+                bins.extend(gx.counting_bins());
+            } else {
+                bins.extend(gx.collapsed_exons());
+            }
+            Ok(())
+        };
+
+        match input_fmt {
+            InputFormat::RefFlat => {
+                let mut reader = RefFlatReader::from_reader(input);
+                reader.error_policy(errors.policy());
+                for result in reader.genes_stream() {
+                    match result {
+                        Ok(gx) => add_gene(gx)?,
+                        Err(e) => errors.skip(e)?,
+                    }
+                }
+                errors.write_report(reader.error_report())?;
+            },
+            InputFormat::Gff(gff_type) => {
+                let mut reader = GffReader::from_reader(input, gff_type);
+                reader.error_policy(errors.policy());
+                for result in reader.genes().map_err(|e| utils::input_error(input_path, e))? {
+                    match result {
+                        Ok(gx) => add_gene(gx)?,
+                        Err(e) => errors.skip(e)?,
+                    }
+                }
+                errors.write_report(reader.error_report())?;
+            },
+        }
+    }
+
+    ExonBin::flag_shared(&mut bins);
+    let exclude_shared = args.is_present("exclude_shared");
+    let output_fmt = args.value_of("output_format").unwrap();
+    if output_fmt == "saf" {
+        writeln!(writer, "GeneID\tChr\tStart\tEnd\tStrand")?;
+    }
+
+    let mut part_numbers: HashMap<Option<&str>, usize> = HashMap::new();
+    for bin in bins.iter() {
+        let part_number = {
+            let counter = part_numbers.entry(bin.gene_id()).or_insert(0);
+            *counter += 1;
+            *counter
+        };
+        if exclude_shared && bin.is_shared() {
+            continue;
+        }
+        match output_fmt {
+            "saf" => write_saf(&mut writer, bin)?,
+            "bed" => write_bed(&mut writer, bin, part_number)?,
+            _ => write_gtf(&mut writer, bin, part_number)?,
+        }
+    }
+
+    if let Some(ref mut lw) = lengths_writer {
+        lw.finish()?;
+    }
+    writer.finish()
+}
+
+/// Writes the bin as a SAF row, with one-based coordinates.
+fn write_saf<W: Write>(writer: &mut W, bin: &ExonBin) -> ::Result<()> {
+    writeln!(writer, "{}\t{}\t{}\t{}\t{}",
+             bin.gene_id().unwrap_or(UNKNOWN_ID), bin.seq_name(), bin.start() + 1, bin.end(),
+             strand_to_char(bin.strand()))?;
+    Ok(())
+}
+
+/// Writes the bin as a BED6 row, named after its gene and part number.
+fn write_bed<W: Write>(writer: &mut W, bin: &ExonBin, part_number: usize) -> ::Result<()> {
+    writeln!(writer, "{}\t{}\t{}\t{}:{:03}\t{}\t{}",
+             bin.seq_name(), bin.start(), bin.end(), bin.gene_id().unwrap_or(UNKNOWN_ID),
+             part_number, if bin.is_shared() { 1 } else { 0 }, strand_to_char(bin.strand()))?;
+    Ok(())
+}
+
+/// Writes the bin as a DEXSeq-style GTF row.
+fn write_gtf<W: Write>(writer: &mut W, bin: &ExonBin, part_number: usize) -> ::Result<()> {
+    let shared = if bin.is_shared() { " shared \"true\";" } else { "" };
+    writeln!(writer, "{}\t{}\t{}\t{}\t{}\t.\t{}\t.\tgene_id \"{}\"; transcripts \"{}\"; \
+                      exonic_part_number \"{:03}\";{}",
+             bin.seq_name(), GTF_SOURCE, GTF_FEATURE, bin.start() + 1, bin.end(),
+             strand_to_char(bin.strand()), bin.gene_id().unwrap_or(UNKNOWN_ID),
+             bin.transcript_ids().join("+"), part_number, shared)?;
+    Ok(())
+}
+
+/// Helper function to convert strands into their char representation.
+fn strand_to_char(strand: &Strand) -> char {
+    match strand {
+        &Strand::Forward => '+',
+        &Strand::Reverse => '-',
+        &Strand::Unknown => '.',
+    }
+}
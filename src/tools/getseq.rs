@@ -0,0 +1,180 @@
+use std::fs;
+use std::io::Write;
+
+use clap::{App, Arg, ArgMatches, SubCommand};
+use gte::{self, GeneticCode, GffReader, RefFlatReader, SeqReader, Transcript, TranslationIssue};
+
+use tools::TEMPLATE_SUBCMD;
+use utils::{self, InputFormat};
+
+pub const NAME: &'static str = "getseq";
+
+/// Number of bases written per FASTA sequence line.
+const FASTA_LINE_WIDTH: usize = 60;
+
+/// Identifier used for transcripts without any transcript identifiers.
+const UNKNOWN_ID: &'static str = "<unknown>";
+
+
+pub fn build_cli<'a, 'b>() -> App<'a, 'b> {
+    SubCommand::with_name(NAME)
+        .about("Extracts transcript sequences from an indexed FASTA reference")
+        .template(TEMPLATE_SUBCMD)
+        .arg(Arg::with_name("input")
+                .required(true)
+                .takes_value(true)
+                .help("Path to input annotation file or '-' for stdin"))
+        .arg(Arg::with_name("output")
+                .default_value("-")
+                .takes_value(true)
+                .help("Path to output FASTA file or '-' for stdout"))
+        .arg(Arg::with_name("reference")
+                .short("-r")
+                .long("--reference")
+                .required(true)
+                .takes_value(true)
+                .value_name("FASTA")
+                .display_order(1)
+                .help("Path to reference FASTA file, indexed with 'samtools faidx'"))
+        .arg(Arg::with_name("format")
+                .short("-f")
+                .long("--format")
+                .required(true)
+                .takes_value(true)
+                .value_name("FORMAT")
+                .possible_values(&["refflat", "gff3", "gtf"])
+                .display_order(2)
+                .help("Input annotation format"))
+        .arg(Arg::with_name("type")
+                .short("-t")
+                .long("--type")
+                .takes_value(true)
+                .value_name("TYPE")
+                .default_value("cdna")
+                .possible_values(&["cdna", "cds", "utr5", "utr3", "exon", "protein"])
+                .display_order(3)
+                .help("Type of sequence to extract"))
+        .arg(Arg::with_name("table")
+                .long("--table")
+                .takes_value(true)
+                .value_name("ID")
+                .default_value("1")
+                .display_order(4)
+                .help("NCBI translation table for protein sequences, unless overridden by \
+                       the 'transl_table' attribute of a transcript"))
+        .arg(Arg::with_name("use_frame")
+                .long("--use-frame")
+                .display_order(5)
+                .help("Skip incomplete 5' codons of protein sequences using CDS frames"))
+        .args(&utils::error_args(6))
+}
+
+pub fn run(args: &ArgMatches) -> ::Result<()> {
+
+    let input_fmt = utils::resolve_input_format(args.value_of("format").unwrap())?;
+    let input_path = args.value_of("input").unwrap();
+    let mut errors = utils::RecordErrors::from_args(args, input_path)?;
+    let input = utils::resolve_reader(input_path)?;
+    let mut seq_reader = SeqReader::from_file(args.value_of("reference").unwrap())?;
+    let mut writer = utils::resolve_writer(args.value_of("output").unwrap())?;
+    let seq_type = args.value_of("type").unwrap();
+    let genetic_code = args.value_of("table").unwrap().parse::<u8>().ok()
+        .and_then(GeneticCode::from_id)
+        .ok_or(::Error::Other("invalid translation table"))?;
+    let use_frame = args.is_present("use_frame");
+    let mut get_seqs = |trx: &Transcript| -> gte::Result<Vec<(String, Vec<u8>)>> {
+        match seq_type {
+            "protein" => translate(&mut seq_reader, trx, genetic_code, use_frame),
+            _ => extract_seqs(&mut seq_reader, trx, seq_type),
+        }
+    };
+
+    match input_fmt {
+        InputFormat::RefFlat => {
+            let mut reader = RefFlatReader::from_reader(input);
+            reader.error_policy(errors.policy());
+            for result in reader.transcripts_stream() {
+                match result.and_then(|trx| get_seqs(&trx)) {
+                    Ok(records) => write_fasta_records(&mut writer, &records)?,
+                    Err(e) => errors.skip(e)?,
+                }
+            }
+            errors.write_report(reader.error_report())?;
+        },
+        InputFormat::Gff(gff_type) => {
+            let mut reader = GffReader::from_reader(input, gff_type);
+            reader.error_policy(errors.policy());
+            for result in reader.transcripts().map_err(|e| utils::input_error(input_path, e))? {
+                match result.and_then(|trx| get_seqs(&trx)) {
+                    Ok(records) => write_fasta_records(&mut writer, &records)?,
+                    Err(e) => errors.skip(e)?,
+                }
+            }
+            errors.write_report(reader.error_report())?;
+        },
+    }
+
+    writer.finish()
+}
+
+/// Extracts the sequences of the given type from a transcript, along with their identifiers.
+///
+/// Empty sequences, such as coding sequences of non-coding transcripts, are not returned.
+fn extract_seqs(
+    seq_reader: &mut SeqReader<fs::File>,
+    trx: &Transcript,
+    seq_type: &str,
+) -> gte::Result<Vec<(String, Vec<u8>)>> {
+    let tid = trx.id().unwrap_or(UNKNOWN_ID);
+    let records: Vec<(String, Vec<u8>)> = match seq_type {
+        "exon" => seq_reader.exon_seqs(trx)?.into_iter().enumerate()
+            .map(|(idx, seq)| (format!("{}_exon{}", tid, idx + 1), seq))
+            .collect(),
+        "cds" => vec![(tid.to_owned(), seq_reader.coding_seq(trx, true)?)],
+        "utr5" => vec![(tid.to_owned(), seq_reader.utr5_seq(trx)?)],
+        "utr3" => vec![(tid.to_owned(), seq_reader.utr3_seq(trx)?)],
+        _ => vec![(tid.to_owned(), seq_reader.transcript_seq(trx)?)],
+    };
+    Ok(records.into_iter().filter(|&(_, ref seq)| !seq.is_empty()).collect())
+}
+
+/// Translates the coding sequence of a transcript, along with its identifier.
+///
+/// Issues found during the translation are reported to stderr. Non-coding transcripts are not
+/// returned.
+fn translate(
+    seq_reader: &mut SeqReader<fs::File>,
+    trx: &Transcript,
+    genetic_code: GeneticCode,
+    use_frame: bool,
+) -> gte::Result<Vec<(String, Vec<u8>)>> {
+    let tid = trx.id().unwrap_or(UNKNOWN_ID);
+    let translation = match seq_reader.translate(trx, genetic_code, use_frame)? {
+        Some(tl) => tl,
+        None => return Ok(vec![]),
+    };
+    for issue in translation.issues() {
+        let msg = match issue {
+            &TranslationIssue::MissingStartCodon => "missing start codon".to_owned(),
+            &TranslationIssue::MissingStopCodon => "missing stop codon".to_owned(),
+            &TranslationIssue::InternalStopCodon(pos) =>
+                format!("internal stop codon at codon {}", pos + 1),
+            &TranslationIssue::InvalidLength(len) =>
+                format!("coding sequence length {} is not a multiple of three", len),
+        };
+        eprintln!("warning: {}, transcript ID: {}", msg, tid);
+    }
+    Ok(vec![(tid.to_owned(), translation.take_protein())])
+}
+
+/// Writes the given identifiers and sequences as FASTA records.
+fn write_fasta_records<W: Write>(writer: &mut W, records: &[(String, Vec<u8>)]) -> ::Result<()> {
+    for &(ref id, ref seq) in records {
+        writeln!(writer, ">{}", id)?;
+        for line in seq.chunks(FASTA_LINE_WIDTH) {
+            writer.write_all(line)?;
+            writer.write_all(b"\n")?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,122 @@
+use clap::{App, Arg, ArgMatches, SubCommand};
+use gte::{BedWriter, GffReader};
+
+use tools::TEMPLATE_SUBCMD;
+use utils;
+
+pub const NAME: &'static str = "gff-to-bed";
+
+
+pub fn build_cli<'a, 'b>() -> App<'a, 'b> {
+    SubCommand::with_name(NAME)
+        .about("Converts from the GTF or GFF3 format to BED12")
+        .template(TEMPLATE_SUBCMD)
+        .arg(Arg::with_name("input")
+                .required(true)
+                .takes_value(true)
+                .help("Path to input annotation file or '-' for stdin"))
+        .arg(Arg::with_name("output")
+                .required(true)
+                .takes_value(true)
+                .help("Path to output annotation file or '-' for stdout"))
+        .arg(Arg::with_name("gff_type")
+                .short("-t")
+                .long("--gff-type")
+                .required(true)
+                .takes_value(true)
+                .value_name("TYPE")
+                .possible_values(&["gff3", "gtf"])
+                .display_order(1)
+                .help("Input GFF variant"))
+        .arg(Arg::with_name("seq_prefix")
+                .long("--seq-prefix")
+                .value_name("VALUE")
+                .takes_value(true)
+                .display_order(2)
+                .help("String to prepend to all sequence names"))
+        .arg(Arg::with_name("seq_lstrip")
+                .long("--seq-lstrip")
+                .value_name("VALUE")
+                .takes_value(true)
+                .display_order(3)
+                .help("Left-most string to remove from all sequence names"))
+        .arg(Arg::with_name("gene_id_attr")
+                .long("--gid")
+                .value_name("KEY")
+                .default_value("gene_id")
+                .takes_value(true)
+                .display_order(4)
+                .help("Key of GTF record attribute to use as gene identifier"))
+        .arg(Arg::with_name("transcript_id_attr")
+                .long("--tid")
+                .value_name("KEY")
+                .default_value("transcript_id")
+                .display_order(5)
+                .takes_value(true)
+                .help("Key of GTF record attribute to use as transcript identifier"))
+        .arg(Arg::with_name("loose_codons")
+                .long("--loose-codons")
+                .display_order(6)
+                .takes_value(false)
+                .long_help(
+                    "If not specified, only GFF transcripts with start and stop codons will be \
+                     created. If specified, GFF transcripts without start and/or stop codons \
+                     will be created using the min/max coordinates of all their CDS."))
+        .arg(Arg::with_name("introns")
+                .long("--introns")
+                .display_order(7)
+                .takes_value(false)
+                .help("Write one BED6 row per intron instead of one BED12 row per transcript"))
+        .args(&utils::error_args(8))
+        .arg(Arg::with_name("compress")
+                .long("--compress")
+                .display_order(10)
+                .help("Write BGZF-compressed output, which can be indexed with tabix"))
+}
+
+pub fn run(args: &ArgMatches) -> ::Result<()> {
+
+    let gff_type = utils::resolve_gff_type(args.value_of("gff_type").unwrap())?;
+
+    let input_path = args.value_of("input").unwrap();
+    let mut errors = utils::RecordErrors::from_args(args, input_path)?;
+    let mut reader = utils::resolve_reader(input_path)
+        .map(|r| GffReader::from_reader(r, gff_type))?;
+
+    if let Some(prefix) = args.value_of("seq_prefix") {
+        reader.seq_name_prefix(prefix);
+    }
+    if let Some(lstrip) = args.value_of("seq_lstrip") {
+        reader.seq_name_lstrip(lstrip);
+    }
+    reader
+        .gene_id_attr(args.value_of("gene_id_attr").unwrap())
+        .transcript_id_attr(args.value_of("transcript_id_attr").unwrap())
+        .loose_codons(args.is_present("loose_codons"))
+        .error_policy(errors.policy());
+
+    let output_path = args.value_of("output").unwrap();
+    let mut output = utils::resolve_compressed_writer(output_path, args.is_present("compress"))?;
+    {
+        let mut writer = BedWriter::from_writer(&mut output);
+        let write_introns = args.is_present("introns");
+        for result in reader.transcripts().map_err(|e| utils::input_error(input_path, e))? {
+            let wresult = result
+                .and_then(|ref trx| {
+                    if write_introns {
+                        writer.write_introns(trx)
+                    } else {
+                        writer.write_transcript(trx)
+                    }
+                });
+            match wresult {
+                Ok(_) => {},
+                Err(e) => errors.skip(e)?,
+            }
+        }
+        writer.flush()?;
+    }
+    output.finish()?;
+
+    errors.write_report(reader.error_report())
+}
@@ -1,5 +1,5 @@
 use clap::{App, Arg, ArgMatches, SubCommand};
-use gte::{self, GffReader, RefFlatWriter};
+use gte::{GffReader, RefFlatWriter};
 
 use tools::TEMPLATE_SUBCMD;
 use utils;
@@ -46,14 +46,14 @@ pub fn build_cli<'a, 'b>() -> App<'a, 'b> {
                 .default_value("gene_id")
                 .takes_value(true)
                 .display_order(4)
-                .help("Key of GFF record attribute to use as gene identifier"))
+                .help("Key of GTF record attribute to use as gene identifier"))
         .arg(Arg::with_name("transcript_id_attr")
                 .long("--tid")
                 .value_name("KEY")
                 .default_value("transcript_id")
                 .display_order(5)
                 .takes_value(true)
-                .help("Key of GFF record attribute to use as transcript identifier"))
+                .help("Key of GTF record attribute to use as transcript identifier"))
         .arg(Arg::with_name("loose_codons")
                 .long("--loose-codons")
                 .display_order(6)
@@ -62,36 +62,49 @@ pub fn build_cli<'a, 'b>() -> App<'a, 'b> {
                     "If not specified, only GFF transcripts with start and stop codons will be \
                      created. If specified, GFF transcripts without start and/or stop codons \
                      will be created using the min/max coordinates of all their CDS."))
+        .args(&utils::error_args(7))
+        .arg(Arg::with_name("compress")
+                .long("--compress")
+                .display_order(9)
+                .help("Write BGZF-compressed output, which can be indexed with tabix"))
 }
 
 pub fn run(args: &ArgMatches) -> ::Result<()> {
 
     let gff_type = utils::resolve_gff_type(args.value_of("gff_type").unwrap())?;
 
-    let mut reader = utils::resolve_reader(args.value_of("input").unwrap())
+    let input_path = args.value_of("input").unwrap();
+    let mut errors = utils::RecordErrors::from_args(args, input_path)?;
+    let mut reader = utils::resolve_reader(input_path)
         .map(|r| GffReader::from_reader(r, gff_type))?;
 
+    if let Some(prefix) = args.value_of("seq_prefix") {
+        reader.seq_name_prefix(prefix);
+    }
+    if let Some(lstrip) = args.value_of("seq_lstrip") {
+        reader.seq_name_lstrip(lstrip);
+    }
     reader
         .gene_id_attr(args.value_of("gene_id_attr").unwrap())
         .transcript_id_attr(args.value_of("transcript_id_attr").unwrap())
-        .seq_name_prefix(args.value_of("seq_prefix"))
-        .seq_name_lstrip(args.value_of("seq_lstrip"))
-        .loose_codons(args.is_present("loose_codons"));
-
-    let mut writer = utils::resolve_writer(args.value_of("output").unwrap())
-        .map(|w| RefFlatWriter::from_writer(w))?;
+        .loose_codons(args.is_present("loose_codons"))
+        .error_policy(errors.policy());
 
-    for result in reader.transcripts()? {
-        let wresult = result
-            .and_then(|ref trx| writer.write_transcript(trx));
-        if let Err(e) = wresult {
-            if let gte::Error::Gff(gffe) = e {
-                eprintln!("skipping: {}", gffe);
-            } else {
-                return Err(::Error::from(e));
+    let output_path = args.value_of("output").unwrap();
+    let mut output = utils::resolve_compressed_writer(output_path, args.is_present("compress"))?;
+    {
+        let mut writer = RefFlatWriter::from_writer(&mut output);
+        for result in reader.transcripts().map_err(|e| utils::input_error(input_path, e))? {
+            let wresult = result
+                .and_then(|ref trx| writer.write_transcript(trx));
+            match wresult {
+                Ok(_) => {},
+                Err(e) => errors.skip(e)?,
             }
         }
+        writer.flush()?;
     }
+    output.finish()?;
 
-    Ok(())
+    errors.write_report(reader.error_report())
 }
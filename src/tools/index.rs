@@ -0,0 +1,150 @@
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::str::FromStr;
+
+use clap::{App, Arg, ArgMatches, SubCommand};
+use gte::{self, BgzfWriter, TabixError, TabixIndexBuilder, TabixPreset};
+
+use tools::TEMPLATE_SUBCMD;
+use utils;
+
+pub const NAME: &'static str = "index";
+
+/// Directive line after which the GFF3 records end and FASTA sequences begin.
+const FASTA_DIRECTIVE: &'static str = "##FASTA";
+
+/// Prefixes of BED lines that do not contain records.
+const BED_NON_RECORD_PREFIXES: [&'static str; 2] = ["track", "browser"];
+
+
+pub fn build_cli<'a, 'b>() -> App<'a, 'b> {
+    SubCommand::with_name(NAME)
+        .about("Sorts, compresses, and indexes a GTF, GFF3, or BED file for random access")
+        .template(TEMPLATE_SUBCMD)
+        .arg(Arg::with_name("input")
+                .required(true)
+                .takes_value(true)
+                .help("Path to input annotation file or '-' for stdin"))
+        .arg(Arg::with_name("output")
+                .required(true)
+                .takes_value(true)
+                .long_help(
+                    "Path to output BGZF file. The index is written to the same path with a \
+                     '.tbi' or '.csi' extension added."))
+        .arg(Arg::with_name("format")
+                .short("-f")
+                .long("--format")
+                .required(true)
+                .takes_value(true)
+                .value_name("FORMAT")
+                .possible_values(&["gff3", "gtf", "bed"])
+                .display_order(1)
+                .help("Input annotation format"))
+        .arg(Arg::with_name("csi")
+                .long("--csi")
+                .display_order(2)
+                .help("Write a CSI index instead of a TBI index, for sequences over 512 Mbp"))
+}
+
+pub fn run(args: &ArgMatches) -> ::Result<()> {
+
+    let preset = match args.value_of("format").unwrap() {
+        "bed" => TabixPreset::Bed,
+        _ => TabixPreset::Gff,
+    };
+    let input_path = args.value_of("input").unwrap();
+    let output_path = args.value_of("output").unwrap();
+    let (mut builder, index_ext) =
+        if args.is_present("csi") {
+            (TabixIndexBuilder::csi(preset), "csi")
+        } else {
+            (TabixIndexBuilder::tbi(preset), "tbi")
+        };
+
+    let input = utils::resolve_reader(input_path)?;
+    let (header, mut rows) = read_rows(input_path, BufReader::new(input), preset)?;
+    rows.sort_by_key(|row| (row.seq_rank, row.start));
+
+    let mut writer = BgzfWriter::from_file(output_path)?;
+    for line in header.iter() {
+        writeln!(writer, "{}", line)?;
+    }
+    for row in rows.iter() {
+        let start_offset = writer.virtual_offset();
+        writeln!(writer, "{}", row.line)?;
+        builder.add_line(&row.line, start_offset, writer.virtual_offset())
+            .map_err(|e| utils::input_error(input_path, e))?;
+    }
+    writer.finish()?;
+
+    builder.build().to_file(format!("{}.{}", output_path, index_ext))?;
+    Ok(())
+}
+
+/// Helper struct for a data line and the values it is sorted by.
+struct Row {
+    seq_rank: usize,
+    start: u64,
+    line: String,
+}
+
+/// Helper function to read the header lines and the data lines of the input.
+///
+/// Comment lines before the first data line form the header. Other comment lines, empty lines,
+/// BED track and browser lines, and GFF3 FASTA sections are not kept. Sequences are ranked by
+/// the order of their first lines.
+fn read_rows<R: BufRead>(
+    input_path: &str,
+    input: R,
+    preset: TabixPreset,
+) -> ::Result<(Vec<String>, Vec<Row>)> {
+
+    let (start_idx, zero_based) = match preset {
+        TabixPreset::Gff => (3, false),
+        TabixPreset::Bed => (1, true),
+    };
+    let mut header = Vec::new();
+    let mut rows = Vec::new();
+    let mut seq_ranks = HashMap::new();
+
+    for (idx, result) in input.lines().enumerate() {
+        let line = result?;
+        if line.starts_with(FASTA_DIRECTIVE) {
+            break;
+        }
+        if line.starts_with('#') {
+            if rows.is_empty() {
+                header.push(line);
+            }
+            continue;
+        }
+        if line.trim().is_empty()
+            || (preset == TabixPreset::Bed
+                && BED_NON_RECORD_PREFIXES.iter().any(|pre| line.starts_with(pre)))
+        {
+            continue;
+        }
+
+        let parsed = {
+            let mut cols = line.split('\t');
+            let seq_name = cols.next().unwrap_or("");
+            let start = cols.nth(start_idx - 1)
+                .and_then(|raw| u64::from_str(raw).ok())
+                .and_then(|start| if zero_based || start > 0 { Some(start) } else { None });
+            start.map(|start| (seq_name.to_owned(), start))
+        };
+        let (seq_name, start) = match parsed {
+            Some(parsed) => parsed,
+            None => {
+                let err = gte::Error::from(TabixError::InvalidLine(line.clone()));
+                let err = gte::Error::Line(idx + 1, Some("start"), line, Box::new(err));
+                return Err(utils::input_error(input_path, err));
+            },
+        };
+        let num_seqs = seq_ranks.len();
+        let seq_rank = *seq_ranks.entry(seq_name).or_insert(num_seqs);
+        rows.push(Row { seq_rank: seq_rank, start: start, line: line });
+    }
+
+    Ok((header, rows))
+}
@@ -0,0 +1,91 @@
+use clap::{App, Arg, ArgMatches, SubCommand};
+use gte::{GffReader, JunctionCollector, JunctionFormat, RefFlatReader};
+
+use tools::TEMPLATE_SUBCMD;
+use utils::{self, InputFormat};
+
+pub const NAME: &'static str = "junctions";
+
+
+pub fn build_cli<'a, 'b>() -> App<'a, 'b> {
+    SubCommand::with_name(NAME)
+        .about("Writes the unique splice junctions of all transcripts for aligners")
+        .template(TEMPLATE_SUBCMD)
+        .arg(Arg::with_name("input")
+                .required(true)
+                .takes_value(true)
+                .help("Path to input annotation file or '-' for stdin"))
+        .arg(Arg::with_name("output")
+                .default_value("-")
+                .takes_value(true)
+                .help("Path to output junctions file or '-' for stdout"))
+        .arg(Arg::with_name("format")
+                .short("-f")
+                .long("--format")
+                .required(true)
+                .takes_value(true)
+                .value_name("FORMAT")
+                .possible_values(&["refflat", "gff3", "gtf"])
+                .display_order(1)
+                .help("Input annotation format"))
+        .arg(Arg::with_name("output_format")
+                .short("-o")
+                .long("--output-format")
+                .takes_value(true)
+                .value_name("FORMAT")
+                .default_value("star")
+                .possible_values(&["star", "hisat2-ss", "hisat2-exon", "regtools"])
+                .display_order(2)
+                .long_help(
+                    "Output format. 'star' writes a STAR sjdbFileChrStartEnd file with one-based \
+                     intron coordinates, followed by the supporting transcript and gene \
+                     identifiers. 'hisat2-ss' and 'hisat2-exon' write the splice site and exon \
+                     files of hisat2-build, which only have four columns. 'regtools' writes \
+                     BED12 junctions whose blocks are the shortest flanking exons and whose \
+                     scores are the numbers of supporting transcripts."))
+        .args(&utils::error_args(3))
+}
+
+pub fn run(args: &ArgMatches) -> ::Result<()> {
+
+    let input_fmt = utils::resolve_input_format(args.value_of("format").unwrap())?;
+    let input_path = args.value_of("input").unwrap();
+    let mut errors = utils::RecordErrors::from_args(args, input_path)?;
+    let input = utils::resolve_reader(input_path)?;
+    let mut writer = utils::resolve_writer(args.value_of("output").unwrap())?;
+    let output_fmt = match args.value_of("output_format").unwrap() {
+        "hisat2-ss" => JunctionFormat::Hisat2SpliceSites,
+        "hisat2-exon" => JunctionFormat::Hisat2Exons,
+        "regtools" => JunctionFormat::Regtools,
+        _ => JunctionFormat::Star,
+    };
+
+    let mut collector = JunctionCollector::new();
+    match input_fmt {
+        InputFormat::RefFlat => {
+            let mut reader = RefFlatReader::from_reader(input);
+            reader.error_policy(errors.policy());
+            for result in reader.transcripts_stream() {
+                match result {
+                    Ok(trx) => collector.add_transcript(&trx),
+                    Err(e) => errors.skip(e)?,
+                }
+            }
+            errors.write_report(reader.error_report())?;
+        },
+        InputFormat::Gff(gff_type) => {
+            let mut reader = GffReader::from_reader(input, gff_type);
+            reader.error_policy(errors.policy());
+            for result in reader.transcripts().map_err(|e| utils::input_error(input_path, e))? {
+                match result {
+                    Ok(trx) => collector.add_transcript(&trx),
+                    Err(e) => errors.skip(e)?,
+                }
+            }
+            errors.write_report(reader.error_report())?;
+        },
+    }
+
+    collector.write(&mut writer, output_fmt)?;
+    writer.finish()
+}
@@ -0,0 +1,170 @@
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+
+use clap::{App, Arg, ArgMatches, SubCommand};
+use gte::{self, GffReader, RefFlatReader, Transcript, TranscriptPos};
+
+use tools::TEMPLATE_SUBCMD;
+use utils::{self, InputFormat};
+
+pub const NAME: &'static str = "map-coords";
+
+/// Value written for positions that can not be mapped.
+const MISSING: &'static str = ".";
+
+
+pub fn build_cli<'a, 'b>() -> App<'a, 'b> {
+    SubCommand::with_name(NAME)
+        .about("Maps positions between the genome and transcripts using HGVS numbering")
+        .template(TEMPLATE_SUBCMD)
+        .arg(Arg::with_name("input")
+                .required(true)
+                .takes_value(true)
+                .help("Path to input annotation file"))
+        .arg(Arg::with_name("positions")
+                .required(true)
+                .takes_value(true)
+                .help("Path to input TSV file of transcript identifiers and one-based genomic, \
+                       'n.', or 'c.' positions, or '-' for stdin"))
+        .arg(Arg::with_name("output")
+                .default_value("-")
+                .takes_value(true)
+                .help("Path to output TSV file or '-' for stdout"))
+        .arg(Arg::with_name("format")
+                .short("-f")
+                .long("--format")
+                .required(true)
+                .takes_value(true)
+                .value_name("FORMAT")
+                .possible_values(&["refflat", "gff3", "gtf"])
+                .display_order(1)
+                .help("Input annotation format"))
+        .args(&utils::error_args(2))
+}
+
+pub fn run(args: &ArgMatches) -> ::Result<()> {
+
+    let input_fmt = utils::resolve_input_format(args.value_of("format").unwrap())?;
+    let input_path = args.value_of("input").unwrap();
+    let mut errors = utils::RecordErrors::from_args(args, input_path)?;
+    let input = utils::resolve_reader(input_path)?;
+    let positions = utils::resolve_reader(args.value_of("positions").unwrap())?;
+    let mut writer = utils::resolve_writer(args.value_of("output").unwrap())?;
+
+    let transcripts = match input_fmt {
+        InputFormat::RefFlat => {
+            let mut reader = RefFlatReader::from_reader(input);
+            reader.error_policy(errors.policy());
+            let transcripts =
+                collect_transcripts(&mut errors, reader.transcripts_stream())?;
+            errors.write_report(reader.error_report())?;
+            transcripts
+        },
+        InputFormat::Gff(gff_type) => {
+            let mut reader = GffReader::from_reader(input, gff_type);
+            reader.error_policy(errors.policy());
+            let transcripts = {
+                let results = reader.transcripts()
+                    .map_err(|e| utils::input_error(input_path, e))?;
+                collect_transcripts(&mut errors, results)?
+            };
+            errors.write_report(reader.error_report())?;
+            transcripts
+        },
+    };
+
+    writeln!(writer, "transcript_id\tquery\tseq_name\tpos\tcdna\tcds")?;
+    for line in BufReader::new(positions).lines() {
+        let line = line?;
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut cols = line.split('\t');
+        let (tid, query) = match (cols.next(), cols.next()) {
+            (Some(tid), Some(query)) => (tid, query.trim()),
+            _ => {
+                eprintln!("skipping: invalid row: '{}'", line);
+                continue;
+            },
+        };
+        let trx = match transcripts.get(tid) {
+            Some(trx) => trx,
+            None => {
+                eprintln!("skipping: unknown transcript: '{}'", tid);
+                continue;
+            },
+        };
+        match map_query(trx, query) {
+            Ok(genome_pos) => write_row(&mut writer, trx, tid, query, genome_pos)?,
+            Err(gte::Error::Mapping(me)) => eprintln!("skipping: {}", me),
+            Err(e) => return Err(::Error::from(e)),
+        }
+    }
+
+    writer.finish()
+}
+
+/// Collects the given transcript results of an input by their identifiers, skipping transcripts
+/// that can not be created unless the policy is strict.
+fn collect_transcripts<I>(
+    errors: &mut utils::RecordErrors,
+    results: I,
+) -> ::Result<HashMap<String, Transcript>>
+    where I: IntoIterator<Item=gte::Result<Transcript>>
+{
+    let mut transcripts = HashMap::new();
+    for result in results {
+        match result {
+            Ok(trx) => {
+                let tid = trx.id().map(|id| id.to_owned());
+                if let Some(tid) = tid {
+                    let _ = transcripts.insert(tid, trx);
+                }
+            },
+            Err(e) => errors.skip(e)?,
+        }
+    }
+    Ok(transcripts)
+}
+
+/// Resolves a query into a zero-based genome-wise position.
+///
+/// Queries starting with `c.` or `n.` are treated as CDS or cDNA positions. All other queries are
+/// treated as one-based genome-wise positions.
+fn map_query(trx: &Transcript, query: &str) -> gte::Result<Option<u64>> {
+    if query.starts_with("c.") {
+        Ok(trx.cds_to_genome(&query.parse::<TranscriptPos>()?))
+    } else if query.starts_with("n.") {
+        Ok(trx.cdna_to_genome(&query.parse::<TranscriptPos>()?))
+    } else {
+        query.replace(',', "").parse::<u64>().ok()
+            .and_then(|pos| pos.checked_sub(1))
+            .map(Some)
+            .ok_or_else(|| {
+                let err = gte::MappingError::InvalidPosition(query.to_owned());
+                gte::Error::from(err)
+            })
+    }
+}
+
+/// Writes the genome-wise, cDNA, and CDS positions of a query as a TSV row.
+fn write_row<W: Write>(
+    writer: &mut W,
+    trx: &Transcript,
+    tid: &str,
+    query: &str,
+    genome_pos: Option<u64>,
+) -> ::Result<()> {
+    let (pos, cdna, cds) = match genome_pos {
+        Some(gpos) => (
+            (gpos + 1).to_string(),
+            format!("n.{}", trx.genome_to_cdna(gpos)),
+            trx.genome_to_cds(gpos)
+                .map(|cpos| format!("c.{}", cpos))
+                .unwrap_or_else(|| MISSING.to_owned()),
+        ),
+        None => (MISSING.to_owned(), MISSING.to_owned(), MISSING.to_owned()),
+    };
+    writeln!(writer, "{}\t{}\t{}\t{}\t{}\t{}", tid, query, trx.seq_name(), pos, cdna, cds)?;
+    Ok(())
+}
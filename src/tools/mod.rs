@@ -2,6 +2,18 @@
 
 pub mod stats;
 pub mod gff_to_refflat;
+pub mod refflat_to_gff;
+pub mod gff_to_bed;
+pub mod refflat_to_bed;
+pub mod getseq;
+pub mod query;
+pub mod map_coords;
+pub mod junctions;
+pub mod flatten;
+pub mod to_saf;
+pub mod table;
+pub mod validate;
+pub mod index;
 
 const TEMPLATE_SUBCMD: &'static str = "
 Usage: {usage}
@@ -0,0 +1,206 @@
+use std::io::Write;
+
+use clap::{App, Arg, ArgMatches, SubCommand};
+use gte::{self, AnnotationIndex, GffReader, Indexable, RefFlatReader, Strand};
+
+use tools::TEMPLATE_SUBCMD;
+use utils::{self, InputFormat};
+
+pub const NAME: &'static str = "query";
+
+/// Name used for models without any identifiers.
+const UNKNOWN_ID: &'static str = ".";
+
+
+pub fn build_cli<'a, 'b>() -> App<'a, 'b> {
+    SubCommand::with_name(NAME)
+        .about("Finds genes, transcripts, or exons in a region and writes them as BED6")
+        .template(TEMPLATE_SUBCMD)
+        .arg(Arg::with_name("input")
+                .required(true)
+                .takes_value(true)
+                .help("Path to input annotation file or '-' for stdin"))
+        .arg(Arg::with_name("region")
+                .required(true)
+                .takes_value(true)
+                .help("Region to query, e.g. 'chr1:1,000,000-1,050,000', 'chr1:1000', or 'chr1'"))
+        .arg(Arg::with_name("output")
+                .default_value("-")
+                .takes_value(true)
+                .help("Path to output BED file or '-' for stdout"))
+        .arg(Arg::with_name("format")
+                .short("-f")
+                .long("--format")
+                .required(true)
+                .takes_value(true)
+                .value_name("FORMAT")
+                .possible_values(&["refflat", "gff3", "gtf"])
+                .display_order(1)
+                .help("Input annotation format"))
+        .arg(Arg::with_name("level")
+                .short("-l")
+                .long("--level")
+                .takes_value(true)
+                .value_name("LEVEL")
+                .default_value("transcript")
+                .possible_values(&["gene", "transcript", "exon"])
+                .display_order(2)
+                .help("Model level to query"))
+        .arg(Arg::with_name("mode")
+                .short("-m")
+                .long("--mode")
+                .takes_value(true)
+                .value_name("MODE")
+                .default_value("overlap")
+                .possible_values(&["overlap", "contained", "containing"])
+                .display_order(3)
+                .help("Whether to find models overlapping, contained in, or containing the region"))
+        .arg(Arg::with_name("strand")
+                .short("-s")
+                .long("--strand")
+                .takes_value(true)
+                .value_name("STRAND")
+                .possible_values(&["+", "-"])
+                .display_order(4)
+                .help("Only find models on the given strand"))
+        .args(&utils::error_args(5))
+}
+
+pub fn run(args: &ArgMatches) -> ::Result<()> {
+
+    let input_fmt = utils::resolve_input_format(args.value_of("format").unwrap())?;
+    let input_path = args.value_of("input").unwrap();
+    let mut errors = utils::RecordErrors::from_args(args, input_path)?;
+    let input = utils::resolve_reader(input_path)?;
+    let (seq_name, start, end) = utils::resolve_region(args.value_of("region").unwrap())?;
+    let strand = match args.value_of("strand") {
+        Some("+") => Some(Strand::Forward),
+        Some("-") => Some(Strand::Reverse),
+        _ => None,
+    };
+    let query = Query {
+        seq_name: seq_name,
+        start: start,
+        end: end,
+        strand: strand,
+        mode: args.value_of("mode").unwrap(),
+    };
+    let mut writer = utils::resolve_writer(args.value_of("output").unwrap())?;
+
+    match (input_fmt, args.value_of("level").unwrap()) {
+        (InputFormat::RefFlat, "gene") => {
+            let mut reader = RefFlatReader::from_reader(input);
+            reader.error_policy(errors.policy());
+            let index = build_index(&mut errors, reader.genes_stream())?;
+            errors.write_report(reader.error_report())?;
+            write_bed6(&mut writer, query.run(&index), |gx| gx.id())?;
+        },
+        (InputFormat::RefFlat, level) => {
+            let mut reader = RefFlatReader::from_reader(input);
+            reader.error_policy(errors.policy());
+            let index = build_index(&mut errors, reader.transcripts_stream())?;
+            errors.write_report(reader.error_report())?;
+            if level == "exon" {
+                let exons = query.exons(&index);
+                write_bed6(&mut writer, exons, |ex| ex.id().or(ex.transcript_id()))?;
+            } else {
+                write_bed6(&mut writer, query.run(&index), |trx| trx.id())?;
+            }
+        },
+        (InputFormat::Gff(gff_type), "gene") => {
+            let mut reader = GffReader::from_reader(input, gff_type);
+            reader.error_policy(errors.policy());
+            let index = {
+                let results = reader.genes().map_err(|e| utils::input_error(input_path, e))?;
+                build_index(&mut errors, results)?
+            };
+            errors.write_report(reader.error_report())?;
+            write_bed6(&mut writer, query.run(&index), |gx| gx.id())?;
+        },
+        (InputFormat::Gff(gff_type), level) => {
+            let mut reader = GffReader::from_reader(input, gff_type);
+            reader.error_policy(errors.policy());
+            let index = {
+                let results = reader.transcripts().map_err(|e| utils::input_error(input_path, e))?;
+                build_index(&mut errors, results)?
+            };
+            errors.write_report(reader.error_report())?;
+            if level == "exon" {
+                let exons = query.exons(&index);
+                write_bed6(&mut writer, exons, |ex| ex.id().or(ex.transcript_id()))?;
+            } else {
+                write_bed6(&mut writer, query.run(&index), |trx| trx.id())?;
+            }
+        },
+    }
+    writer.finish()
+}
+
+/// Region query values.
+struct Query<'a> {
+    seq_name: String,
+    start: u64,
+    end: u64,
+    strand: Option<Strand>,
+    mode: &'a str,
+}
+
+impl<'a> Query<'a> {
+
+    /// Runs the query on the given index.
+    fn run<'b, T: Indexable>(&self, index: &'b AnnotationIndex<T>) -> Vec<&'b T> {
+        let (seq_name, start, end) = (self.seq_name.as_str(), self.start, self.end);
+        match self.mode {
+            "contained" => index.contained(seq_name, start, end, self.strand.as_ref()),
+            "containing" => index.containing(seq_name, start, end, self.strand.as_ref()),
+            _ => index.overlapping(seq_name, start, end, self.strand.as_ref()),
+        }
+    }
+
+    /// Runs the query on the exons of the transcripts in the given index.
+    fn exons<'b>(&self, index: &'b AnnotationIndex<gte::Transcript>) -> Vec<&'b gte::Exon> {
+        let (start, end) = (self.start, self.end);
+        index.overlapping_exons(&self.seq_name, start, end, self.strand.as_ref()).into_iter()
+            .filter(|ex| match self.mode {
+                "contained" => ex.start() >= start && ex.end() <= end,
+                "containing" => ex.start() <= start && ex.end() >= end,
+                _ => true,
+            })
+            .collect()
+    }
+}
+
+/// Creates an index from the given model results of an input, skipping models that can not be
+/// created unless the policy is strict.
+fn build_index<T, I>(
+    errors: &mut utils::RecordErrors,
+    results: I,
+) -> ::Result<AnnotationIndex<T>>
+    where T: Indexable, I: IntoIterator<Item=gte::Result<T>>
+{
+    let mut models = vec![];
+    for result in results {
+        match result {
+            Ok(model) => models.push(model),
+            Err(e) => errors.skip(e)?,
+        }
+    }
+    Ok(AnnotationIndex::new(models))
+}
+
+/// Writes the given models as BED6 rows.
+fn write_bed6<W, T, F>(writer: &mut W, models: Vec<&T>, name_func: F) -> ::Result<()>
+    where W: Write, T: Indexable, F: Fn(&T) -> Option<&str>
+{
+    for model in models {
+        let strand_char = match model.strand() {
+            &Strand::Forward => '+',
+            &Strand::Reverse => '-',
+            &Strand::Unknown => '.',
+        };
+        writeln!(writer, "{}\t{}\t{}\t{}\t0\t{}",
+                 model.seq_name(), model.start(), model.end(),
+                 name_func(model).unwrap_or(UNKNOWN_ID), strand_char)?;
+    }
+    Ok(())
+}
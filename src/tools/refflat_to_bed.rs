@@ -0,0 +1,85 @@
+use clap::{App, Arg, ArgMatches, SubCommand};
+use gte::{BedWriter, RefFlatReader};
+
+use tools::TEMPLATE_SUBCMD;
+use utils;
+
+pub const NAME: &'static str = "refflat-to-bed";
+
+
+pub fn build_cli<'a, 'b>() -> App<'a, 'b> {
+    SubCommand::with_name(NAME)
+        .about("Converts from the refFlat format to BED12")
+        .template(TEMPLATE_SUBCMD)
+        .arg(Arg::with_name("input")
+                .required(true)
+                .takes_value(true)
+                .help("Path to input annotation file or '-' for stdin"))
+        .arg(Arg::with_name("output")
+                .required(true)
+                .takes_value(true)
+                .help("Path to output annotation file or '-' for stdout"))
+        .arg(Arg::with_name("seq_prefix")
+                .long("--seq-prefix")
+                .value_name("VALUE")
+                .takes_value(true)
+                .display_order(1)
+                .help("String to prepend to all sequence names"))
+        .arg(Arg::with_name("seq_lstrip")
+                .long("--seq-lstrip")
+                .value_name("VALUE")
+                .takes_value(true)
+                .display_order(2)
+                .help("Left-most string to remove from all sequence names"))
+        .arg(Arg::with_name("introns")
+                .long("--introns")
+                .display_order(3)
+                .takes_value(false)
+                .help("Write one BED6 row per intron instead of one BED12 row per transcript"))
+        .args(&utils::error_args(4))
+        .arg(Arg::with_name("compress")
+                .long("--compress")
+                .display_order(6)
+                .help("Write BGZF-compressed output, which can be indexed with tabix"))
+}
+
+pub fn run(args: &ArgMatches) -> ::Result<()> {
+
+    let input_path = args.value_of("input").unwrap();
+    let mut errors = utils::RecordErrors::from_args(args, input_path)?;
+    let mut reader = utils::resolve_reader(input_path)
+        .map(RefFlatReader::from_reader)?;
+
+    if let Some(prefix) = args.value_of("seq_prefix") {
+        reader.seq_name_prefix(prefix);
+    }
+    if let Some(lstrip) = args.value_of("seq_lstrip") {
+        reader.seq_name_lstrip(lstrip);
+    }
+    reader.error_policy(errors.policy());
+
+    let output_path = args.value_of("output").unwrap();
+    let mut output = utils::resolve_compressed_writer(output_path, args.is_present("compress"))?;
+    {
+        let mut writer = BedWriter::from_writer(&mut output);
+        let write_introns = args.is_present("introns");
+        for result in reader.transcripts_stream() {
+            let wresult = result
+                .and_then(|ref trx| {
+                    if write_introns {
+                        writer.write_introns(trx)
+                    } else {
+                        writer.write_transcript(trx)
+                    }
+                });
+            match wresult {
+                Ok(_) => {},
+                Err(e) => errors.skip(e)?,
+            }
+        }
+        writer.flush()?;
+    }
+    output.finish()?;
+
+    errors.write_report(reader.error_report())
+}
@@ -0,0 +1,84 @@
+use clap::{App, Arg, ArgMatches, SubCommand};
+use gte::{GffWriter, RefFlatReader};
+
+use tools::TEMPLATE_SUBCMD;
+use utils;
+
+pub const NAME: &'static str = "refflat-to-gff";
+
+
+pub fn build_cli<'a, 'b>() -> App<'a, 'b> {
+    SubCommand::with_name(NAME)
+        .about("Converts from the refFlat format to GTF or GFF3")
+        .template(TEMPLATE_SUBCMD)
+        .arg(Arg::with_name("input")
+                .required(true)
+                .takes_value(true)
+                .help("Path to input annotation file or '-' for stdin"))
+        .arg(Arg::with_name("output")
+                .required(true)
+                .takes_value(true)
+                .help("Path to output annotation file or '-' for stdout"))
+        .arg(Arg::with_name("gff_type")
+                .short("-t")
+                .long("--gff-type")
+                .required(true)
+                .takes_value(true)
+                .value_name("TYPE")
+                .possible_values(&["gff3", "gtf"])
+                .display_order(1)
+                .help("Output GFF variant"))
+        .arg(Arg::with_name("seq_prefix")
+                .long("--seq-prefix")
+                .value_name("VALUE")
+                .takes_value(true)
+                .display_order(2)
+                .help("String to prepend to all sequence names"))
+        .arg(Arg::with_name("seq_lstrip")
+                .long("--seq-lstrip")
+                .value_name("VALUE")
+                .takes_value(true)
+                .display_order(3)
+                .help("Left-most string to remove from all sequence names"))
+        .args(&utils::error_args(4))
+        .arg(Arg::with_name("compress")
+                .long("--compress")
+                .display_order(6)
+                .help("Write BGZF-compressed output, which can be indexed with tabix"))
+}
+
+pub fn run(args: &ArgMatches) -> ::Result<()> {
+
+    let gff_type = utils::resolve_gff_type(args.value_of("gff_type").unwrap())?;
+
+    let input_path = args.value_of("input").unwrap();
+    let mut errors = utils::RecordErrors::from_args(args, input_path)?;
+    let mut reader = utils::resolve_reader(input_path)
+        .map(RefFlatReader::from_reader)?;
+
+    if let Some(prefix) = args.value_of("seq_prefix") {
+        reader.seq_name_prefix(prefix);
+    }
+    if let Some(lstrip) = args.value_of("seq_lstrip") {
+        reader.seq_name_lstrip(lstrip);
+    }
+    reader.error_policy(errors.policy());
+
+    let output_path = args.value_of("output").unwrap();
+    let mut output = utils::resolve_compressed_writer(output_path, args.is_present("compress"))?;
+    {
+        let mut writer = GffWriter::from_writer(&mut output, gff_type);
+        for result in reader.genes_stream() {
+            let wresult = result
+                .and_then(|ref gx| writer.write_gene(gx));
+            match wresult {
+                Ok(_) => {},
+                Err(e) => errors.skip(e)?,
+            }
+        }
+        writer.flush()?;
+    }
+    output.finish()?;
+
+    errors.write_report(reader.error_report())
+}
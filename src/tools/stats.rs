@@ -1,9 +1,17 @@
+use std::collections::{BTreeMap, HashSet};
+use std::io::Write;
+
 use clap::{App, Arg, ArgMatches, SubCommand};
+use gte::{GffReader, RefFlatReader, Transcript};
 
 use tools::TEMPLATE_SUBCMD;
+use utils::{self, InputFormat};
 
 pub const NAME: &'static str = "stats";
 
+/// Gene identifier used for transcripts without any gene or transcript identifiers.
+const UNKNOWN_ID: &'static str = "<unknown>";
+
 
 pub fn build_cli<'a, 'b>() -> App<'a, 'b> {
     SubCommand::with_name(NAME)
@@ -14,8 +22,353 @@ pub fn build_cli<'a, 'b>() -> App<'a, 'b> {
                     .help("Path to input annotation file or '-' for stdin")
                     .takes_value(true)
                     .required(true))
+        .arg(Arg::with_name("output")
+                    .value_name("output")
+                    .default_value("-")
+                    .takes_value(true)
+                    .help("Path to output file or '-' for stdout"))
+        .arg(Arg::with_name("format")
+                .short("-f")
+                .long("--format")
+                .required(true)
+                .takes_value(true)
+                .value_name("FORMAT")
+                .possible_values(&["refflat", "gff3", "gtf"])
+                .display_order(1)
+                .help("Input annotation format"))
+        .arg(Arg::with_name("mode")
+                .short("-m")
+                .long("--mode")
+                .takes_value(true)
+                .value_name("MODE")
+                .default_value("text")
+                .possible_values(&["text", "tsv", "json"])
+                .display_order(2)
+                .help("Output mode"))
+        .args(&utils::error_args(3))
 }
 
 pub fn run(args: &ArgMatches) -> ::Result<()> {
-    unimplemented!()
+
+    let input_fmt = utils::resolve_input_format(args.value_of("format").unwrap())?;
+    let input_path = args.value_of("input").unwrap();
+    let mut errors = utils::RecordErrors::from_args(args, input_path)?;
+    let input = utils::resolve_reader(input_path)?;
+
+    let mut stats = Stats::default();
+
+    match input_fmt {
+        InputFormat::RefFlat => {
+            let mut reader = RefFlatReader::from_reader(input);
+            reader.error_policy(errors.policy());
+            for result in reader.transcripts_stream() {
+                match result {
+                    Ok(trx) => stats.add_transcript(&trx),
+                    Err(e) => errors.skip(e)?,
+                }
+            }
+            errors.write_report(reader.error_report())?;
+        },
+        InputFormat::Gff(gff_type) => {
+            let mut reader = GffReader::from_reader(input, gff_type);
+            reader.error_policy(errors.policy());
+            for result in reader.transcripts().map_err(|e| utils::input_error(input_path, e))? {
+                match result {
+                    Ok(trx) => stats.add_transcript(&trx),
+                    Err(e) => errors.skip(e)?,
+                }
+            }
+            errors.write_report(reader.error_report())?;
+        },
+    }
+
+    let mut writer = utils::resolve_writer(args.value_of("output").unwrap())?;
+    match args.value_of("mode").unwrap() {
+        "tsv" => stats.write_tsv(&mut writer)?,
+        "json" => stats.write_json(&mut writer)?,
+        _ => stats.write_text(&mut writer)?,
+    }
+    writer.finish()
+}
+
+/// Counts of features located in a single sequence.
+#[derive(Debug, Default)]
+struct SeqCounts {
+    gene_ids: HashSet<String>,
+    transcripts: usize,
+    coding_transcripts: usize,
+    exons: usize,
+}
+
+impl SeqCounts {
+
+    fn noncoding_transcripts(&self) -> usize {
+        self.transcripts - self.coding_transcripts
+    }
+}
+
+/// Summary values of a distribution.
+#[derive(Debug)]
+struct Summary {
+    count: usize,
+    min: u64,
+    median: f64,
+    max: u64,
+    n50: u64,
+}
+
+impl Summary {
+
+    /// Summarizes the given values, or returns `None` if there are no values.
+    fn from_values(values: &[u64]) -> Option<Summary> {
+        if values.is_empty() {
+            return None;
+        }
+        let mut sorted = values.to_vec();
+        sorted.sort();
+
+        let n = sorted.len();
+        let median =
+            if n % 2 == 0 {
+                (sorted[n / 2 - 1] + sorted[n / 2]) as f64 / 2.0
+            } else {
+                sorted[n / 2] as f64
+            };
+
+        let half_total = sorted.iter().fold(0, |acc, x| acc + x) as f64 / 2.0;
+        let mut cumsum = 0;
+        let mut n50 = sorted[n - 1];
+        for value in sorted.iter().rev() {
+            cumsum += *value;
+            if cumsum as f64 >= half_total {
+                n50 = *value;
+                break;
+            }
+        }
+
+        Some(Summary {
+            count: n,
+            min: sorted[0],
+            median: median,
+            max: sorted[n - 1],
+            n50: n50,
+        })
+    }
+}
+
+/// Statistics gathered from transcripts.
+#[derive(Debug, Default)]
+struct Stats {
+    gene_ids: HashSet<String>,
+    per_seq: BTreeMap<String, SeqCounts>,
+    transcript_spans: Vec<u64>,
+    spliced_lens: Vec<u64>,
+    coding_lens: Vec<u64>,
+    exon_counts: Vec<u64>,
+    intron_lens: Vec<u64>,
+}
+
+impl Stats {
+
+    fn add_transcript(&mut self, transcript: &Transcript) {
+        let gene_id = transcript.gene_id()
+            .or(transcript.id())
+            .unwrap_or(UNKNOWN_ID)
+            .to_owned();
+        let is_coding = transcript.is_coding();
+
+        {
+            let counts = self.per_seq.entry(transcript.seq_name().to_owned())
+                .or_insert_with(SeqCounts::default);
+            let _ = counts.gene_ids.insert(gene_id.clone());
+            counts.transcripts += 1;
+            counts.exons += transcript.exons().len();
+            if is_coding {
+                counts.coding_transcripts += 1;
+            }
+        }
+        let _ = self.gene_ids.insert(gene_id);
+
+        self.transcript_spans.push(transcript.span());
+        self.spliced_lens.push(transcript.spliced_len());
+        self.exon_counts.push(transcript.exons().len() as u64);
+        if is_coding {
+            self.coding_lens.push(transcript.coding_len());
+        }
+        for pair in transcript.exons().windows(2) {
+            if pair[0].end() < pair[1].start() {
+                self.intron_lens.push(pair[1].start() - pair[0].end());
+            }
+        }
+    }
+
+    /// Returns the counts summed over all sequences.
+    ///
+    /// The number of genes is counted from unique gene identifiers over all sequences.
+    fn total_counts(&self) -> (usize, usize, usize, usize, usize) {
+        let (trxs, coding, exons) = self.per_seq.values()
+            .fold((0, 0, 0), |acc, c| (acc.0 + c.transcripts,
+                                       acc.1 + c.coding_transcripts,
+                                       acc.2 + c.exons));
+        (self.gene_ids.len(), trxs, coding, trxs - coding, exons)
+    }
+
+    /// Returns the names and summaries of all distributions.
+    fn summaries(&self) -> Vec<(&'static str, Option<Summary>)> {
+        vec![
+            ("transcript_span", Summary::from_values(&self.transcript_spans)),
+            ("spliced_length", Summary::from_values(&self.spliced_lens)),
+            ("cds_length", Summary::from_values(&self.coding_lens)),
+            ("exon_count", Summary::from_values(&self.exon_counts)),
+            ("intron_length", Summary::from_values(&self.intron_lens)),
+        ]
+    }
+
+    fn write_text<W: Write>(&self, w: &mut W) -> ::Result<()> {
+        let (genes, trxs, coding, noncoding, exons) = self.total_counts();
+        writeln!(w, "Genes               {:>12}", genes)?;
+        writeln!(w, "Transcripts         {:>12}", trxs)?;
+        writeln!(w, "  coding            {:>12}", coding)?;
+        writeln!(w, "  non-coding        {:>12}", noncoding)?;
+        writeln!(w, "Exons               {:>12}", exons)?;
+
+        writeln!(w, "")?;
+        writeln!(w, "{:<20}{:>10}{:>12}{:>14}{:>12}{:>12}",
+                 "Distribution", "count", "min", "median", "max", "N50")?;
+        for (name, summary) in self.summaries() {
+            match summary {
+                Some(s) => writeln!(w, "{:<20}{:>10}{:>12}{:>14.1}{:>12}{:>12}",
+                                    name, s.count, s.min, s.median, s.max, s.n50)?,
+                None => writeln!(w, "{:<20}{:>10}{:>12}{:>14}{:>12}{:>12}",
+                                 name, 0, "-", "-", "-", "-")?,
+            }
+        }
+
+        writeln!(w, "")?;
+        writeln!(w, "{:<20}{:>10}{:>12}{:>14}{:>12}{:>12}",
+                 "Sequence", "genes", "transcripts", "coding", "non-coding", "exons")?;
+        for (seq_name, c) in self.per_seq.iter() {
+            writeln!(w, "{:<20}{:>10}{:>12}{:>14}{:>12}{:>12}",
+                     seq_name, c.gene_ids.len(), c.transcripts, c.coding_transcripts,
+                     c.noncoding_transcripts(), c.exons)?;
+        }
+        Ok(())
+    }
+
+    fn write_tsv<W: Write>(&self, w: &mut W) -> ::Result<()> {
+        let (genes, trxs, coding, noncoding, exons) = self.total_counts();
+        writeln!(w, "scope\tmetric\tvalue")?;
+        writeln!(w, "all\tgenes\t{}", genes)?;
+        writeln!(w, "all\ttranscripts\t{}", trxs)?;
+        writeln!(w, "all\tcoding_transcripts\t{}", coding)?;
+        writeln!(w, "all\tnoncoding_transcripts\t{}", noncoding)?;
+        writeln!(w, "all\texons\t{}", exons)?;
+        for (name, summary) in self.summaries() {
+            if let Some(s) = summary {
+                writeln!(w, "all\t{}.count\t{}", name, s.count)?;
+                writeln!(w, "all\t{}.min\t{}", name, s.min)?;
+                writeln!(w, "all\t{}.median\t{}", name, s.median)?;
+                writeln!(w, "all\t{}.max\t{}", name, s.max)?;
+                writeln!(w, "all\t{}.n50\t{}", name, s.n50)?;
+            } else {
+                writeln!(w, "all\t{}.count\t0", name)?;
+            }
+        }
+        for (seq_name, c) in self.per_seq.iter() {
+            writeln!(w, "{}\tgenes\t{}", seq_name, c.gene_ids.len())?;
+            writeln!(w, "{}\ttranscripts\t{}", seq_name, c.transcripts)?;
+            writeln!(w, "{}\tcoding_transcripts\t{}", seq_name, c.coding_transcripts)?;
+            writeln!(w, "{}\tnoncoding_transcripts\t{}", seq_name, c.noncoding_transcripts())?;
+            writeln!(w, "{}\texons\t{}", seq_name, c.exons)?;
+        }
+        Ok(())
+    }
+
+    fn write_json<W: Write>(&self, w: &mut W) -> ::Result<()> {
+        let (genes, trxs, coding, noncoding, exons) = self.total_counts();
+        writeln!(w, "{{")?;
+        writeln!(w, "  \"counts\": {{\"genes\": {}, \"transcripts\": {}, \
+                     \"coding_transcripts\": {}, \"noncoding_transcripts\": {}, \
+                     \"exons\": {}}},",
+                 genes, trxs, coding, noncoding, exons)?;
+
+        writeln!(w, "  \"distributions\": {{")?;
+        let summaries = self.summaries();
+        let n_summaries = summaries.len();
+        for (idx, (name, summary)) in summaries.into_iter().enumerate() {
+            let sep = if idx + 1 < n_summaries { "," } else { "" };
+            match summary {
+                Some(s) => writeln!(w, "    \"{}\": {{\"count\": {}, \"min\": {}, \
+                                        \"median\": {}, \"max\": {}, \"n50\": {}}}{}",
+                                    name, s.count, s.min, s.median, s.max, s.n50, sep)?,
+                None => writeln!(w, "    \"{}\": {{\"count\": 0}}{}", name, sep)?,
+            }
+        }
+        writeln!(w, "  }},")?;
+
+        writeln!(w, "  \"seq_names\": {{")?;
+        let n_seqs = self.per_seq.len();
+        for (idx, (seq_name, c)) in self.per_seq.iter().enumerate() {
+            let sep = if idx + 1 < n_seqs { "," } else { "" };
+            writeln!(w, "    \"{}\": {{\"genes\": {}, \"transcripts\": {}, \
+                         \"coding_transcripts\": {}, \"noncoding_transcripts\": {}, \
+                         \"exons\": {}}}{}",
+                     json_escape(seq_name), c.gene_ids.len(), c.transcripts,
+                     c.coding_transcripts, c.noncoding_transcripts(), c.exons, sep)?;
+        }
+        writeln!(w, "  }}")?;
+        writeln!(w, "}}")?;
+        Ok(())
+    }
+}
+
+/// Escapes the given string so that it can be written as a JSON string.
+fn json_escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Summary;
+
+    #[test]
+    fn summary_empty() {
+        assert!(Summary::from_values(&[]).is_none());
+    }
+
+    #[test]
+    fn summary_odd_count() {
+        let summary = Summary::from_values(&[10, 1, 4, 3, 2]).expect("a summary");
+        assert_eq!(summary.count, 5);
+        assert_eq!(summary.min, 1);
+        assert_eq!(summary.median, 3.0);
+        assert_eq!(summary.max, 10);
+        assert_eq!(summary.n50, 10);
+    }
+
+    #[test]
+    fn summary_even_count() {
+        let summary = Summary::from_values(&[5, 2, 4, 3]).expect("a summary");
+        assert_eq!(summary.count, 4);
+        assert_eq!(summary.min, 2);
+        assert_eq!(summary.median, 3.5);
+        assert_eq!(summary.max, 5);
+        assert_eq!(summary.n50, 4);
+    }
+
+    #[test]
+    fn summary_single_value() {
+        let summary = Summary::from_values(&[7]).expect("a summary");
+        assert_eq!(summary.median, 7.0);
+        assert_eq!(summary.n50, 7);
+    }
 }
@@ -0,0 +1,154 @@
+use std::io::Write;
+
+use clap::{App, Arg, ArgMatches, SubCommand};
+use gte::{Gene, GffReader, RefFlatReader, Strand, Transcript};
+
+use tools::TEMPLATE_SUBCMD;
+use utils::{self, InputFormat};
+
+pub const NAME: &'static str = "table";
+
+/// Value written for missing column values.
+const MISSING: &'static str = "NA";
+
+/// Columns written by the `tx2gene` preset.
+const TX2GENE_COLUMNS: &'static str = "transcript_id,gene_id";
+
+/// Columns written by default.
+const DEFAULT_COLUMNS: &'static str =
+    "transcript_id,gene_id,gene_name,gene_biotype,seq_name,strand,spliced_len,coding_len,\
+     num_exons";
+
+
+pub fn build_cli<'a, 'b>() -> App<'a, 'b> {
+    SubCommand::with_name(NAME)
+        .about("Writes a per-transcript TSV table, such as a tx2gene table for tximport")
+        .template(TEMPLATE_SUBCMD)
+        .arg(Arg::with_name("input")
+                .required(true)
+                .takes_value(true)
+                .help("Path to input annotation file or '-' for stdin"))
+        .arg(Arg::with_name("output")
+                .default_value("-")
+                .takes_value(true)
+                .help("Path to output TSV file or '-' for stdout"))
+        .arg(Arg::with_name("format")
+                .short("-f")
+                .long("--format")
+                .required(true)
+                .takes_value(true)
+                .value_name("FORMAT")
+                .possible_values(&["refflat", "gff3", "gtf"])
+                .display_order(1)
+                .help("Input annotation format"))
+        .arg(Arg::with_name("columns")
+                .short("-c")
+                .long("--columns")
+                .takes_value(true)
+                .value_name("COLUMNS")
+                .conflicts_with("tx2gene")
+                .display_order(2)
+                .long_help(
+                    "Comma-separated names of the columns to write. The names 'transcript_id', \
+                     'gene_id', 'seq_name', 'start', 'end', 'strand', 'spliced_len', \
+                     'coding_len', and 'num_exons' denote transcript fields, with one-based \
+                     start coordinates. Any other name denotes an attribute of the transcript \
+                     or, if the transcript does not have it, of its gene. Defaults to \
+                     'transcript_id,gene_id,gene_name,gene_biotype,seq_name,strand,spliced_len,\
+                     coding_len,num_exons'."))
+        .arg(Arg::with_name("tx2gene")
+                .long("--tx2gene")
+                .display_order(3)
+                .help("Only write the transcript and gene identifiers, for tximport"))
+        .arg(Arg::with_name("no_header")
+                .long("--no-header")
+                .display_order(4)
+                .help("Do not write the header row"))
+        .args(&utils::error_args(5))
+}
+
+pub fn run(args: &ArgMatches) -> ::Result<()> {
+
+    let input_fmt = utils::resolve_input_format(args.value_of("format").unwrap())?;
+    let input_path = args.value_of("input").unwrap();
+    let mut errors = utils::RecordErrors::from_args(args, input_path)?;
+    let input = utils::resolve_reader(input_path)?;
+    let mut writer = utils::resolve_writer(args.value_of("output").unwrap())?;
+    let raw_columns =
+        if args.is_present("tx2gene") {
+            TX2GENE_COLUMNS
+        } else {
+            args.value_of("columns").unwrap_or(DEFAULT_COLUMNS)
+        };
+    let columns = raw_columns.split(',')
+        .map(|col| col.trim())
+        .filter(|col| !col.is_empty())
+        .collect::<Vec<&str>>();
+    if columns.is_empty() {
+        return Err(::Error::Other("no columns specified"));
+    }
+
+    if !args.is_present("no_header") {
+        writeln!(writer, "{}", columns.join("\t"))?;
+    }
+    {
+        let mut write_gene = |gx: Gene| -> ::Result<()> {
+            for trx in gx.transcripts().values() {
+                let values = columns.iter()
+                    .map(|col| column_value(trx, &gx, col))
+                    .collect::<Vec<String>>();
+                writeln!(writer, "{}", values.join("\t"))?;
+            }
+            Ok(())
+        };
+
+        match input_fmt {
+            InputFormat::RefFlat => {
+                let mut reader = RefFlatReader::from_reader(input);
+                reader.error_policy(errors.policy());
+                for result in reader.genes_stream() {
+                    match result {
+                        Ok(gx) => write_gene(gx)?,
+                        Err(e) => errors.skip(e)?,
+                    }
+                }
+                errors.write_report(reader.error_report())?;
+            },
+            InputFormat::Gff(gff_type) => {
+                let mut reader = GffReader::from_reader(input, gff_type);
+                reader.error_policy(errors.policy());
+                for result in reader.genes().map_err(|e| utils::input_error(input_path, e))? {
+                    match result {
+                        Ok(gx) => write_gene(gx)?,
+                        Err(e) => errors.skip(e)?,
+                    }
+                }
+                errors.write_report(reader.error_report())?;
+            },
+        }
+    }
+    writer.finish()
+}
+
+/// Returns the value of the given column for a transcript.
+fn column_value(trx: &Transcript, gene: &Gene, column: &str) -> String {
+    let value = match column {
+        "transcript_id" => trx.id().map(|id| id.to_owned()),
+        "gene_id" => trx.gene_id().or_else(|| gene.id()).map(|id| id.to_owned()),
+        "seq_name" => Some(trx.seq_name().to_owned()),
+        "start" => Some((trx.start() + 1).to_string()),
+        "end" => Some(trx.end().to_string()),
+        "strand" => Some(match trx.strand() {
+            &Strand::Forward => "+".to_owned(),
+            &Strand::Reverse => "-".to_owned(),
+            &Strand::Unknown => ".".to_owned(),
+        }),
+        "spliced_len" => Some(trx.spliced_len().to_string()),
+        "coding_len" => Some(trx.coding_len().to_string()),
+        "num_exons" => Some(trx.exons().len().to_string()),
+        attr => trx.attributes().get_vec(attr)
+            .or_else(|| gene.attributes().get_vec(attr))
+            .map(|values| values.join(",")),
+    };
+    value.unwrap_or_else(|| MISSING.to_owned())
+}
@@ -0,0 +1,86 @@
+use clap::{App, Arg, ArgMatches, SubCommand};
+use gte::{GffReader, RefFlatReader, SafLevel, SafWriter};
+
+use tools::TEMPLATE_SUBCMD;
+use utils::{self, InputFormat};
+
+pub const NAME: &'static str = "to-saf";
+
+
+pub fn build_cli<'a, 'b>() -> App<'a, 'b> {
+    SubCommand::with_name(NAME)
+        .about("Converts from the refFlat, GTF, or GFF3 format to SAF for featureCounts")
+        .template(TEMPLATE_SUBCMD)
+        .arg(Arg::with_name("input")
+                .required(true)
+                .takes_value(true)
+                .help("Path to input annotation file or '-' for stdin"))
+        .arg(Arg::with_name("output")
+                .default_value("-")
+                .takes_value(true)
+                .help("Path to output SAF file or '-' for stdout"))
+        .arg(Arg::with_name("format")
+                .short("-f")
+                .long("--format")
+                .required(true)
+                .takes_value(true)
+                .value_name("FORMAT")
+                .possible_values(&["refflat", "gff3", "gtf"])
+                .display_order(1)
+                .help("Input annotation format"))
+        .arg(Arg::with_name("level")
+                .short("-l")
+                .long("--level")
+                .takes_value(true)
+                .value_name("LEVEL")
+                .default_value("exon")
+                .possible_values(&["exon", "gene", "cds"])
+                .display_order(2)
+                .help("Whether to write one row per unique exon, per gene, or per unique CDS"))
+        .args(&utils::error_args(3))
+}
+
+pub fn run(args: &ArgMatches) -> ::Result<()> {
+
+    let input_fmt = utils::resolve_input_format(args.value_of("format").unwrap())?;
+    let input_path = args.value_of("input").unwrap();
+    let mut errors = utils::RecordErrors::from_args(args, input_path)?;
+    let input = utils::resolve_reader(input_path)?;
+    let mut output = utils::resolve_writer(args.value_of("output").unwrap())?;
+    let level = match args.value_of("level").unwrap() {
+        "gene" => SafLevel::Gene,
+        "cds" => SafLevel::CDS,
+        _ => SafLevel::Exon,
+    };
+    {
+        let mut writer = SafWriter::from_writer(&mut output);
+        writer.level(level);
+
+        match input_fmt {
+            InputFormat::RefFlat => {
+                let mut reader = RefFlatReader::from_reader(input);
+                reader.error_policy(errors.policy());
+                for result in reader.genes_stream() {
+                    match result.and_then(|gx| writer.write_gene(&gx)) {
+                        Ok(_) => {},
+                        Err(e) => errors.skip(e)?,
+                    }
+                }
+                errors.write_report(reader.error_report())?;
+            },
+            InputFormat::Gff(gff_type) => {
+                let mut reader = GffReader::from_reader(input, gff_type);
+                reader.error_policy(errors.policy());
+                for result in reader.genes().map_err(|e| utils::input_error(input_path, e))? {
+                    match result.and_then(|gx| writer.write_gene(&gx)) {
+                        Ok(_) => {},
+                        Err(e) => errors.skip(e)?,
+                    }
+                }
+                errors.write_report(reader.error_report())?;
+            },
+        }
+        writer.flush()?;
+    }
+    output.finish()
+}
@@ -0,0 +1,461 @@
+use std::cmp::max;
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{self, Write};
+
+use clap::{App, Arg, ArgMatches, SubCommand};
+use gte::{self, ExonFeatureKind, Gene, GffError, GffReader, IdLines, ModelError, RefFlatError,
+          RefFlatReader, Transcript};
+
+use tools::TEMPLATE_SUBCMD;
+use utils::{self, InputFormat};
+
+pub const NAME: &'static str = "validate";
+
+/// Value written for issues without any known identifiers or lines.
+const MISSING: &'static str = ".";
+
+
+pub fn build_cli<'a, 'b>() -> App<'a, 'b> {
+    SubCommand::with_name(NAME)
+        .about("Checks an annotation file and reports its invalid or suspicious models")
+        .template(TEMPLATE_SUBCMD)
+        .arg(Arg::with_name("input")
+                .required(true)
+                .takes_value(true)
+                .help("Path to input annotation file or '-' for stdin"))
+        .arg(Arg::with_name("output")
+                .default_value("-")
+                .takes_value(true)
+                .help("Path to output TSV report or '-' for stdout"))
+        .arg(Arg::with_name("format")
+                .short("-f")
+                .long("--format")
+                .required(true)
+                .takes_value(true)
+                .value_name("FORMAT")
+                .possible_values(&["refflat", "gff3", "gtf"])
+                .display_order(1)
+                .help("Input annotation format"))
+        .arg(Arg::with_name("summary")
+                .short("-s")
+                .long("--summary")
+                .takes_value(true)
+                .value_name("FILE")
+                .display_order(2)
+                .help("Path to output TSV file of issue counts per kind, instead of stderr"))
+}
+
+pub fn run(args: &ArgMatches) -> ::Result<()> {
+
+    let input_fmt = utils::resolve_input_format(args.value_of("format").unwrap())?;
+    let reader = utils::resolve_reader(args.value_of("input").unwrap())?;
+    let mut writer = utils::resolve_writer(args.value_of("output").unwrap())?;
+
+    let validator = validate(reader, input_fmt)?;
+
+    writeln!(writer, "kind\tid\tlines\tmessage")?;
+    for issue in validator.issues.iter() {
+        let lines = if issue.lines.is_empty() {
+            MISSING.to_owned()
+        } else {
+            issue.lines.iter().map(|n| n.to_string()).collect::<Vec<String>>().join(",")
+        };
+        writeln!(writer, "{}\t{}\t{}\t{}",
+                 issue.kind, issue.id.as_ref().map(|id| id.as_str()).unwrap_or(MISSING),
+                 lines, issue.message)?;
+    }
+
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for issue in validator.issues.iter() {
+        *counts.entry(issue.kind.as_str()).or_insert(0) += 1;
+    }
+    let mut summary_writer = match args.value_of("summary") {
+        Some(path) => utils::resolve_writer(path)?,
+        None => utils::Output::Plain(Box::new(io::stderr())),
+    };
+    writeln!(summary_writer, "kind\tcount")?;
+    for (kind, count) in counts.iter() {
+        writeln!(summary_writer, "{}\t{}", kind, count)?;
+    }
+    writer.finish()?;
+    summary_writer.finish()?;
+
+    if validator.issues.is_empty() {
+        Ok(())
+    } else {
+        Err(::Error::Other("annotation has issues"))
+    }
+}
+
+/// Checks the given annotation and returns the validator with all issues found.
+///
+/// The input is read once, as genes. Transcripts are checked as part of their genes, so the
+/// transcripts of genes that can not be created are not checked further.
+fn validate<R: io::Read>(input: R, input_fmt: InputFormat) -> ::Result<Validator> {
+    let mut validator = Validator::new();
+    match input_fmt {
+        InputFormat::RefFlat => {
+            let mut reader = RefFlatReader::from_reader(input);
+            for result in reader.record_id_lines(true).genes_stream() {
+                validator.check_gene(result)?;
+            }
+            validator.finish(reader.id_lines());
+        },
+        InputFormat::Gff(gff_type) => {
+            let mut reader = GffReader::from_reader(input, gff_type);
+            match reader.record_id_lines(true).genes() {
+                Ok(results) => {
+                    for result in results {
+                        validator.check_gene(result)?;
+                    }
+                },
+                Err(e) => validator.add_error(e)?,
+            }
+            validator.finish(reader.id_lines());
+        },
+    }
+    Ok(validator)
+}
+
+/// A single problem found in the annotation.
+#[derive(Debug)]
+struct Issue {
+    kind: String,
+    id: Option<String>,
+    /// Line numbers of the issue, either from its error or from the rows of its identifier.
+    lines: Vec<usize>,
+    message: String,
+}
+
+/// Checker of transcripts and genes, which collects their issues.
+struct Validator {
+    issues: Vec<Issue>,
+    /// Number of times each transcript identifier was seen.
+    transcript_ids: BTreeMap<String, usize>,
+    /// Sequence names of each gene identifier.
+    gene_seq_names: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl Validator {
+
+    fn new() -> Validator {
+        Validator {
+            issues: vec![],
+            transcript_ids: BTreeMap::new(),
+            gene_seq_names: BTreeMap::new(),
+        }
+    }
+
+    fn add_issue<T: Into<String>>(&mut self, kind: &str, id: Option<&str>, message: T) {
+        self.issues.push(Issue {
+            kind: kind.to_owned(),
+            id: id.map(|v| v.to_owned()),
+            lines: vec![],
+            message: message.into(),
+        });
+    }
+
+    /// Records the errors returned by the readers.
+    ///
+    /// Errors unrelated to the annotation contents, such as IO errors, are returned.
+    fn add_error(&mut self, err: gte::Error) -> ::Result<()> {
+        let kind_id = match *err.inner() {
+            gte::Error::Model(ref me) => Some((model_error_kind(me), model_error_id(me))),
+            gte::Error::Gff(ref gffe) =>
+                gff_error_kind(gffe).map(|kind| (kind, gff_error_id(gffe))),
+            gte::Error::RefFlat(ref rfe) =>
+                refflat_error_kind(rfe).map(|kind| (kind, refflat_error_id(rfe))),
+            gte::Error::InvalidUtf8 => Some(("InvalidUtf8", None)),
+            _ => None,
+        };
+        match kind_id {
+            Some((kind, id)) => {
+                let message = match err.field() {
+                    Some(field) => format!("field '{}': {}", field, err.inner()),
+                    None => err.inner().to_string(),
+                };
+                self.add_issue(kind, id.as_ref().map(|v| v.as_str()), message);
+                if let Some(issue) = self.issues.last_mut() {
+                    issue.lines = err.line().into_iter().collect();
+                }
+                Ok(())
+            },
+            None => Err(::Error::from(err)),
+        }
+    }
+
+    /// Runs all transcript checks on the given transcript.
+    fn check_transcript(&mut self, trx: &Transcript) {
+        let tid = trx.id().map(|id| id.to_owned());
+        let tid = tid.as_ref().map(|id| id.as_str());
+        if let Some(id) = tid {
+            *self.transcript_ids.entry(id.to_owned()).or_insert(0) += 1;
+        }
+
+        let mut exon_coords = trx.exons().iter()
+            .map(|exon| (exon.start(), exon.end()))
+            .collect::<Vec<(u64, u64)>>();
+        exon_coords.sort();
+        for &(start, end) in exon_coords.iter() {
+            if start == end {
+                self.add_issue("ZeroLengthExon", tid, format!("zero-length exon at {}", start));
+            }
+        }
+        for (prev, next) in exon_coords.iter().zip(exon_coords.iter().skip(1)) {
+            if next.0 < prev.1 {
+                self.add_issue("OverlappingExons", tid,
+                               format!("exons {}-{} and {}-{} overlap",
+                                       prev.0, prev.1, next.0, next.1));
+            }
+        }
+
+        let coding_len = trx.exons().iter()
+            .flat_map(|exon| exon.features().iter())
+            .filter(|fx| match fx.kind() {
+                &ExonFeatureKind::CDS { .. } | &ExonFeatureKind::StopCodon { .. } => true,
+                _ => false,
+            })
+            .map(|fx| fx.span())
+            .fold(0, |acc, x| acc + x);
+        if coding_len % 3 != 0 {
+            self.add_issue("CodingLengthNotMultipleOfThree", tid,
+                           format!("coding length {} is not divisible by three", coding_len));
+        }
+    }
+
+    /// Runs all gene checks on the given reader result, along with the checks of its
+    /// transcripts.
+    fn check_gene(&mut self, result: gte::Result<Gene>) -> ::Result<()> {
+        let gx = match result {
+            Ok(gx) => gx,
+            Err(e) => return self.add_error(e),
+        };
+        if let Some(gid) = gx.id() {
+            let seq_names = self.gene_seq_names.entry(gid.to_owned())
+                .or_insert_with(BTreeSet::new);
+            let _ = seq_names.insert(gx.seq_name().to_owned());
+            for trx in gx.transcripts().values() {
+                let _ = seq_names.insert(trx.seq_name().to_owned());
+            }
+        }
+        for trx in gx.transcripts().values() {
+            self.check_transcript(trx);
+        }
+        Ok(())
+    }
+
+    /// Checks identifiers across all transcripts and genes, and locates the issues without
+    /// line numbers by the rows of their identifiers.
+    ///
+    /// Transcript identifiers are duplicated if they occur in more than one transcript, or in
+    /// more than one GFF row that defines a transcript. GTF transcripts without `transcript`
+    /// rows that share an identifier on the same sequence and strand can not be told apart,
+    /// and are not reported.
+    fn finish(&mut self, id_lines: &IdLines) {
+        let mut counts = self.transcript_ids.clone();
+        for (tid, &num_rows) in id_lines.transcript_rows() {
+            let count = counts.entry(tid.clone()).or_insert(0);
+            *count = max(*count, num_rows);
+        }
+        for (tid, count) in counts.into_iter().filter(|&(_, count)| count > 1) {
+            self.add_issue("DuplicateTranscriptId", Some(&tid),
+                           format!("transcript ID occurs in {} transcripts", count));
+        }
+        let multi_seqs = self.gene_seq_names.iter()
+            .filter(|&(_, seq_names)| seq_names.len() > 1)
+            .map(|(gid, seq_names)| {
+                let names = seq_names.iter().map(|v| v.as_str()).collect::<Vec<&str>>();
+                (gid.clone(), names.join(","))
+            })
+            .collect::<Vec<(String, String)>>();
+        for (gid, seq_names) in multi_seqs {
+            self.add_issue("GeneOnMultipleSequences", Some(&gid),
+                           format!("gene lies on multiple sequences: {}", seq_names));
+        }
+        for issue in self.issues.iter_mut().filter(|issue| issue.lines.is_empty()) {
+            if let Some(lines) = issue.id.as_ref().and_then(|id| id_lines.get(id)) {
+                issue.lines = lines.iter().cloned().collect();
+            }
+        }
+    }
+}
+
+/// Returns the issue kind of the given model error.
+fn model_error_kind(err: &ModelError) -> &'static str {
+    match err {
+        &ModelError::InvalidInterval(_) => "InvalidInterval",
+        &ModelError::InvalidStrandChar(_) => "InvalidStrandChar",
+        &ModelError::ConflictingStrand => "ConflictingStrand",
+        &ModelError::UnspecifiedStrand => "UnspecifiedStrand",
+        &ModelError::InvalidExonInterval(_) => "InvalidExonInterval",
+        &ModelError::InvalidCodingInterval(_) => "InvalidCodingInterval",
+        &ModelError::UnspecifiedExons(_) => "UnspecifiedExons",
+        &ModelError::UnmatchedExons(_) => "UnmatchedExons",
+        &ModelError::CodingTooLarge(_) => "CodingTooLarge",
+        &ModelError::CodingTooSmall(_) => "CodingTooSmall",
+        &ModelError::CodingNotFullyEnveloped(_) => "CodingNotFullyEnveloped",
+        &ModelError::CodingInIntron(_) => "CodingInIntron",
+        &ModelError::TranscriptNotFullyEnveloped(_) => "TranscriptNotFullyEnveloped",
+    }
+}
+
+/// Returns the issue kind of the given GFF error, or `None` if it is unrelated to the contents.
+fn gff_error_kind(err: &GffError) -> Option<&'static str> {
+    let kind = match err {
+        &GffError::MissingGeneId => "MissingGeneId",
+        &GffError::MissingTranscriptId => "MissingTranscriptId",
+        &GffError::MultipleTranscriptIds => "MultipleTranscriptIds",
+        &GffError::StopCodonInCds(_) => "StopCodonInCds",
+        &GffError::MissingTranscript(_) => "MissingTranscript",
+        &GffError::MultipleTranscripts => "MultipleTranscripts",
+        &GffError::OrphanStop(_) => "OrphanStop",
+        &GffError::OrphanStart(_) => "OrphanStart",
+        &GffError::OrphanCodon(_) => "OrphanCodon",
+        &GffError::OrphanCds(_) => "OrphanCds",
+        &GffError::UngroupedTranscript(_) => "UngroupedTranscript",
+        &GffError::UnsupportedGffType => "UnsupportedGffType",
+        &GffError::InvalidColumnCount(_) => "InvalidColumnCount",
+        &GffError::InvalidCoord(_) => "InvalidCoord",
+        &GffError::InvalidStrand(_) => "InvalidStrand",
+        &GffError::Regex(_) => "Regex",
+        &GffError::Csv(_) => "Csv",
+        &GffError::Bio(_) => "Bio",
+        &GffError::Io(_) => return None,
+    };
+    Some(kind)
+}
+
+/// Returns the issue kind of the given refFlat error, or `None` if it is unrelated to the
+/// contents.
+fn refflat_error_kind(err: &RefFlatError) -> Option<&'static str> {
+    let kind = match err {
+        &RefFlatError::ExonCountMismatch(_) => "ExonCountMismatch",
+        &RefFlatError::DuplicateTranscriptId(_) => "DuplicateTranscriptId",
+        &RefFlatError::MissingGeneId => "MissingGeneId",
+        &RefFlatError::MissingTranscriptId => "MissingTranscriptId",
+        &RefFlatError::InvalidExonCoord(_, _) => "InvalidExonCoord",
+        &RefFlatError::InvalidNumber(_, _) => "InvalidNumber",
+        &RefFlatError::InvalidStrand(_) => "InvalidStrand",
+        &RefFlatError::InvalidCdsStat(_, _) => "InvalidCdsStat",
+        &RefFlatError::InvalidColumnCount(_) => "InvalidColumnCount",
+        &RefFlatError::Csv(_) => "Csv",
+        &RefFlatError::Io(_) => return None,
+    };
+    Some(kind)
+}
+
+/// Returns the transcript identifier of the given model error, if any.
+fn model_error_id(err: &ModelError) -> Option<String> {
+    match err {
+        &ModelError::InvalidExonInterval(ref tid) |
+        &ModelError::InvalidCodingInterval(ref tid) |
+        &ModelError::UnspecifiedExons(ref tid) |
+        &ModelError::UnmatchedExons(ref tid) |
+        &ModelError::CodingTooLarge(ref tid) |
+        &ModelError::CodingTooSmall(ref tid) |
+        &ModelError::CodingNotFullyEnveloped(ref tid) |
+        &ModelError::CodingInIntron(ref tid) |
+        &ModelError::TranscriptNotFullyEnveloped(ref tid) => tid.clone(),
+        _ => None,
+    }
+}
+
+/// Returns the transcript identifier of the given GFF error, if any.
+fn gff_error_id(err: &GffError) -> Option<String> {
+    match err {
+        &GffError::StopCodonInCds(ref tid) |
+        &GffError::MissingTranscript(ref tid) |
+        &GffError::OrphanStop(ref tid) |
+        &GffError::OrphanStart(ref tid) |
+        &GffError::OrphanCodon(ref tid) |
+        &GffError::OrphanCds(ref tid) |
+        &GffError::UngroupedTranscript(ref tid) => tid.clone(),
+        _ => None,
+    }
+}
+
+/// Returns the transcript or gene identifier of the given refFlat error, if any.
+fn refflat_error_id(err: &RefFlatError) -> Option<String> {
+    match err {
+        &RefFlatError::ExonCountMismatch(ref tid) |
+        &RefFlatError::DuplicateTranscriptId(ref tid) |
+        &RefFlatError::InvalidExonCoord(_, ref tid) |
+        &RefFlatError::InvalidNumber(_, ref tid) |
+        &RefFlatError::InvalidStrand(ref tid) |
+        &RefFlatError::InvalidCdsStat(_, ref tid) => tid.clone(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use gte::{self, GffError, GffType};
+
+    use utils::InputFormat;
+    use super::{validate, Validator};
+
+    fn issue_kinds(validator: &Validator) -> Vec<&str> {
+        validator.issues.iter().map(|issue| issue.kind.as_str()).collect()
+    }
+
+    #[test]
+    fn refflat_duplicate_transcript_id() {
+        let rows = "DDX11L1\tNR_046018\tchr1\t+\t11873\t14409\t14409\t14409\t1\t11873,\t14409,\n\
+                    DDX11L2\tNR_046018\tchr2\t+\t11873\t14409\t14409\t14409\t1\t11873,\t14409,\n";
+        let validator = validate(rows.as_bytes(), InputFormat::RefFlat).expect("a validator");
+        assert_eq!(issue_kinds(&validator), vec!["DuplicateTranscriptId"]);
+        assert_eq!(validator.issues[0].id, Some("NR_046018".to_owned()));
+        assert_eq!(validator.issues[0].lines, vec![1, 2]);
+    }
+
+    #[test]
+    fn refflat_exon_count_mismatch() {
+        let rows = "DDX11L1\tNR_046018\tchr1\t+\t11873\t14409\t14409\t14409\t2\t11873,\t14409,\n";
+        let validator = validate(rows.as_bytes(), InputFormat::RefFlat).expect("a validator");
+        assert_eq!(issue_kinds(&validator), vec!["ExonCountMismatch"]);
+        assert_eq!(validator.issues[0].lines, vec![1]);
+    }
+
+    #[test]
+    fn refflat_invalid_utf8() {
+        let rows = b"DDX11L1\tNR_046018\tchr1\t+\t11873\t14409\t14409\t14409\t1\t\xff,\t14409,\n";
+        let validator = validate(rows, InputFormat::RefFlat).expect("a validator");
+        assert_eq!(issue_kinds(&validator), vec!["InvalidUtf8"]);
+        assert_eq!(validator.issues[0].lines, vec![1]);
+    }
+
+    #[test]
+    fn gff3_duplicate_transcript_rows() {
+        let rows = "chr1\tsrc\tmRNA\t101\t500\t.\t+\t.\tID=rna-1;Parent=gene-1\n\
+                    chr1\tsrc\texon\t101\t500\t.\t+\t.\tID=exon-1;Parent=rna-1\n\
+                    chr1\tsrc\tmRNA\t601\t900\t.\t+\t.\tID=rna-1;Parent=gene-1\n\
+                    chr1\tsrc\texon\t601\t900\t.\t+\t.\tID=exon-2;Parent=rna-1\n";
+        let input_fmt = InputFormat::Gff(GffType::GFF3);
+        let validator = validate(rows.as_bytes(), input_fmt).expect("a validator");
+        assert_eq!(issue_kinds(&validator), vec!["MultipleTranscripts", "DuplicateTranscriptId"]);
+        assert_eq!(validator.issues[0].lines, vec![1]);
+        assert_eq!(validator.issues[1].id, Some("rna-1".to_owned()));
+        assert_eq!(validator.issues[1].lines, vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn gtf_transcript_rows() {
+        let rows = "chr1\tsrc\ttranscript\t101\t500\t.\t+\t.\t\
+                    gene_id \"gene-1\"; transcript_id \"rna-1\";\n\
+                    chr1\tsrc\texon\t101\t500\t.\t+\t.\t\
+                    gene_id \"gene-1\"; transcript_id \"rna-1\";\n";
+        let input_fmt = InputFormat::Gff(GffType::GTF2);
+        let validator = validate(rows.as_bytes(), input_fmt).expect("a validator");
+        assert!(validator.issues.is_empty());
+    }
+
+    #[test]
+    fn io_error_is_fatal() {
+        let mut validator = Validator::new();
+        let err = io::Error::new(io::ErrorKind::Other, "broken");
+        assert!(validator.add_error(gte::Error::from(GffError::Io(err))).is_err());
+        assert!(validator.issues.is_empty());
+    }
+}
@@ -1,31 +1,101 @@
 use std::fs;
 use std::io::{self, BufReader, BufWriter, Read, Write};
 
-use gte::GffType;
+use clap::{Arg, ArgMatches};
+use gte::{self, BedError, BgzfWriter, ErrorPolicy, ErrorReport, GffError, GffType, GzipReader,
+          RefFlatError, SeqError};
 
 use Error;
 
 
 const STREAM_ARG: &'static str = "-";
 
+/// Name shown in diagnostics for inputs read from stdin.
+const STREAM_NAME: &'static str = "<stdin>";
 
+/// Value written for missing values in error reports.
+const MISSING: &'static str = ".";
+
+/// Extensions of output paths that are written as BGZF.
+const BGZF_EXTS: [&'static str; 2] = [".gz", ".bgz"];
+
+
+/// Resolves a reader of the given path or stdin.
+///
+/// Gzip and BGZF-compressed input is decompressed transparently.
 pub fn resolve_reader(raw_arg: &str) -> ::Result<Box<Read>>
 {
-    match raw_arg {
-        STREAM_ARG => Ok(Box::new(io::stdin())),
+    let reader = match raw_arg {
+        STREAM_ARG => Box::new(io::stdin()) as Box<Read>,
         path => fs::File::open(path)
-            .map_err(::Error::from)
-            .map(|file| Box::new(BufReader::new(file)) as Box<Read>)
+            .map(|file| Box::new(BufReader::new(file)) as Box<Read>)?,
+    };
+    GzipReader::from_reader(reader)
+        .map_err(::Error::from)
+        .map(|reader| Box::new(reader) as Box<Read>)
+}
+
+/// Output of a tool, which may be written as BGZF.
+///
+/// `finish` must be called after everything has been written, since errors when writing the
+/// remaining buffered bytes are otherwise lost.
+pub enum Output {
+    Plain(Box<Write>),
+    Bgzf(BgzfWriter<Box<Write>>),
+}
+
+impl Output {
+
+    /// Writes all buffered bytes and, for BGZF output, the end-of-file marker block.
+    pub fn finish(&mut self) -> ::Result<()> {
+        let result = match *self {
+            Output::Plain(ref mut w) => w.flush(),
+            Output::Bgzf(ref mut w) => w.finish(),
+        };
+        result.map_err(::Error::from)
+    }
+}
+
+impl Write for Output {
+
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match *self {
+            Output::Plain(ref mut w) => w.write(buf),
+            Output::Bgzf(ref mut w) => w.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match *self {
+            Output::Plain(ref mut w) => w.flush(),
+            Output::Bgzf(ref mut w) => w.flush(),
+        }
     }
 }
 
-pub fn resolve_writer(raw_arg: &str) -> ::Result<Box<Write>>
+/// Resolves a writer of the given path or stdout.
+///
+/// Paths ending with `.gz` or `.bgz` are written as BGZF.
+pub fn resolve_writer(raw_arg: &str) -> ::Result<Output>
+{
+    resolve_compressed_writer(raw_arg, false)
+}
+
+/// Resolves a writer of the given path or stdout, which writes BGZF if `compress` is set.
+///
+/// Paths ending with `.gz` or `.bgz` are always written as BGZF.
+pub fn resolve_compressed_writer(raw_arg: &str, compress: bool) -> ::Result<Output>
 {
-    match raw_arg {
-        STREAM_ARG => Ok(Box::new(io::stdout())),
-        path => fs::File::create(path)
-            .map_err(::Error::from)
-            .map(|file| Box::new(BufWriter::new(file)) as Box<Write>)
+    let compress = compress || BGZF_EXTS.iter().any(|ext| raw_arg.ends_with(ext));
+    let writer: Box<Write> = match raw_arg {
+        STREAM_ARG => Box::new(io::stdout()),
+        path if compress => Box::new(fs::File::create(path)?),
+        path => Box::new(BufWriter::new(fs::File::create(path)?)),
+    };
+    if compress {
+        Ok(Output::Bgzf(BgzfWriter::from_writer(writer)))
+    } else {
+        Ok(Output::Plain(writer))
     }
 }
 
@@ -38,3 +108,197 @@ pub fn resolve_gff_type(raw_arg: &str) -> ::Result<GffType> {
         _ => Err(Error::Other("invalid gff type")),
     }
 }
+
+/// Annotation formats that can be read by the tools.
+#[derive(Debug, Clone, Copy)]
+pub enum InputFormat {
+    RefFlat,
+    Gff(GffType),
+}
+
+pub fn resolve_input_format(raw_arg: &str) -> ::Result<InputFormat> {
+    match raw_arg.to_owned().to_lowercase().as_str() {
+        "refflat" => Ok(InputFormat::RefFlat),
+        otherwise => resolve_gff_type(otherwise)
+            .map(InputFormat::Gff)
+            .map_err(|_| Error::Other("invalid input format")),
+    }
+}
+
+/// Parses a region string into its sequence name and zero-based, half-open coordinates.
+///
+/// Regions are written as one-based, inclusive coordinates, e.g. `chr1:1,000,000-1,050,000`.
+/// Single positions such as `chr1:1000` and whole sequences such as `chr1` are also accepted.
+pub fn resolve_region(raw_arg: &str) -> ::Result<(String, u64, u64)> {
+
+    let invalid = || Error::Other("invalid region");
+    let parse_coord = |raw: &str| raw.replace(',', "").parse::<u64>()
+        .map_err(|_| invalid())
+        .and_then(|coord| if coord == 0 { Err(invalid()) } else { Ok(coord) });
+
+    let mut parts = raw_arg.rsplitn(2, ':');
+    let (coords, seq_name) = match (parts.next(), parts.next()) {
+        (Some(coords), Some(seq_name)) => (coords, seq_name),
+        (Some(seq_name), None) => return Ok((seq_name.to_owned(), 0, ::std::u64::MAX)),
+        _ => return Err(invalid()),
+    };
+
+    let mut coord_parts = coords.splitn(2, '-');
+    let start = parse_coord(coord_parts.next().unwrap_or(""))?;
+    let end = match coord_parts.next() {
+        Some(raw_end) => parse_coord(raw_end)?,
+        None => start,
+    };
+    if seq_name.is_empty() || end < start {
+        return Err(invalid());
+    }
+    Ok((seq_name.to_owned(), start - 1, end))
+}
+
+/// Returns a diagnostic message for an error that occurred when reading the given input.
+///
+/// Errors located at a line of the input are written in the `file:line: message` form.
+pub fn diagnostic(input: &str, err: &gte::Error) -> String {
+    let name = if input == STREAM_ARG { STREAM_NAME } else { input };
+    match (err.line(), err.field()) {
+        (Some(line), Some(field)) =>
+            format!("{}:{}: field '{}': {}", name, line, field, err.inner()),
+        (Some(line), None) => format!("{}:{}: {}", name, line, err.inner()),
+        _ => format!("{}: {}", name, err),
+    }
+}
+
+/// Converts an error that occurred when reading the given input into a tool error.
+///
+/// Errors located at a line of the input keep the input name, so that they are reported in the
+/// `file:line: message` form.
+pub fn input_error(input: &str, err: gte::Error) -> Error {
+    match err.line() {
+        Some(_) => Error::Input(diagnostic(input, &err)),
+        None => Error::from(err),
+    }
+}
+
+/// Creates the `--on-error` and `--error-report` arguments, shown from the given display order.
+pub fn error_args<'a, 'b>(display_order: usize) -> [Arg<'a, 'b>; 2] {
+    [Arg::with_name("on_error")
+        .long("--on-error")
+        .value_name("POLICY")
+        .takes_value(true)
+        .possible_values(&["strict", "skip", "collect"])
+        .display_order(display_order)
+        .long_help(
+            "How to handle records that can not be read or written. 'strict' stops at the \
+             first error, 'skip' writes a warning for each error, and 'collect' writes all \
+             errors to the error report. Defaults to 'collect' if '--error-report' is \
+             specified and to 'skip' otherwise."),
+     Arg::with_name("error_report")
+        .long("--error-report")
+        .value_name("FILE")
+        .takes_value(true)
+        .display_order(display_order + 1)
+        .help("Path to output TSV file of rejected records, instead of stderr")]
+}
+
+/// Handler of the errors of records of an input, following the `--on-error` and
+/// `--error-report` arguments.
+///
+/// Readers collect the errors of the records they reject themselves. Errors that occur
+/// afterwards, such as those of writing a record, are collected by the handler, so that the
+/// error report contains both.
+pub struct RecordErrors<'a> {
+    input: &'a str,
+    policy: ErrorPolicy,
+    report_path: Option<&'a str>,
+    rejected: Vec<gte::Error>,
+}
+
+impl<'a> RecordErrors<'a> {
+
+    /// Creates a handler of the errors of the given input from the command-line arguments.
+    ///
+    /// Writing an error report implies the `collect` policy.
+    pub fn from_args<'b>(args: &'a ArgMatches<'b>, input: &'a str) -> ::Result<Self> {
+        let report_path = args.value_of("error_report");
+        let policy = match (args.value_of("on_error"), report_path.is_some()) {
+            (Some("strict"), false) => ErrorPolicy::Strict,
+            (Some("skip"), false) | (None, false) => ErrorPolicy::Skip,
+            (Some("collect"), _) | (None, true) => ErrorPolicy::Collect,
+            (Some("strict"), true) | (Some("skip"), true) =>
+                return Err(Error::Other("error report requires the 'collect' error policy")),
+            _ => return Err(Error::Other("invalid error policy")),
+        };
+        Ok(RecordErrors {
+            input: input,
+            policy: policy,
+            report_path: report_path,
+            rejected: Vec::new(),
+        })
+    }
+
+    /// Returns the error policy to be set on readers.
+    pub fn policy(&self) -> ErrorPolicy {
+        self.policy
+    }
+
+    /// Handles an error that occurred when reading, processing, or writing a record.
+    ///
+    /// Errors of records that can not be used are returned with the `strict` policy, collected
+    /// with the `collect` policy, and written as warnings otherwise. Other errors, such as those
+    /// of the underlying reader, are always returned.
+    pub fn skip(&mut self, err: gte::Error) -> ::Result<()> {
+        if self.policy == ErrorPolicy::Strict || !is_record_error(&err) {
+            return Err(input_error(self.input, err));
+        }
+        if self.policy == ErrorPolicy::Collect {
+            self.rejected.push(err);
+        } else {
+            eprintln!("skipping: {}", diagnostic(self.input, &err));
+        }
+        Ok(())
+    }
+
+    /// Writes the records rejected with the `collect` policy as TSV rows.
+    ///
+    /// The records rejected by the given reader report come first, followed by those collected
+    /// by the handler, whose identifiers are not known. The rows are written to the error
+    /// report path, or to stderr if no path is given.
+    pub fn write_report(&self, report: &ErrorReport) -> ::Result<()> {
+        if self.policy != ErrorPolicy::Collect {
+            return Ok(());
+        }
+        let mut writer = match self.report_path {
+            Some(path) => resolve_writer(path)?,
+            None => Output::Plain(Box::new(io::stderr())),
+        };
+        writeln!(writer, "id\tline\tfield\treason")?;
+        let rejected = report.rejected().iter()
+            .map(|rejected| (rejected.id(), rejected.error()))
+            .chain(self.rejected.iter().map(|err| (None, err)));
+        for (id, err) in rejected {
+            writeln!(writer, "{}\t{}\t{}\t{}",
+                     id.unwrap_or(MISSING),
+                     err.line().map(|line| line.to_string()).unwrap_or_else(|| MISSING.to_owned()),
+                     err.field().unwrap_or(MISSING), err.inner())?;
+        }
+        writer.finish()
+    }
+}
+
+/// Helper function to check whether an error only concerns the record being processed.
+fn is_record_error(err: &gte::Error) -> bool {
+    match *err.inner() {
+        gte::Error::RefFlat(RefFlatError::Io(_))
+            | gte::Error::Gff(GffError::Io(_))
+            | gte::Error::Bed(BedError::Io(_))
+            | gte::Error::Seq(SeqError::Io(_)) => false,
+        gte::Error::RefFlat(_)
+            | gte::Error::Gff(_)
+            | gte::Error::Bed(_)
+            | gte::Error::Model(_)
+            | gte::Error::Saf(_)
+            | gte::Error::Seq(_)
+            | gte::Error::InvalidUtf8 => true,
+        _ => false,
+    }
+}